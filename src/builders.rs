@@ -0,0 +1,404 @@
+use libcnb_data::buildpack::BuildpackTarget;
+use serde::Deserialize;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use toml_edit::{value, ArrayOfTables, DocumentMut, Item, Table};
+
+pub(crate) struct BuilderFile {
+    pub(crate) path: PathBuf,
+    pub(crate) document: DocumentMut,
+}
+
+pub(crate) fn builder_file_path(builder_repository_path: &Path, builder: &str) -> PathBuf {
+    builder_repository_path.join(builder).join("builder.toml")
+}
+
+pub(crate) fn read_builder_file(path: PathBuf) -> Result<BuilderFile, ReadBuilderFileError> {
+    let contents = std::fs::read_to_string(&path)
+        .map_err(|e| ReadBuilderFileError::Reading(path.clone(), e))?;
+    let document = DocumentMut::from_str(&contents)
+        .map_err(|e| ReadBuilderFileError::Parsing(path.clone(), e))?;
+    Ok(BuilderFile { path, document })
+}
+
+#[derive(Debug, thiserror::Error)]
+pub(crate) enum ReadBuilderFileError {
+    #[error("Could not read builder\nPath: {0}\nError: {1}")]
+    Reading(PathBuf, #[source] std::io::Error),
+    #[error("Could not parse builder\nPath: {0}\nError: {1}")]
+    Parsing(PathBuf, #[source] toml_edit::TomlError),
+}
+
+// A shared list of the supported builders (e.g.; `--builders-file builders.toml`), so workflows
+// don't each keep their own `--builders` list. Other keys of each entry (e.g.; the `repository`
+// and `stacks` of the builder) are there for other tools and are ignored.
+//
+// [[builders]]
+// name = "builder-24"
+// repository = "heroku/builder"
+// stacks = ["heroku-24"]
+#[derive(Debug, Deserialize)]
+struct BuildersManifest {
+    builders: Vec<BuildersManifestEntry>,
+}
+
+#[derive(Debug, Deserialize)]
+struct BuildersManifestEntry {
+    name: String,
+}
+
+// Returns the builders listed with `--builders` or, when a builders file is given, those listed
+// in that file.
+pub(crate) fn resolve_builders(
+    builders: &[String],
+    builders_file: Option<&Path>,
+) -> Result<Vec<String>, ReadBuildersFileError> {
+    let Some(builders_file) = builders_file else {
+        return Ok(builders.to_vec());
+    };
+    let contents = std::fs::read_to_string(builders_file)
+        .map_err(|e| ReadBuildersFileError::Reading(builders_file.to_path_buf(), e))?;
+    let manifest = toml::from_str::<BuildersManifest>(&contents)
+        .map_err(|e| ReadBuildersFileError::Parsing(builders_file.to_path_buf(), e))?;
+    Ok(manifest
+        .builders
+        .into_iter()
+        .map(|builder| builder.name)
+        .collect())
+}
+
+#[derive(Debug, thiserror::Error)]
+pub(crate) enum ReadBuildersFileError {
+    #[error("Could not read builders file\nPath: {0}\nError: {1}")]
+    Reading(PathBuf, #[source] std::io::Error),
+    #[error("Could not parse builders file\nPath: {0}\nError: {1}")]
+    Parsing(PathBuf, #[source] toml::de::Error),
+}
+
+// Returns the ids of every buildpack a builder references, either as a `[[buildpacks]]`
+// entry or in an `[[order.group]]`.
+pub(crate) fn read_builder_buildpack_ids(document: &DocumentMut) -> HashSet<String> {
+    let buildpack_ids = document
+        .get("buildpacks")
+        .and_then(Item::as_array_of_tables)
+        .into_iter()
+        .flat_map(ArrayOfTables::iter)
+        .filter_map(|buildpack| buildpack.get("id").and_then(Item::as_str));
+
+    let group_ids = document
+        .get("order")
+        .and_then(Item::as_array_of_tables)
+        .into_iter()
+        .flat_map(ArrayOfTables::iter)
+        .filter_map(|order| order.get("group").and_then(Item::as_array_of_tables))
+        .flat_map(ArrayOfTables::iter)
+        .filter_map(|group| group.get("id").and_then(Item::as_str));
+
+    buildpack_ids
+        .chain(group_ids)
+        .map(ToString::to_string)
+        .collect()
+}
+
+// Returns the targets a builder is built for from its `[[targets]]`. Builders that only declare
+// a `[stack]` predate multi-arch builder images and are `linux/amd64`. Returns `None` if the
+// builder declares neither.
+pub(crate) fn read_builder_targets(document: &DocumentMut) -> Option<Vec<BuildpackTarget>> {
+    let get_string = |table: &toml_edit::Table, key: &str| {
+        table
+            .get(key)
+            .and_then(Item::as_str)
+            .map(ToString::to_string)
+    };
+
+    if let Some(targets) = document.get("targets").and_then(Item::as_array_of_tables) {
+        return Some(
+            targets
+                .iter()
+                .map(|target| BuildpackTarget {
+                    os: get_string(target, "os"),
+                    arch: get_string(target, "arch"),
+                    variant: get_string(target, "variant"),
+                    distros: vec![],
+                })
+                .collect(),
+        );
+    }
+
+    document.get("stack").map(|_| {
+        vec![BuildpackTarget {
+            os: Some("linux".into()),
+            arch: Some("amd64".into()),
+            variant: None,
+            distros: vec![],
+        }]
+    })
+}
+
+// The layouts of builder.toml used by cnb-builder-images:
+// - `V1` declares a `[stack]` and references buildpack images as `docker://` URIs.
+// - `V2` declares `[[targets]]` along with `[build]` and `[run]` images, and references buildpack
+//   images as plain image references.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub(crate) enum BuilderSchema {
+    V1,
+    V2,
+}
+
+pub(crate) fn detect_builder_schema(document: &DocumentMut) -> BuilderSchema {
+    if document
+        .get("targets")
+        .and_then(Item::as_array_of_tables)
+        .is_some()
+    {
+        BuilderSchema::V2
+    } else {
+        BuilderSchema::V1
+    }
+}
+
+const DOCKER_URI_SCHEME: &str = "docker://";
+
+// Writes a buildpack image URI (e.g.; `docker://docker.io/heroku/buildpack-java@sha256:...`) the
+// way the given schema references images. URIs of other kinds (e.g.; `https://`) are left as
+// they are.
+pub(crate) fn format_buildpack_uri(uri: &str, schema: BuilderSchema) -> String {
+    match schema {
+        BuilderSchema::V1 if !uri.contains("://") => format!("{DOCKER_URI_SCHEME}{uri}"),
+        BuilderSchema::V2 => uri
+            .strip_prefix(DOCKER_URI_SCHEME)
+            .unwrap_or(uri)
+            .to_string(),
+        BuilderSchema::V1 => uri.to_string(),
+    }
+}
+
+// Moves a `V1` builder to the `V2` layout: the `[stack]` images become the `[build]` and `[run]`
+// images of a single `linux/amd64` target, and buildpack images lose their `docker://` scheme.
+// Returns `false` if the builder already uses the `V2` layout.
+pub(crate) fn migrate_builder_schema(document: &mut DocumentMut) -> bool {
+    if detect_builder_schema(document) == BuilderSchema::V2 {
+        return false;
+    }
+
+    if let Some(stack) = document.remove("stack") {
+        if let Some(build_image) = stack.get("build-image").and_then(Item::as_str) {
+            let mut build = Table::new();
+            build.insert("image", value(build_image));
+            document.insert("build", Item::Table(build));
+        }
+        if let Some(run_image) = stack.get("run-image").and_then(Item::as_str) {
+            let mut image = Table::new();
+            image.insert("image", value(run_image));
+            if let Some(mirrors) = stack.get("run-image-mirrors") {
+                image.insert("mirrors", mirrors.clone());
+            }
+            let mut images = ArrayOfTables::new();
+            images.push(image);
+            let mut run = Table::new();
+            run.set_implicit(true);
+            run.insert("images", Item::ArrayOfTables(images));
+            document.insert("run", Item::Table(run));
+        }
+    }
+
+    let mut target = Table::new();
+    target.insert("os", value("linux"));
+    target.insert("arch", value("amd64"));
+    let mut targets = ArrayOfTables::new();
+    targets.push(target);
+    document.insert("targets", Item::ArrayOfTables(targets));
+
+    for buildpack in document
+        .get_mut("buildpacks")
+        .and_then(Item::as_array_of_tables_mut)
+        .into_iter()
+        .flat_map(ArrayOfTables::iter_mut)
+    {
+        if let Some(uri) = buildpack.get("uri").and_then(Item::as_str) {
+            let uri = format_buildpack_uri(uri, BuilderSchema::V2);
+            buildpack["uri"] = value(uri);
+        }
+    }
+
+    true
+}
+
+#[cfg(test)]
+mod test {
+    use crate::builders::{
+        detect_builder_schema, format_buildpack_uri, migrate_builder_schema,
+        read_builder_buildpack_ids, read_builder_targets, resolve_builders, BuilderSchema,
+    };
+    use std::collections::HashSet;
+    use std::str::FromStr;
+    use toml_edit::DocumentMut;
+
+    #[test]
+    fn test_read_builder_buildpack_ids() {
+        let document = DocumentMut::from_str(
+            r#"
+[[buildpacks]]
+  id = "heroku/java"
+  uri = "docker://docker.io/heroku/buildpack-java@sha256:abc"
+
+[[buildpacks]]
+  id = "heroku/ruby"
+  uri = "https://cnb-shim.herokuapp.com/v1/heroku/ruby"
+
+[[order]]
+  [[order.group]]
+    id = "heroku/java"
+    version = "0.6.9"
+
+[[order]]
+  [[order.group]]
+    id = "heroku/nodejs"
+    version = "1.0.0"
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            read_builder_buildpack_ids(&document),
+            HashSet::from([
+                "heroku/java".to_string(),
+                "heroku/ruby".to_string(),
+                "heroku/nodejs".to_string()
+            ])
+        );
+    }
+
+    #[test]
+    fn test_read_builder_targets() {
+        let targets = |contents: &str| {
+            read_builder_targets(&DocumentMut::from_str(contents).unwrap()).map(|targets| {
+                targets
+                    .into_iter()
+                    .map(|target| (target.os, target.arch))
+                    .collect::<Vec<_>>()
+            })
+        };
+        let target = |os: &str, arch: &str| (Some(os.to_string()), Some(arch.to_string()));
+
+        assert_eq!(
+            targets(
+                r#"
+[[targets]]
+  os = "linux"
+  arch = "amd64"
+
+[[targets]]
+  os = "linux"
+  arch = "arm64"
+"#
+            ),
+            Some(vec![target("linux", "amd64"), target("linux", "arm64")])
+        );
+        assert_eq!(
+            targets("[stack]\n  id = \"heroku-20\""),
+            Some(vec![target("linux", "amd64")])
+        );
+        assert_eq!(targets("[[order]]"), None);
+    }
+
+    #[test]
+    fn test_resolve_builders() {
+        let dir = tempfile::tempdir().unwrap();
+        let builders_file = dir.path().join("builders.toml");
+        std::fs::write(
+            &builders_file,
+            r#"
+[[builders]]
+name = "builder-22"
+repository = "heroku/builder"
+stacks = ["heroku-22"]
+
+[[builders]]
+name = "builder-24"
+repository = "heroku/builder"
+stacks = ["heroku-24"]
+"#,
+        )
+        .unwrap();
+
+        let builders = vec!["salesforce-functions".to_string()];
+        assert_eq!(resolve_builders(&builders, None).unwrap(), builders);
+        assert_eq!(
+            resolve_builders(&[], Some(&builders_file)).unwrap(),
+            vec!["builder-22".to_string(), "builder-24".to_string()]
+        );
+        assert!(resolve_builders(&[], Some(&dir.path().join("missing.toml"))).is_err());
+    }
+
+    #[test]
+    fn test_format_buildpack_uri() {
+        let uri = "docker://docker.io/heroku/buildpack-java@sha256:abc";
+        let reference = "docker.io/heroku/buildpack-java@sha256:abc";
+        let shim_uri = "https://cnb-shim.herokuapp.com/v1/heroku/scala";
+        assert_eq!(format_buildpack_uri(uri, BuilderSchema::V1), uri);
+        assert_eq!(format_buildpack_uri(reference, BuilderSchema::V1), uri);
+        assert_eq!(format_buildpack_uri(uri, BuilderSchema::V2), reference);
+        assert_eq!(
+            format_buildpack_uri(reference, BuilderSchema::V2),
+            reference
+        );
+        assert_eq!(format_buildpack_uri(shim_uri, BuilderSchema::V1), shim_uri);
+        assert_eq!(format_buildpack_uri(shim_uri, BuilderSchema::V2), shim_uri);
+    }
+
+    #[test]
+    fn test_migrate_builder_schema() {
+        let mut document = DocumentMut::from_str(
+            r#"description = "Ubuntu 22.04 base image with buildpacks for Java and Node.js"
+
+[stack]
+  id = "heroku-22"
+  build-image = "heroku/heroku:22-cnb-build"
+  run-image = "heroku/heroku:22-cnb"
+  run-image-mirrors = ["public.ecr.aws/heroku/heroku:22-cnb"]
+
+[[buildpacks]]
+  id = "heroku/java"
+  uri = "docker://docker.io/heroku/buildpack-java@sha256:abc"
+
+[[order]]
+  [[order.group]]
+    id = "heroku/java"
+    version = "0.6.9"
+"#,
+        )
+        .unwrap();
+        assert_eq!(detect_builder_schema(&document), BuilderSchema::V1);
+
+        assert!(migrate_builder_schema(&mut document));
+        assert_eq!(detect_builder_schema(&document), BuilderSchema::V2);
+        assert_eq!(
+            document.to_string(),
+            r#"description = "Ubuntu 22.04 base image with buildpacks for Java and Node.js"
+
+[[buildpacks]]
+  id = "heroku/java"
+  uri = "docker.io/heroku/buildpack-java@sha256:abc"
+
+[[order]]
+  [[order.group]]
+    id = "heroku/java"
+    version = "0.6.9"
+
+[build]
+image = "heroku/heroku:22-cnb-build"
+
+[[run.images]]
+image = "heroku/heroku:22-cnb"
+mirrors = ["public.ecr.aws/heroku/heroku:22-cnb"]
+
+[[targets]]
+os = "linux"
+arch = "amd64"
+"#
+        );
+        assert!(!migrate_builder_schema(&mut document));
+    }
+}
@@ -1,6 +1,12 @@
+use globset::{Glob, GlobMatcher};
+use lazy_static::lazy_static;
 use libcnb_common::toml_file::{read_toml_file, TomlFileError};
-use libcnb_data::buildpack::BuildpackDescriptor;
+use libcnb_data::buildpack::{BuildpackDescriptor, BuildpackTarget, Distro};
+use libcnb_data::generic::GenericMetadata;
 use libcnb_package::find_buildpack_dirs;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 use std::process::{Command, ExitStatus};
 
@@ -28,6 +34,217 @@ pub(crate) fn calculate_digest(digest_url: &str) -> Result<String, CalculateDige
     }
 }
 
+#[derive(Debug, thiserror::Error)]
+pub(crate) enum VerifyManifestError {
+    #[error("Failed to execute crane manifest {0}\nError: {1}")]
+    CommandFailure(String, #[source] std::io::Error),
+    #[error(
+        "No manifest found for {0}, crane manifest exited with a non-zero status\nStatus: {1}"
+    )]
+    ExitStatus(String, ExitStatus),
+}
+
+pub(crate) fn verify_manifest_exists(image_url: &str) -> Result<(), VerifyManifestError> {
+    let output = Command::new("crane")
+        .args(["manifest", image_url])
+        .output()
+        .map_err(|e| VerifyManifestError::CommandFailure(image_url.to_owned(), e))?;
+
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(VerifyManifestError::ExitStatus(
+            image_url.to_owned(),
+            output.status,
+        ))
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub(crate) enum CopyImageError {
+    #[error("Failed to execute crane copy {0} {1}\nError: {2}")]
+    CommandFailure(String, String, #[source] std::io::Error),
+    #[error("Command crane copy {0} {1} exited with a non-zero status\nStatus: {2}")]
+    ExitStatus(String, String, ExitStatus),
+}
+
+// Copies an image, or a manifest list along with every image it references, keeping its digest.
+pub(crate) fn copy_image(source_url: &str, destination_url: &str) -> Result<(), CopyImageError> {
+    let output = Command::new("crane")
+        .args(["copy", source_url, destination_url])
+        .output()
+        .map_err(|e| {
+            CopyImageError::CommandFailure(source_url.to_owned(), destination_url.to_owned(), e)
+        })?;
+
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(CopyImageError::ExitStatus(
+            source_url.to_owned(),
+            destination_url.to_owned(),
+            output.status,
+        ))
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub(crate) enum ListRepositoriesError {
+    #[error("Failed to execute crane catalog {0}\nError: {1}")]
+    CommandFailure(String, #[source] std::io::Error),
+    #[error("Command crane catalog {0} exited with a non-zero status\nStatus: {1}")]
+    ExitStatus(String, ExitStatus),
+}
+
+// Lists the repositories of a registry (e.g.; `public.ecr.aws`) by name, without the registry.
+// Registries that don't support the catalog API (e.g.; Docker Hub) fail with an error.
+pub(crate) fn list_repositories(registry: &str) -> Result<Vec<String>, ListRepositoriesError> {
+    let output = Command::new("crane")
+        .args(["catalog", registry])
+        .output()
+        .map_err(|e| ListRepositoriesError::CommandFailure(registry.to_owned(), e))?;
+
+    if output.status.success() {
+        Ok(String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .map(ToString::to_string)
+            .collect())
+    } else {
+        Err(ListRepositoriesError::ExitStatus(
+            registry.to_owned(),
+            output.status,
+        ))
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub(crate) enum ListImageTagsError {
+    #[error("Failed to execute crane ls {0}\nError: {1}")]
+    CommandFailure(String, #[source] std::io::Error),
+    #[error("Command crane ls {0} exited with a non-zero status\nStatus: {1}")]
+    ExitStatus(String, ExitStatus),
+}
+
+pub(crate) fn list_image_tags(repository: &str) -> Result<Vec<String>, ListImageTagsError> {
+    let output = Command::new("crane")
+        .args(["ls", repository])
+        .output()
+        .map_err(|e| ListImageTagsError::CommandFailure(repository.to_owned(), e))?;
+
+    if output.status.success() {
+        Ok(String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .map(ToString::to_string)
+            .collect())
+    } else {
+        Err(ListImageTagsError::ExitStatus(
+            repository.to_owned(),
+            output.status,
+        ))
+    }
+}
+
+// The same endpoint the release workflow checks before publishing to the CNB registry.
+const CNB_REGISTRY_API_URL: &str = "https://registry.buildpacks.io/api/v1/buildpacks";
+
+#[derive(Debug, thiserror::Error)]
+pub(crate) enum CheckCnbRegistryError {
+    #[error("Failed to execute curl {0}\nError: {1}")]
+    CommandFailure(String, #[source] std::io::Error),
+    #[error("Command curl {0} exited with a non-zero status\nStatus: {1}")]
+    ExitStatus(String, ExitStatus),
+    #[error("Unexpected response from the CNB registry for {0}\nStatus code: {1}")]
+    UnexpectedStatusCode(String, String),
+}
+
+// Whether the given version of a buildpack has been added to the CNB registry index. Anything but
+// a found or not found response (e.g.; rate limiting) is an error instead of a guess.
+pub(crate) fn is_registered_in_cnb_registry(
+    buildpack_id: &str,
+    version: &str,
+) -> Result<bool, CheckCnbRegistryError> {
+    let url = format!("{CNB_REGISTRY_API_URL}/{buildpack_id}/{version}");
+    let output = Command::new("curl")
+        .args([
+            "--head",
+            "--silent",
+            "--show-error",
+            "--retry",
+            "1",
+            "--retry-all-errors",
+            "--connect-timeout",
+            "10",
+            "--max-time",
+            "60",
+            "--output",
+            "/dev/null",
+            "--write-out",
+            "%{http_code}",
+            &url,
+        ])
+        .output()
+        .map_err(|e| CheckCnbRegistryError::CommandFailure(url.clone(), e))?;
+
+    if !output.status.success() {
+        return Err(CheckCnbRegistryError::ExitStatus(url, output.status));
+    }
+
+    match String::from_utf8_lossy(&output.stdout).trim() {
+        "200" => Ok(true),
+        "404" => Ok(false),
+        status_code => Err(CheckCnbRegistryError::UnexpectedStatusCode(
+            url,
+            status_code.to_string(),
+        )),
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub(crate) enum ReadImageLabelsError {
+    #[error("Failed to execute crane config {0}\nError: {1}")]
+    CommandFailure(String, #[source] std::io::Error),
+    #[error("Command crane config {0} exited with a non-zero status\nStatus: {1}")]
+    ExitStatus(String, ExitStatus),
+    #[error("Could not parse image config for {0}\nError: {1}")]
+    ParsingConfig(String, #[source] serde_json::Error),
+}
+
+#[derive(Debug, Deserialize)]
+struct ImageConfig {
+    config: Option<ImageConfigDetails>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ImageConfigDetails {
+    #[serde(rename = "Labels")]
+    labels: Option<HashMap<String, String>>,
+}
+
+pub(crate) fn read_image_labels(
+    image_url: &str,
+) -> Result<HashMap<String, String>, ReadImageLabelsError> {
+    let output = Command::new("crane")
+        .args(["config", image_url])
+        .output()
+        .map_err(|e| ReadImageLabelsError::CommandFailure(image_url.to_owned(), e))?;
+
+    if !output.status.success() {
+        return Err(ReadImageLabelsError::ExitStatus(
+            image_url.to_owned(),
+            output.status,
+        ));
+    }
+
+    serde_json::from_slice::<ImageConfig>(&output.stdout)
+        .map(|image_config| {
+            image_config
+                .config
+                .and_then(|config| config.labels)
+                .unwrap_or_default()
+        })
+        .map_err(|e| ReadImageLabelsError::ParsingConfig(image_url.to_owned(), e))
+}
+
 pub(crate) fn read_image_repository_metadata(
     buildpack_descriptor: &BuildpackDescriptor,
 ) -> Option<String> {
@@ -45,38 +262,449 @@ pub(crate) fn read_image_repository_metadata(
         .map(|value| value.to_string())
 }
 
+// Registries with a legacy layout host each architecture in its own repository. Those targets
+// override the image repository, keyed by OCI target (e.g.; `linux/arm64`):
+//
+// [metadata.release.image.targets]
+// "linux/arm64" = "docker.io/heroku/buildpack-example-arm64"
+pub(crate) fn read_image_target_repositories_metadata(
+    buildpack_descriptor: &BuildpackDescriptor,
+) -> HashMap<String, String> {
+    let metadata = match buildpack_descriptor {
+        BuildpackDescriptor::Component(descriptor) => &descriptor.metadata,
+        BuildpackDescriptor::Composite(descriptor) => &descriptor.metadata,
+    };
+
+    #[allow(clippy::redundant_closure_for_method_calls)]
+    metadata
+        .as_ref()
+        .and_then(|metadata| metadata.get("release").and_then(|value| value.as_table()))
+        .and_then(|release| release.get("image").and_then(|value| value.as_table()))
+        .and_then(|image| image.get("targets").and_then(|value| value.as_table()))
+        .map(|targets| {
+            targets
+                .iter()
+                .filter_map(|(target, repository)| {
+                    Some((target.clone(), repository.as_str()?.to_string()))
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+// Repositories that published images are copied to, in addition to the image repository:
+//
+// [metadata.release.image]
+// mirrors = ["ghcr.io/heroku/buildpack-example"]
+pub(crate) fn read_image_mirrors_metadata(
+    buildpack_descriptor: &BuildpackDescriptor,
+) -> Vec<String> {
+    let metadata = match buildpack_descriptor {
+        BuildpackDescriptor::Component(descriptor) => &descriptor.metadata,
+        BuildpackDescriptor::Composite(descriptor) => &descriptor.metadata,
+    };
+
+    #[allow(clippy::redundant_closure_for_method_calls)]
+    metadata
+        .as_ref()
+        .and_then(|metadata| metadata.get("release").and_then(|value| value.as_table()))
+        .and_then(|release| release.get("image").and_then(|value| value.as_table()))
+        .and_then(|image| image.get("mirrors").and_then(|value| value.as_array()))
+        .map(|mirrors| {
+            mirrors
+                .iter()
+                .filter_map(|mirror| mirror.as_str().map(ToString::to_string))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+// Checks that `metadata.release.image.repository` (and any per-target or mirror repository) is a
+// repository that images can be pushed to: a registry host followed by a lowercase path,
+// without any tag or digest.
+pub(crate) fn validate_image_repository_metadata(
+    buildpack_dir: &Path,
+    buildpack_descriptor: &BuildpackDescriptor,
+) -> Result<(), InvalidImageRepositoryError> {
+    // sorted so the same invalid target is reported on every run
+    let mut target_repositories = read_image_target_repositories_metadata(buildpack_descriptor)
+        .into_iter()
+        .collect::<Vec<_>>();
+    target_repositories.sort();
+
+    read_image_repository_metadata(buildpack_descriptor)
+        .map(|repository| ("metadata.release.image.repository".to_string(), repository))
+        .into_iter()
+        .chain(target_repositories.into_iter().map(|(target, repository)| {
+            (
+                format!("metadata.release.image.targets.\"{target}\""),
+                repository,
+            )
+        }))
+        .chain(
+            read_image_mirrors_metadata(buildpack_descriptor)
+                .into_iter()
+                .enumerate()
+                .map(|(index, repository)| {
+                    (
+                        format!("metadata.release.image.mirrors[{index}]"),
+                        repository,
+                    )
+                }),
+        )
+        .try_for_each(|(key, repository)| {
+            validate_image_repository(&repository).map_err(|reason| {
+                InvalidImageRepositoryError(descriptor_path(buildpack_dir), key, repository, reason)
+            })
+        })
+}
+
+fn validate_image_repository(repository: &str) -> Result<(), &'static str> {
+    lazy_static! {
+        static ref HOST: Regex = Regex::new(
+            r"^(?:localhost|[a-zA-Z0-9-]+(?:\.[a-zA-Z0-9-]+)+|[a-zA-Z0-9-]+:[0-9]+|[a-zA-Z0-9-]+(?:\.[a-zA-Z0-9-]+)+:[0-9]+)$"
+        )
+        .expect("Should be a valid regex");
+        static ref PATH_COMPONENT: Regex =
+            Regex::new(r"^[a-z0-9]+(?:(?:[._]|__|-+)[a-z0-9]+)*$").expect("Should be a valid regex");
+    }
+
+    if repository.contains('@') {
+        return Err("it must not include a digest");
+    }
+
+    let Some((host, path)) = repository.split_once('/') else {
+        return Err("it must include a registry host (e.g.; docker.io/heroku/buildpack-example)");
+    };
+
+    if !HOST.is_match(host) {
+        return Err("it must include a registry host (e.g.; docker.io/heroku/buildpack-example)");
+    }
+
+    if path
+        .rsplit('/')
+        .next()
+        .is_some_and(|name| name.contains(':'))
+    {
+        return Err("it must not include a tag");
+    }
+
+    if path.chars().any(|c| c.is_ascii_uppercase()) {
+        return Err("it must be lowercase");
+    }
+
+    if !path
+        .split('/')
+        .all(|component| PATH_COMPONENT.is_match(component))
+    {
+        return Err("it contains an invalid path component");
+    }
+
+    Ok(())
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("Invalid image repository `{}` in {}, {}\nPath: {}", .2, .1, .3, .0.display())]
+pub(crate) struct InvalidImageRepositoryError(PathBuf, String, String, &'static str);
+
+// Buildpacks keep their changelog in `CHANGELOG.md` next to `buildpack.toml` unless
+// `[metadata.release] changelog` points elsewhere, relative to the buildpack directory
+// (e.g.; a repository-level changelog for repos that ship a single composite buildpack).
+pub(crate) fn read_changelog_metadata(
+    buildpack_descriptor: &BuildpackDescriptor,
+) -> Option<String> {
+    let metadata = match buildpack_descriptor {
+        BuildpackDescriptor::Component(descriptor) => &descriptor.metadata,
+        BuildpackDescriptor::Composite(descriptor) => &descriptor.metadata,
+    };
+
+    #[allow(clippy::redundant_closure_for_method_calls)]
+    metadata
+        .as_ref()
+        .and_then(|metadata| metadata.get("release").and_then(|value| value.as_table()))
+        .and_then(|release| release.get("changelog").and_then(|value| value.as_str()))
+        .map(|value| value.to_string())
+}
+
+pub(crate) fn buildpack_changelog_path(
+    buildpack_dir: &Path,
+    buildpack_descriptor: &BuildpackDescriptor,
+) -> PathBuf {
+    buildpack_dir
+        .join(read_changelog_metadata(buildpack_descriptor).unwrap_or("CHANGELOG.md".to_string()))
+}
+
+// Reads targets from buildpacks while ensuring each buildpack returns at least
+// one target (libcnb assumes a linux/amd64 target by default, even if no
+// targets are defined).
+pub(crate) fn read_buildpack_targets(
+    buildpack_descriptor: &BuildpackDescriptor,
+) -> Vec<BuildpackTarget> {
+    let mut targets = match buildpack_descriptor {
+        BuildpackDescriptor::Component(descriptor) => descriptor.targets.clone(),
+        BuildpackDescriptor::Composite(descriptor) => {
+            read_metadata_targets(descriptor.metadata.clone()).unwrap_or_default()
+        }
+    };
+    if targets.is_empty() {
+        targets.push(BuildpackTarget {
+            os: Some("linux".into()),
+            arch: Some("amd64".into()),
+            variant: None,
+            distros: vec![],
+        });
+    };
+    targets
+}
+
+// Project descriptors for composite buildpacks don't support `[[targets]]`,
+// but this project needs a way to determine what targets to package composite
+// buildpacks for. This function reads `[[targets]]` out of a project
+// descriptor's metadata (which is unrestricted) instead.
+fn read_metadata_targets(md: GenericMetadata) -> Option<Vec<BuildpackTarget>> {
+    let get_toml_string = |table: &toml::Table, key: &str| -> Option<String> {
+        Some(table.get(key)?.as_str()?.to_string())
+    };
+    Some(
+        md?.get("targets")?
+            .as_array()?
+            .iter()
+            .filter_map(|tgt_value| {
+                let tgt_table = tgt_value.as_table()?;
+                Some(BuildpackTarget {
+                    os: get_toml_string(tgt_table, "os"),
+                    arch: get_toml_string(tgt_table, "arch"),
+                    variant: get_toml_string(tgt_table, "variant"),
+                    distros: tgt_table
+                        .get("distros")
+                        .and_then(toml::Value::as_array)
+                        .into_iter()
+                        .flatten()
+                        .filter_map(|distro_value| {
+                            let distro_table = distro_value.as_table()?;
+                            Some(Distro {
+                                name: get_toml_string(distro_table, "name")?,
+                                version: get_toml_string(distro_table, "version")?,
+                            })
+                        })
+                        .collect(),
+                })
+            })
+            .collect(),
+    )
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Serialize)]
+pub(crate) struct BuildpackOwner {
+    pub(crate) team: String,
+    pub(crate) language: Option<String>,
+}
+
+pub(crate) fn read_owner_metadata(
+    buildpack_descriptor: &BuildpackDescriptor,
+) -> Option<BuildpackOwner> {
+    let metadata = match buildpack_descriptor {
+        BuildpackDescriptor::Component(descriptor) => &descriptor.metadata,
+        BuildpackDescriptor::Composite(descriptor) => &descriptor.metadata,
+    };
+
+    let owner = metadata
+        .as_ref()
+        .and_then(|metadata| metadata.get("release").and_then(|value| value.as_table()))
+        .and_then(|release| release.get("owner").and_then(|value| value.as_table()))?;
+
+    Some(BuildpackOwner {
+        team: owner
+            .get("team")
+            .and_then(|value| value.as_str())?
+            .to_string(),
+        language: owner
+            .get("language")
+            .and_then(|value| value.as_str())
+            .map(ToString::to_string),
+    })
+}
+
+// Buildpacks can also be left out with `exclude` globs, which are matched against both the
+// buildpack id (e.g.; `heroku/nodejs-*`) and the buildpack directory relative to `starting_dir`
+// (e.g.; `test/fixtures/**`).
 pub(crate) fn find_releasable_buildpacks(
     starting_dir: &Path,
+    exclude: &[Glob],
 ) -> Result<Vec<PathBuf>, FindReleasableBuildpacksError> {
-    find_buildpack_dirs(starting_dir)
-        .map(|results| {
-            results
-                .into_iter()
-                .filter(|dir| dir.join("CHANGELOG.md").exists())
-                .collect()
+    let exclude = exclude
+        .iter()
+        .map(Glob::compile_matcher)
+        .collect::<Vec<_>>();
+
+    find_buildpacks_with_changelogs(starting_dir).map(|buildpacks| {
+        buildpacks
+            .into_iter()
+            .filter(|(_, descriptor)| !is_release_skipped(descriptor))
+            .filter(|(dir, descriptor)| !is_excluded(starting_dir, dir, descriptor, &exclude))
+            .map(|(dir, _)| dir)
+            .collect()
+    })
+}
+
+// Buildpacks that have a changelog but opt out of releases (e.g.; test fixtures or a buildpack
+// that's still in progress) with `[metadata.release] skip = true` or `publish = false`.
+pub(crate) fn find_skipped_buildpacks(
+    starting_dir: &Path,
+) -> Result<Vec<PathBuf>, FindReleasableBuildpacksError> {
+    find_buildpacks_with_changelogs(starting_dir).map(|buildpacks| {
+        buildpacks
+            .into_iter()
+            .filter(|(_, descriptor)| is_release_skipped(descriptor))
+            .map(|(dir, _)| dir)
+            .collect()
+    })
+}
+
+// Descriptors are read as plain TOML rather than with `read_buildpack_descriptor`, which rejects
+// any version that isn't `X.Y.Z` (e.g.; a pre-release like `2.0.0-rc.1`).
+fn find_buildpacks_with_changelogs(
+    starting_dir: &Path,
+) -> Result<Vec<(PathBuf, toml::Table)>, FindReleasableBuildpacksError> {
+    let dirs = find_buildpack_dirs(starting_dir)
+        .and_then(|mut dirs| {
+            dirs.extend(find_extension_dirs(starting_dir)?);
+            Ok(dirs)
         })
-        .map_err(|e| FindReleasableBuildpacksError(starting_dir.to_path_buf(), e))
+        .map_err(|e| {
+            FindReleasableBuildpacksError::FindingBuildpacks(starting_dir.to_path_buf(), e)
+        })?;
+
+    let mut buildpacks = vec![];
+    for dir in dirs {
+        let descriptor_path = descriptor_path(&dir);
+        let descriptor = read_toml_file::<toml::Table>(&descriptor_path).map_err(|e| {
+            FindReleasableBuildpacksError::ReadingDescriptor(ReadBuildpackDescriptorError(
+                descriptor_path,
+                e,
+            ))
+        })?;
+        if dir.join("CHANGELOG.md").exists()
+            || read_release_table_value(&descriptor, "changelog").is_some()
+        {
+            buildpacks.push((dir, descriptor));
+        }
+    }
+    Ok(buildpacks)
+}
+
+fn find_extension_dirs(starting_dir: &Path) -> Result<Vec<PathBuf>, ignore::Error> {
+    ignore::Walk::new(starting_dir)
+        .filter_map(|entry| match entry {
+            Ok(entry) if is_extension_dir(entry.path()) => Some(Ok(entry.into_path())),
+            Ok(_) => None,
+            Err(e) => Some(Err(e)),
+        })
+        .collect()
 }
+
+fn is_excluded(
+    starting_dir: &Path,
+    dir: &Path,
+    descriptor: &toml::Table,
+    exclude: &[GlobMatcher],
+) -> bool {
+    if exclude.is_empty() {
+        return false;
+    }
+    let relative_dir = dir.strip_prefix(starting_dir).unwrap_or(dir);
+    // extensions keep their id in `[extension]` rather than `[buildpack]`
+    let buildpack_id = descriptor
+        .get("buildpack")
+        .or_else(|| descriptor.get("extension"))
+        .and_then(|buildpack| buildpack.get("id"))
+        .and_then(toml::Value::as_str);
+    exclude.iter().any(|matcher| {
+        matcher.is_match(relative_dir)
+            || buildpack_id.is_some_and(|buildpack_id| matcher.is_match(buildpack_id))
+    })
+}
+
+fn is_release_skipped(descriptor: &toml::Table) -> bool {
+    let read_bool =
+        |key: &str| read_release_table_value(descriptor, key).and_then(toml::Value::as_bool);
+
+    read_bool("skip") == Some(true) || read_bool("publish") == Some(false)
+}
+
+fn read_release_table_value<'a>(descriptor: &'a toml::Table, key: &str) -> Option<&'a toml::Value> {
+    descriptor
+        .get("metadata")
+        .and_then(|metadata| metadata.get("release"))
+        .and_then(|release| release.get(key))
+}
+
 #[derive(Debug, thiserror::Error)]
-#[error("I/O error while finding buildpacks\nPath: {0}\nError: {1}")]
-pub(crate) struct FindReleasableBuildpacksError(PathBuf, ignore::Error);
+pub(crate) enum FindReleasableBuildpacksError {
+    #[error("I/O error while finding buildpacks\nPath: {0}\nError: {1}")]
+    FindingBuildpacks(PathBuf, ignore::Error),
+    #[error(transparent)]
+    ReadingDescriptor(ReadBuildpackDescriptorError),
+}
+
+// Image extensions are released alongside buildpacks. Their `extension.toml` has the same
+// shape as a component buildpack's `buildpack.toml`, with an `[extension]` table in place of
+// `[buildpack]`, so it's read as one.
+pub(crate) fn is_extension_dir(dir: &Path) -> bool {
+    !dir.join("buildpack.toml").exists() && dir.join("extension.toml").exists()
+}
+
+pub(crate) fn descriptor_path(dir: &Path) -> PathBuf {
+    if is_extension_dir(dir) {
+        dir.join("extension.toml")
+    } else {
+        dir.join("buildpack.toml")
+    }
+}
 
 pub(crate) fn read_buildpack_descriptor(
     dir: &Path,
 ) -> Result<BuildpackDescriptor, ReadBuildpackDescriptorError> {
+    if is_extension_dir(dir) {
+        return read_extension_descriptor(dir);
+    }
     let buildpack_path = dir.join("buildpack.toml");
     read_toml_file::<BuildpackDescriptor>(&buildpack_path)
         .map_err(|e| ReadBuildpackDescriptorError(buildpack_path, e))
 }
 
+fn read_extension_descriptor(
+    dir: &Path,
+) -> Result<BuildpackDescriptor, ReadBuildpackDescriptorError> {
+    let extension_path = dir.join("extension.toml");
+    let mut document = read_toml_file::<toml::Table>(&extension_path)
+        .map_err(|e| ReadBuildpackDescriptorError(extension_path.clone(), e))?;
+    if let Some(extension) = document.remove("extension") {
+        document.insert("buildpack".to_string(), extension);
+    }
+    toml::Value::Table(document)
+        .try_into::<BuildpackDescriptor>()
+        .map_err(|e| {
+            ReadBuildpackDescriptorError(extension_path, TomlFileError::TomlDeserializationError(e))
+        })
+}
+
 #[derive(Debug, thiserror::Error)]
 #[error("Failed to read buildpack descriptor\nPath: {0}\nError: {1}")]
 pub(crate) struct ReadBuildpackDescriptorError(PathBuf, #[source] TomlFileError);
 
 #[cfg(test)]
 mod test {
-    use crate::buildpacks::read_image_repository_metadata;
+    use crate::buildpacks::{
+        buildpack_changelog_path, descriptor_path, find_releasable_buildpacks,
+        find_skipped_buildpacks, is_extension_dir, read_buildpack_descriptor,
+        read_image_mirrors_metadata, read_image_repository_metadata, read_owner_metadata,
+        validate_image_repository, validate_image_repository_metadata, BuildpackOwner,
+    };
+    use globset::Glob;
     use libcnb_data::buildpack::BuildpackDescriptor;
+    use std::path::Path;
+    use tempfile::tempdir;
 
     #[test]
     fn test_read_image_repository_metadata() {
@@ -118,4 +746,331 @@ version = "0.0.1"
         let buildpack_descriptor = toml::from_str::<BuildpackDescriptor>(data).unwrap();
         assert_eq!(read_image_repository_metadata(&buildpack_descriptor), None);
     }
+
+    #[test]
+    fn test_read_image_mirrors_metadata() {
+        let data = r#"
+api = "0.9"
+
+[buildpack]
+id = "foo/bar"
+version = "0.0.1"
+
+[metadata.release.image]
+repository = "docker.io/heroku/buildpack-bar"
+mirrors = ["ghcr.io/heroku/buildpack-bar", "public.ecr.aws/heroku/buildpack-bar"]
+"#;
+
+        let buildpack_descriptor = toml::from_str::<BuildpackDescriptor>(data).unwrap();
+        assert_eq!(
+            read_image_mirrors_metadata(&buildpack_descriptor),
+            vec![
+                "ghcr.io/heroku/buildpack-bar".to_string(),
+                "public.ecr.aws/heroku/buildpack-bar".to_string()
+            ]
+        );
+    }
+
+    #[test]
+    fn test_read_owner_metadata() {
+        let data = r#"
+api = "0.9"
+
+[buildpack]
+id = "foo/bar"
+version = "0.0.1"
+
+[metadata.release.owner]
+team = "languages"
+language = "nodejs"
+"#;
+
+        let buildpack_descriptor = toml::from_str::<BuildpackDescriptor>(data).unwrap();
+        assert_eq!(
+            read_owner_metadata(&buildpack_descriptor),
+            Some(BuildpackOwner {
+                team: "languages".to_string(),
+                language: Some("nodejs".to_string())
+            })
+        );
+    }
+
+    #[test]
+    fn test_read_owner_metadata_without_team() {
+        let data = r#"
+api = "0.9"
+
+[buildpack]
+id = "foo/bar"
+version = "0.0.1"
+
+[metadata.release.owner]
+language = "nodejs"
+"#;
+
+        let buildpack_descriptor = toml::from_str::<BuildpackDescriptor>(data).unwrap();
+        assert_eq!(read_owner_metadata(&buildpack_descriptor), None);
+    }
+
+    #[test]
+    fn test_buildpack_changelog_path() {
+        let data = r#"
+api = "0.9"
+
+[buildpack]
+id = "foo/bar"
+version = "0.0.1"
+
+[metadata.release]
+changelog = "../../CHANGELOG.md"
+"#;
+
+        let buildpack_descriptor = toml::from_str::<BuildpackDescriptor>(data).unwrap();
+        assert_eq!(
+            buildpack_changelog_path(Path::new("/repo/buildpacks/bar"), &buildpack_descriptor),
+            Path::new("/repo/buildpacks/bar/../../CHANGELOG.md")
+        );
+    }
+
+    #[test]
+    fn test_find_releasable_buildpacks_with_changelog_metadata() {
+        let repo_dir = tempdir().unwrap();
+        let buildpack_dir = repo_dir.path().join("buildpacks").join("bar");
+        std::fs::create_dir_all(&buildpack_dir).unwrap();
+        std::fs::write(
+            buildpack_dir.join("buildpack.toml"),
+            r#"
+api = "0.9"
+
+[buildpack]
+id = "foo/bar"
+version = "0.0.1"
+
+[[order]]
+[[order.group]]
+id = "foo/baz"
+version = "0.0.1"
+
+[metadata.release]
+changelog = "../../CHANGELOG.md"
+"#,
+        )
+        .unwrap();
+        std::fs::write(repo_dir.path().join("CHANGELOG.md"), "## [Unreleased]\n").unwrap();
+
+        assert_eq!(
+            find_releasable_buildpacks(repo_dir.path(), &[]).unwrap(),
+            vec![buildpack_dir]
+        );
+    }
+
+    #[test]
+    fn test_find_releasable_buildpacks_with_extensions() {
+        let repo_dir = tempdir().unwrap();
+        let extension_dir = repo_dir.path().join("extensions").join("apt");
+        std::fs::create_dir_all(&extension_dir).unwrap();
+        std::fs::write(extension_dir.join("CHANGELOG.md"), "## [Unreleased]\n").unwrap();
+        std::fs::write(
+            extension_dir.join("extension.toml"),
+            r#"
+api = "0.10"
+
+[extension]
+id = "heroku/apt"
+version = "0.0.1"
+
+[metadata.release]
+image = { repository = "docker.io/heroku/extension-apt" }
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            find_releasable_buildpacks(repo_dir.path(), &[]).unwrap(),
+            vec![extension_dir.clone()]
+        );
+        assert!(is_extension_dir(&extension_dir));
+        assert_eq!(
+            descriptor_path(&extension_dir),
+            extension_dir.join("extension.toml")
+        );
+
+        let descriptor = read_buildpack_descriptor(&extension_dir).unwrap();
+        assert_eq!(descriptor.buildpack().id.to_string(), "heroku/apt");
+        assert_eq!(
+            read_image_repository_metadata(&descriptor),
+            Some("docker.io/heroku/extension-apt".to_string())
+        );
+    }
+
+    #[test]
+    fn test_find_releasable_buildpacks_excludes_skipped_buildpacks() {
+        let repo_dir = tempfile::tempdir().unwrap();
+        let released_dir = repo_dir.path().join("buildpacks/released");
+        let skipped_dir = repo_dir.path().join("buildpacks/skipped");
+        let unpublished_dir = repo_dir.path().join("buildpacks/unpublished");
+
+        for (dir, metadata) in [
+            (&released_dir, "skip = false\npublish = true"),
+            (&skipped_dir, "skip = true"),
+            (&unpublished_dir, "publish = false"),
+        ] {
+            std::fs::create_dir_all(dir).unwrap();
+            std::fs::write(dir.join("CHANGELOG.md"), "## [Unreleased]\n").unwrap();
+            std::fs::write(
+                dir.join("buildpack.toml"),
+                format!(
+                    r#"
+api = "0.9"
+
+[buildpack]
+id = "foo/bar"
+version = "0.0.1"
+
+[metadata.release]
+{metadata}
+"#
+                ),
+            )
+            .unwrap();
+        }
+
+        assert_eq!(
+            find_releasable_buildpacks(repo_dir.path(), &[]).unwrap(),
+            vec![released_dir]
+        );
+        assert_eq!(
+            find_skipped_buildpacks(repo_dir.path()).unwrap(),
+            vec![skipped_dir, unpublished_dir]
+        );
+    }
+
+    #[test]
+    fn test_find_releasable_buildpacks_with_exclude() {
+        let repo_dir = tempfile::tempdir().unwrap();
+
+        for (dir, id) in [
+            ("buildpacks/nodejs-engine", "heroku/nodejs-engine"),
+            ("buildpacks/nodejs-npm", "heroku/nodejs-npm"),
+            ("test/fixtures/example", "heroku/example"),
+            ("buildpacks/ruby", "heroku/ruby"),
+        ] {
+            let dir = repo_dir.path().join(dir);
+            std::fs::create_dir_all(&dir).unwrap();
+            std::fs::write(dir.join("CHANGELOG.md"), "## [Unreleased]\n").unwrap();
+            std::fs::write(
+                dir.join("buildpack.toml"),
+                format!("api = \"0.9\"\n\n[buildpack]\nid = \"{id}\"\nversion = \"0.0.1\"\n"),
+            )
+            .unwrap();
+        }
+
+        let exclude = ["heroku/nodejs-*", "test/**"].map(|glob| Glob::new(glob).unwrap());
+        assert_eq!(
+            find_releasable_buildpacks(repo_dir.path(), &exclude).unwrap(),
+            vec![repo_dir.path().join("buildpacks/ruby")]
+        );
+    }
+
+    #[test]
+    fn test_find_releasable_buildpacks_with_pre_release_versions() {
+        let repo_dir = tempfile::tempdir().unwrap();
+        let released_dir = repo_dir.path().join("buildpacks/released");
+        let skipped_dir = repo_dir.path().join("buildpacks/skipped");
+        let excluded_dir = repo_dir.path().join("buildpacks/excluded");
+
+        for (dir, id, metadata) in [
+            (
+                &released_dir,
+                "heroku/released",
+                "changelog = \"../../CHANGELOG.md\"",
+            ),
+            (&skipped_dir, "heroku/skipped", "skip = true"),
+            (&excluded_dir, "heroku/excluded", "skip = false"),
+        ] {
+            std::fs::create_dir_all(dir).unwrap();
+            std::fs::write(
+                dir.join("buildpack.toml"),
+                format!(
+                    r#"
+api = "0.9"
+
+[buildpack]
+id = "{id}"
+version = "2.0.0-rc.1"
+
+[metadata.release]
+{metadata}
+"#
+                ),
+            )
+            .unwrap();
+        }
+        std::fs::write(repo_dir.path().join("CHANGELOG.md"), "## [Unreleased]\n").unwrap();
+        for dir in [&skipped_dir, &excluded_dir] {
+            std::fs::write(dir.join("CHANGELOG.md"), "## [Unreleased]\n").unwrap();
+        }
+
+        let exclude = [Glob::new("heroku/excluded").unwrap()];
+        assert_eq!(
+            find_releasable_buildpacks(repo_dir.path(), &exclude).unwrap(),
+            vec![released_dir]
+        );
+        assert_eq!(
+            find_skipped_buildpacks(repo_dir.path()).unwrap(),
+            vec![skipped_dir]
+        );
+    }
+
+    #[test]
+    fn test_validate_image_repository() {
+        assert!(validate_image_repository("docker.io/heroku/buildpack-java").is_ok());
+        assert!(validate_image_repository("localhost:5000/heroku/buildpack_java").is_ok());
+        assert!(validate_image_repository("public.ecr.aws/heroku/buildpack-java").is_ok());
+        assert_eq!(
+            validate_image_repository("heroku/buildpack-java"),
+            Err("it must include a registry host (e.g.; docker.io/heroku/buildpack-example)")
+        );
+        assert_eq!(
+            validate_image_repository("docker.io/heroku/buildpack-java:1.0.0"),
+            Err("it must not include a tag")
+        );
+        assert_eq!(
+            validate_image_repository("docker.io/heroku/buildpack-java@sha256:abc"),
+            Err("it must not include a digest")
+        );
+        assert_eq!(
+            validate_image_repository("docker.io/heroku/Buildpack-Java"),
+            Err("it must be lowercase")
+        );
+        assert_eq!(
+            validate_image_repository("docker.io/heroku/-buildpack"),
+            Err("it contains an invalid path component")
+        );
+    }
+
+    #[test]
+    fn test_validate_image_repository_metadata_names_the_invalid_key() {
+        let buildpack_descriptor = toml::from_str::<BuildpackDescriptor>(
+            r#"
+api = "0.10"
+
+[buildpack]
+id = "foo/bar"
+version = "0.0.1"
+
+[metadata.release.image]
+repository = "docker.io/heroku/buildpack-bar"
+mirrors = ["ghcr.io/heroku/buildpack-bar", "ghcr.io/heroku/Buildpack-Bar"]
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            validate_image_repository_metadata(Path::new("/repo/bar"), &buildpack_descriptor)
+                .unwrap_err()
+                .to_string(),
+            "Invalid image repository `ghcr.io/heroku/Buildpack-Bar` in metadata.release.image.mirrors[1], it must be lowercase\nPath: /repo/bar/buildpack.toml"
+        );
+    }
 }
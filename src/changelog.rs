@@ -6,40 +6,423 @@ use markdown::{to_mdast, ParseOptions};
 use regex::Regex;
 use semver::Version;
 use std::cmp::Ordering;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fmt::{Display, Formatter};
 use std::num::ParseIntError;
+use std::ops::Range;
 
 #[derive(Debug, Eq, PartialEq)]
 pub(crate) struct Changelog {
+    // Protected regions from before the first release heading (e.g.; a migration note above
+    // `## [Unreleased]`). The rest of the introduction is regenerated at display time.
+    pub(crate) protected_preamble: Option<String>,
     pub(crate) unreleased: Option<String>,
     pub(crate) releases: IndexMap<String, ReleaseEntry>,
 }
 
+impl Changelog {
+    // Adds an entry to the given group (e.g.; `Changed`) of the unreleased section, creating
+    // the group if needed. The entry is appended to the end of the group's existing list using
+    // the same bullet character, so only the added lines show up in a diff.
+    pub(crate) fn add_unreleased_entry(&mut self, group: &str, text: &str) {
+        let bullet = self
+            .unreleased
+            .as_deref()
+            .into_iter()
+            .chain(self.releases.values().map(|release| release.body.as_str()))
+            .find_map(detect_bullet)
+            .unwrap_or('-');
+        let entry = format_entry(bullet, text);
+
+        self.unreleased = Some(match &self.unreleased {
+            Some(unreleased) => append_group_entries(unreleased, group, &entry),
+            None => format!("### {group}\n\n{entry}"),
+        });
+    }
+}
+
+// Appends list entries to the end of a group (e.g.; `### Changed`) of a changelog section,
+// creating the group at the end of the section if it doesn't exist. Lines inside fenced code
+// blocks are never taken for headings or list items, and the entries are indented to the level
+// of the group's top-level list items so they don't end up nested under the last one.
+pub(crate) fn append_group_entries(section: &str, group: &str, entries: &str) -> String {
+    let heading = format!("### {group}");
+    let lines = section.lines().collect::<Vec<_>>();
+    let is_verbatim = find_verbatim_lines(&lines);
+    let is_markdown = |index: &usize| !is_verbatim[*index];
+
+    let Some(group_start) = (0..lines.len())
+        .filter(is_markdown)
+        .find(|index| lines[*index].trim_end() == heading)
+    else {
+        if section.trim().is_empty() {
+            return format!("{heading}\n\n{entries}");
+        }
+        return format!("{}\n\n{heading}\n\n{entries}", section.trim_end());
+    };
+
+    let group_end = (group_start + 1..lines.len())
+        .filter(is_markdown)
+        .find(|index| lines[*index].starts_with('#'))
+        .unwrap_or(lines.len());
+
+    let list_indent = (group_start + 1..group_end)
+        .filter(is_markdown)
+        .filter_map(|index| list_item_indent(lines[index]))
+        .min()
+        .unwrap_or(0);
+    let indent = " ".repeat(list_indent);
+    let entries = entries
+        .lines()
+        .map(|line| {
+            if line.is_empty() {
+                String::new()
+            } else {
+                format!("{indent}{line}")
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    let mut new_lines = lines.iter().map(ToString::to_string).collect::<Vec<_>>();
+    // entries go after the group's list rather than after any protected region that follows it
+    let is_protected = find_protected_lines(&lines);
+    let last_content = (group_start + 1..group_end)
+        .rev()
+        .find(|index| !lines[*index].trim().is_empty() && !is_protected[*index]);
+    if let Some(index) = last_content {
+        new_lines.insert(index + 1, entries);
+        // a code block that isn't part of a list item is kept apart from the list that follows
+        let line = lines[index];
+        if code_fence(line).is_some() && line.len() - line.trim_start().len() < list_indent + 2 {
+            new_lines.insert(index + 1, String::new());
+        }
+    } else {
+        new_lines.insert(group_start + 1, entries);
+        new_lines.insert(group_start + 1, String::new());
+    }
+    new_lines.join("\n")
+}
+
+// Removes change groups without any entries (e.g.; a `### Fixed` heading left behind by a manual
+// edit) and orders the rest as in keep-a-changelog. Groups that aren't in `CHANGE_GROUPS` keep
+// their order after the known ones, and anything before the first group stays first.
+pub(crate) fn normalize_change_groups(section: &str) -> String {
+    let lines = section.lines().collect::<Vec<_>>();
+    let is_verbatim = find_verbatim_lines(&lines);
+
+    let mut preamble: Vec<&str> = vec![];
+    let mut groups: Vec<(&str, Vec<&str>)> = vec![];
+    for (line, is_verbatim) in lines.iter().zip(is_verbatim) {
+        match line.strip_prefix("### ") {
+            Some(group) if !is_verbatim => groups.push((group.trim(), vec![])),
+            _ => match groups.last_mut() {
+                Some((_, group_lines)) => group_lines.push(line),
+                None => preamble.push(line),
+            },
+        }
+    }
+
+    groups.retain(|(_, group_lines)| group_lines.iter().any(|line| !line.trim().is_empty()));
+    groups.sort_by_key(|(group, _)| {
+        CHANGE_GROUPS
+            .iter()
+            .position(|change_group| change_group.eq_ignore_ascii_case(group))
+            .unwrap_or(CHANGE_GROUPS.len())
+    });
+
+    let preamble = trim_blank_lines(&preamble);
+    (!preamble.is_empty())
+        .then_some(preamble)
+        .into_iter()
+        .chain(groups.iter().map(|(group, group_lines)| {
+            format!("### {group}\n\n{}", trim_blank_lines(group_lines))
+        }))
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+fn trim_blank_lines(lines: &[&str]) -> String {
+    let is_blank = |line: &&str| line.trim().is_empty();
+    let start = lines.iter().position(|line| !is_blank(line)).unwrap_or(0);
+    let end = lines
+        .iter()
+        .rposition(|line| !is_blank(line))
+        .map_or(0, |end| end + 1);
+    lines[start..end.max(start)].join("\n")
+}
+
+// Moves every heading down a level (e.g.; `### Added` becomes `#### Added`) so the section can be
+// nested under another heading. Lines inside fenced code blocks or protected regions are left as
+// they are.
+pub(crate) fn demote_headings(section: &str) -> String {
+    let lines = section.lines().collect::<Vec<_>>();
+    let is_verbatim = find_verbatim_lines(&lines);
+    lines
+        .iter()
+        .zip(is_verbatim)
+        .map(|(line, is_verbatim)| {
+            if !is_verbatim && line.starts_with('#') {
+                format!("#{line}")
+            } else {
+                (*line).to_string()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+// Hand-maintained notes (e.g.; a migration guide) can be wrapped in these markers so they're kept
+// byte-for-byte, wherever they are, by every change made to a changelog. Nothing inside them is
+// taken for a release header, change group, or entry. Regions before the first release heading
+// are written back right after the changelog's introduction.
+const PROTECT_START: &str = "<!-- actions:protect-start -->";
+const PROTECT_END: &str = "<!-- actions:protect-end -->";
+
+// Marks the lines that must be kept as they are: those of fenced code blocks and of protected
+// regions, including the fences and markers.
+fn find_verbatim_lines(lines: &[&str]) -> Vec<bool> {
+    find_code_block_lines(lines)
+        .into_iter()
+        .zip(find_protected_lines(lines))
+        .map(|(in_code_block, is_protected)| in_code_block || is_protected)
+        .collect()
+}
+
+// Marks the lines of protected regions, including the markers. An unclosed region runs to the end
+// of the section.
+fn find_protected_lines(lines: &[&str]) -> Vec<bool> {
+    let mut is_open = false;
+    lines
+        .iter()
+        .map(|line| match line.trim() {
+            PROTECT_START if !is_open => {
+                is_open = true;
+                true
+            }
+            PROTECT_END if is_open => {
+                is_open = false;
+                true
+            }
+            _ => is_open,
+        })
+        .collect()
+}
+
+// The protected regions of a section without anything around them, separated by blank lines.
+fn extract_protected_regions(section: &str) -> Option<String> {
+    let lines = section.lines().collect::<Vec<_>>();
+    let mut regions: Vec<String> = vec![];
+    let mut previous_is_protected = false;
+    for (line, is_protected) in lines.iter().zip(find_protected_lines(&lines)) {
+        match regions.last_mut() {
+            Some(region) if is_protected && previous_is_protected => {
+                region.push('\n');
+                region.push_str(line);
+            }
+            _ if is_protected => regions.push((*line).to_string()),
+            _ => {}
+        }
+        previous_is_protected = is_protected;
+    }
+    (!regions.is_empty()).then(|| regions.join("\n\n"))
+}
+
+// The byte ranges of the protected regions of a changelog, so parsed nodes that start inside
+// them can be told apart.
+fn find_protected_ranges(value: &str) -> Vec<Range<usize>> {
+    let lines = value.split_inclusive('\n').collect::<Vec<_>>();
+    let mut ranges: Vec<Range<usize>> = vec![];
+    let mut offset = 0;
+    for (line, is_protected) in lines.iter().zip(find_protected_lines(&lines)) {
+        let line_range = offset..offset + line.len();
+        offset = line_range.end;
+        if !is_protected {
+            continue;
+        }
+        match ranges.last_mut() {
+            Some(range) if range.end == line_range.start => range.end = line_range.end,
+            _ => ranges.push(line_range),
+        }
+    }
+    ranges
+}
+
+fn is_protected_node(node: &Node, protected_ranges: &[Range<usize>]) -> bool {
+    node.position().is_some_and(|position| {
+        protected_ranges
+            .iter()
+            .any(|range| range.contains(&position.start.offset))
+    })
+}
+
+// Marks the lines that are part of a fenced code block (``` or ~~~), including the fences. An
+// unclosed fence runs to the end of the section.
+fn find_code_block_lines(lines: &[&str]) -> Vec<bool> {
+    let mut open_fence: Option<(char, usize)> = None;
+    lines
+        .iter()
+        .map(|line| {
+            let fence = code_fence(line);
+            match (open_fence, fence) {
+                (None, Some(fence)) => {
+                    open_fence = Some(fence);
+                    true
+                }
+                (Some((open_char, open_len)), Some((fence_char, fence_len)))
+                    if fence_char == open_char
+                        && fence_len >= open_len
+                        && line.trim().len() == fence_len =>
+                {
+                    open_fence = None;
+                    true
+                }
+                (open, _) => open.is_some(),
+            }
+        })
+        .collect()
+}
+
+fn code_fence(line: &str) -> Option<(char, usize)> {
+    let trimmed = line.trim_start();
+    let fence_char = trimmed.chars().next().filter(|c| *c == '`' || *c == '~')?;
+    let fence_len = trimmed.chars().take_while(|c| *c == fence_char).count();
+    (fence_len >= 3).then_some((fence_char, fence_len))
+}
+
+fn list_item_indent(line: &str) -> Option<usize> {
+    let trimmed = line.trim_start();
+    let is_list_item = ["- ", "* ", "+ "]
+        .iter()
+        .any(|bullet| trimmed.starts_with(bullet));
+    is_list_item.then_some(line.len() - trimmed.len())
+}
+
+fn detect_bullet(body: &str) -> Option<char> {
+    let lines = body.lines().collect::<Vec<_>>();
+    let is_verbatim = find_verbatim_lines(&lines);
+    lines
+        .iter()
+        .zip(is_verbatim)
+        .filter(|(_, is_verbatim)| !is_verbatim)
+        .map(|(line, _)| line.trim_start())
+        .find_map(|line| match line.chars().next() {
+            Some(bullet @ ('-' | '*' | '+')) if line[1..].starts_with(' ') => Some(bullet),
+            _ => None,
+        })
+}
+
+// Continuation lines are indented to line up with the text of the first line.
+fn format_entry(bullet: char, text: &str) -> String {
+    let text = text.trim();
+    let text = ["- ", "* ", "+ "]
+        .iter()
+        .find_map(|prefix| text.strip_prefix(prefix))
+        .unwrap_or(text);
+    text.lines()
+        .enumerate()
+        .map(|(index, line)| match (index, line.trim()) {
+            (0, line) => format!("{bullet} {line}"),
+            (_, "") => String::new(),
+            (_, line) => format!("  {line}"),
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+// Splits a release body into its change groups (e.g.; `### Added`), each with the list of entries
+// in that group. Continuation lines of an entry are kept with it. Entries that appear before any
+// group heading are collected under `Other`, and protected regions are left out.
+pub(crate) fn changes_by_group(body: &str) -> IndexMap<String, Vec<String>> {
+    let mut groups: IndexMap<String, Vec<String>> = IndexMap::new();
+    let mut current_group = "Other".to_string();
+    let lines = body.lines().collect::<Vec<_>>();
+
+    for (line, is_protected) in lines.iter().zip(find_protected_lines(&lines)) {
+        if is_protected {
+            continue;
+        }
+        if let Some(group) = line.strip_prefix("### ") {
+            current_group = group.trim().to_string();
+            groups.entry(current_group.clone()).or_default();
+        } else if let Some(text) = ["- ", "* ", "+ "]
+            .iter()
+            .find_map(|prefix| line.strip_prefix(prefix))
+        {
+            groups
+                .entry(current_group.clone())
+                .or_default()
+                .push(text.trim().to_string());
+        } else if !line.trim().is_empty() {
+            if let Some(entry) = groups
+                .get_mut(&current_group)
+                .and_then(|entries| entries.last_mut())
+            {
+                entry.push('\n');
+                entry.push_str(line.trim());
+            }
+        }
+    }
+
+    groups
+}
+
+lazy_static! {
+    static ref UNRELEASED_HEADER: Regex =
+        Regex::new(r"(?i)^\[?unreleased]?$").expect("Should be a valid regex");
+    static ref VERSION_HEADER: Regex =
+        Regex::new(r"^\[?(\d+\.\d+\.\d+)]?.*(\d{4})[-/](\d{2})[-/](\d{2})")
+            .expect("Should be a valid regex");
+    static ref VERSION_PREFIX: Regex =
+        Regex::new(r"^\[?\d+\.\d+\.\d+").expect("Should be a valid regex");
+    static ref RELEASED_BY_COMMENT: Regex =
+        Regex::new(r"^<!--\s*released-by\s+(.*?)\s*-->$").expect("Should be a valid regex");
+}
+
+// The change groups from keep-a-changelog, plus `Dependencies` which `prepare-release` can be
+// configured to list updated buildpack dependencies under.
+pub(crate) const CHANGE_GROUPS: [&str; 7] = [
+    "Added",
+    "Changed",
+    "Deprecated",
+    "Removed",
+    "Fixed",
+    "Security",
+    "Dependencies",
+];
+
 impl TryFrom<&str> for Changelog {
     type Error = ChangelogError;
 
     fn try_from(value: &str) -> Result<Self, Self::Error> {
-        lazy_static! {
-            static ref UNRELEASED_HEADER: Regex =
-                Regex::new(r"(?i)^\[?unreleased]?$").expect("Should be a valid regex");
-            static ref VERSION_HEADER: Regex =
-                Regex::new(r"^\[?(\d+\.\d+\.\d+)]?.*(\d{4})[-/](\d{2})[-/](\d{2})")
-                    .expect("Should be a valid regex");
-        }
-
         let changelog_ast =
             to_mdast(value, &ParseOptions::default()).map_err(ChangelogError::Parse)?;
 
         let mut current_header: Option<String> = None;
         let mut headers: Vec<String> = vec![];
         let mut body_nodes_by_header: HashMap<String, Vec<&Node>> = HashMap::new();
+        let mut preamble_end: Option<usize> = None;
 
         if let Node::Root(root) = changelog_ast {
-            for child in &root.children {
-                if let Node::Heading(heading) = child {
+            // the link definitions at the end of the changelog are regenerated at display time
+            // but any others belong to the body of the release they're declared in
+            let (body_children, _) = split_trailing_definitions(&root.children);
+            let protected_ranges = find_protected_ranges(value);
+
+            for child in body_children {
+                let heading = match child {
+                    Node::Heading(heading) if !is_protected_node(child, &protected_ranges) => {
+                        Some(heading)
+                    }
+                    _ => None,
+                };
+                if let Some(heading) = heading {
                     match heading.depth.cmp(&2) {
                         Ordering::Equal => {
+                            if preamble_end.is_none() {
+                                preamble_end =
+                                    child.position().map(|position| position.start.offset);
+                            }
                             headers.push(child.to_string());
                             current_header = Some(child.to_string());
                         }
@@ -54,8 +437,6 @@ impl TryFrom<&str> for Changelog {
                             }
                         }
                     }
-                } else if let Node::Definition(_) = child {
-                    // ignore any defined links, these will be regenerated at display time
                 } else if let Some(header) = &current_header {
                     let body_nodes = body_nodes_by_header.entry(header.clone()).or_default();
                     body_nodes.push(child);
@@ -68,6 +449,7 @@ impl TryFrom<&str> for Changelog {
             for header in headers {
                 let empty_nodes = vec![];
                 let body_nodes = body_nodes_by_header.get(&header).unwrap_or(&empty_nodes);
+                let (released_by, body_nodes) = split_released_by(body_nodes);
 
                 let start = body_nodes
                     .iter()
@@ -86,40 +468,27 @@ impl TryFrom<&str> for Changelog {
                     ""
                 };
 
-                let body = body.trim().to_string();
+                let body = trim_body(body).to_string();
 
                 if UNRELEASED_HEADER.is_match(&header) && !body.is_empty() {
                     unreleased = Some(body);
-                } else if let Some(captures) = VERSION_HEADER.captures(&header) {
-                    let version = captures[1]
-                        .parse::<Version>()
-                        .map_err(ChangelogError::ParseVersion)?;
-                    let year = captures[2]
-                        .parse::<i32>()
-                        .map_err(ChangelogError::ParseReleaseEntryYear)?;
-                    let month = captures[3]
-                        .parse::<u32>()
-                        .map_err(ChangelogError::ParseReleaseEntryMonth)?;
-                    let day = captures[4]
-                        .parse::<u32>()
-                        .map_err(ChangelogError::ParseReleaseEntryDay)?;
-                    let date = match Utc.with_ymd_and_hms(year, month, day, 0, 0, 0) {
-                        LocalResult::None => Err(ChangelogError::InvalidReleaseDate(format!("Could not convert year: {year}, month: {month}, day: {day} into a valid date from {header:?}"))),
-                        LocalResult::Single(value) => Ok(value),
-                        LocalResult::Ambiguous(_, _) => Err(ChangelogError::AmbiguousReleaseDate),
-                    }?;
+                } else if let Some((version, date)) = parse_release_header(&header)? {
                     releases.insert(
                         version.to_string(),
                         ReleaseEntry {
                             version,
                             date,
                             body,
+                            released_by,
                         },
                     );
                 }
             }
 
             Ok(Changelog {
+                protected_preamble: extract_protected_regions(
+                    &value[..preamble_end.unwrap_or(value.len())],
+                ),
                 unreleased,
                 releases,
             })
@@ -129,6 +498,100 @@ impl TryFrom<&str> for Changelog {
     }
 }
 
+// A `<!-- released-by … -->` comment right under a release heading records what produced the
+// release entry. It's kept apart from the body so it isn't included in release notes.
+fn split_released_by<'a, 'b>(body_nodes: &'a [&'b Node]) -> (Option<String>, &'a [&'b Node]) {
+    if let Some((Node::Html(html), rest)) = body_nodes.split_first() {
+        if let Some(captures) = RELEASED_BY_COMMENT.captures(html.value.trim()) {
+            return (Some(captures[1].to_string()), rest);
+        }
+    }
+    (None, body_nodes)
+}
+
+// Returns `None` for headers that aren't release headers (e.g.; `[X.Y.Z] - YYYY-MM-DD`).
+fn parse_release_header(header: &str) -> Result<Option<(Version, DateTime<Utc>)>, ChangelogError> {
+    let Some(captures) = VERSION_HEADER.captures(header) else {
+        return Ok(None);
+    };
+    let version = captures[1]
+        .parse::<Version>()
+        .map_err(ChangelogError::ParseVersion)?;
+    let year = captures[2]
+        .parse::<i32>()
+        .map_err(ChangelogError::ParseReleaseEntryYear)?;
+    let month = captures[3]
+        .parse::<u32>()
+        .map_err(ChangelogError::ParseReleaseEntryMonth)?;
+    let day = captures[4]
+        .parse::<u32>()
+        .map_err(ChangelogError::ParseReleaseEntryDay)?;
+    let date = match Utc.with_ymd_and_hms(year, month, day, 0, 0, 0) {
+        LocalResult::None => Err(ChangelogError::InvalidReleaseDate(format!("Could not convert year: {year}, month: {month}, day: {day} into a valid date from {header:?}"))),
+        LocalResult::Single(value) => Ok(value),
+        LocalResult::Ambiguous(_, _) => Err(ChangelogError::AmbiguousReleaseDate),
+    }?;
+    Ok(Some((version, date)))
+}
+
+// Lists every problem that would keep a changelog from being released correctly, rather than
+// stopping at the first one like parsing does. Change groups are only checked in the unreleased
+// section since earlier releases have already been published as-is.
+pub(crate) fn validate_changelog(value: &str) -> Vec<String> {
+    let children = match to_mdast(value, &ParseOptions::default()) {
+        Ok(Node::Root(root)) => root.children,
+        Ok(_) => return vec![ChangelogError::NoRootNode.to_string()],
+        Err(e) => return vec![ChangelogError::Parse(e).to_string()],
+    };
+    let (body_children, _) = split_trailing_definitions(&children);
+    let protected_ranges = find_protected_ranges(value);
+
+    let mut problems = vec![];
+    let mut has_unreleased = false;
+    let mut in_unreleased = false;
+    let mut versions = HashSet::new();
+
+    for child in body_children {
+        let Node::Heading(heading) = child else {
+            continue;
+        };
+        if is_protected_node(child, &protected_ranges) {
+            continue;
+        }
+        let header = child.to_string();
+        match heading.depth {
+            1 => in_unreleased = false,
+            2 => {
+                in_unreleased = UNRELEASED_HEADER.is_match(&header);
+                has_unreleased |= in_unreleased;
+                match parse_release_header(&header) {
+                    Ok(Some((version, _))) if !versions.insert(version.clone()) => {
+                        problems.push(format!("Duplicate release header for version {version}"));
+                    }
+                    Err(e) => problems.push(format!("Invalid release header `{header}`: {e}")),
+                    Ok(None) if VERSION_PREFIX.is_match(&header) => problems.push(format!(
+                        "Release header `{header}` is missing a date (expected `[X.Y.Z] - YYYY-MM-DD`)"
+                    )),
+                    _ => {}
+                }
+            }
+            3 if in_unreleased && !CHANGE_GROUPS.contains(&header.trim()) => {
+                problems.push(format!(
+                    "Unrecognized change group `{header}` in the unreleased section (expected one of {})",
+                    CHANGE_GROUPS.join(", ")
+                ));
+            }
+            _ => {}
+        }
+    }
+
+    if !has_unreleased {
+        problems.insert(0, "Missing `## [Unreleased]` section".to_string());
+    }
+
+    problems
+}
+
 impl Display for Changelog {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         write!(
@@ -145,8 +608,12 @@ and this project adheres to [Semantic Versioning](https://semver.org/spec/v2.0.0
             .trim()
         )?;
 
+        if let Some(protected_preamble) = &self.protected_preamble {
+            write!(f, "\n\n{protected_preamble}")?;
+        }
+
         if let Some(unreleased) = &self.unreleased {
-            write!(f, "\n\n## [Unreleased]\n\n{}", unreleased.trim())?;
+            write!(f, "\n\n## [Unreleased]\n\n{}", trim_body(unreleased))?;
         } else {
             write!(f, "\n\n## [Unreleased]")?;
         }
@@ -158,8 +625,11 @@ and this project adheres to [Semantic Versioning](https://semver.org/spec/v2.0.0
                 entry.version,
                 entry.date.format("%Y-%m-%d")
             )?;
+            if let Some(released_by) = &entry.released_by {
+                write!(f, "\n\n<!-- released-by {released_by} -->")?;
+            }
             if !entry.body.is_empty() {
-                write!(f, "\n\n{}", entry.body.trim())?;
+                write!(f, "\n\n{}", trim_body(&entry.body))?;
             }
         }
 
@@ -167,11 +637,19 @@ and this project adheres to [Semantic Versioning](https://semver.org/spec/v2.0.0
     }
 }
 
+// Only blank lines are trimmed from the start of a body so that any leading indentation
+// (e.g.; an indented code block) is preserved.
+fn trim_body(body: &str) -> &str {
+    body.trim_end().trim_start_matches(['\n', '\r'])
+}
+
 #[derive(Debug, Clone, Eq, PartialEq)]
 pub(crate) struct ReleaseEntry {
     pub(crate) version: Version,
     pub(crate) date: DateTime<Utc>,
     pub(crate) body: String,
+    // What produced the entry (e.g.; `actions v1.2.3 run 123456`).
+    pub(crate) released_by: Option<String>,
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -194,45 +672,126 @@ pub(crate) enum ChangelogError {
     AmbiguousReleaseDate,
 }
 
+// Returns the link reference definitions found at the end of a changelog (e.g.; the
+// release compare links). These are dropped when a parsed changelog is displayed, so
+// callers that don't regenerate them with `generate_release_declarations` can use this
+// to carry them over.
+pub(crate) fn read_trailing_link_definitions(value: &str) -> Result<String, ChangelogError> {
+    let changelog_ast = to_mdast(value, &ParseOptions::default()).map_err(ChangelogError::Parse)?;
+
+    let Node::Root(root) = changelog_ast else {
+        return Err(ChangelogError::NoRootNode);
+    };
+
+    let (_, definitions) = split_trailing_definitions(&root.children);
+
+    Ok(definitions
+        .iter()
+        .filter_map(|node| {
+            node.position()
+                .map(|position| &value[position.start.offset..position.end.offset])
+        })
+        .collect::<Vec<_>>()
+        .join("\n"))
+}
+
+fn split_trailing_definitions(nodes: &[Node]) -> (&[Node], &[Node]) {
+    let trailing_definitions = nodes
+        .iter()
+        .rev()
+        .take_while(|node| matches!(node, Node::Definition(_)))
+        .count();
+    nodes.split_at(nodes.len() - trailing_definitions)
+}
+
+// Templates for the link reference definitions of each release, with `{repository}`,
+// `{version}` and `{previous_version}` placeholders. The defaults are GitHub's URLs, other hosts
+// (e.g.; GitLab's `{repository}/-/compare/v{previous_version}...v{version}`) need their own.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub(crate) struct LinkTemplates {
+    // Link from a release to the changes since the previous one.
+    pub(crate) compare: String,
+    // Link for a release without a previous one to compare against.
+    pub(crate) tag: String,
+    // Link from the unreleased entry to the changes since the latest release.
+    pub(crate) unreleased: String,
+}
+
+pub(crate) const DEFAULT_COMPARE_LINK_TEMPLATE: &str =
+    "{repository}/compare/v{previous_version}...v{version}";
+pub(crate) const DEFAULT_TAG_LINK_TEMPLATE: &str = "{repository}/releases/tag/v{version}";
+pub(crate) const DEFAULT_UNRELEASED_LINK_TEMPLATE: &str = "{repository}/compare/v{version}...HEAD";
+
+impl Default for LinkTemplates {
+    fn default() -> Self {
+        LinkTemplates {
+            compare: DEFAULT_COMPARE_LINK_TEMPLATE.to_string(),
+            tag: DEFAULT_TAG_LINK_TEMPLATE.to_string(),
+            unreleased: DEFAULT_UNRELEASED_LINK_TEMPLATE.to_string(),
+        }
+    }
+}
+
+fn render_link(
+    template: &str,
+    repository: &str,
+    version: &Version,
+    previous_version: Option<&Version>,
+) -> String {
+    template
+        .replace("{repository}", repository)
+        .replace("{version}", &version.to_string())
+        .replace(
+            "{previous_version}",
+            &previous_version
+                .map(ToString::to_string)
+                .unwrap_or_default(),
+        )
+}
+
+// Each release links to a comparison with the release before it in the changelog. When the
+// repository's tags are given, the comparison is with the closest older tag instead, for
+// repositories whose tags have drifted from the changelog history.
 pub(crate) fn generate_release_declarations<S: Into<String>>(
     changelog: &Changelog,
     repository: S,
+    link_templates: &LinkTemplates,
     starting_with_version: Option<&Version>,
+    tags: Option<&[Version]>,
 ) -> String {
     let repository = repository.into();
 
-    let mut versions = changelog.releases.values().filter_map(|release| {
-        if let Some(starting_version) = &starting_with_version {
-            if (*starting_version).le(&release.version) {
-                Some(&release.version)
-            } else {
-                None
-            }
-        } else {
-            Some(&release.version)
-        }
-    });
+    let versions = changelog
+        .releases
+        .values()
+        .map(|release| &release.version)
+        .filter(|version| starting_with_version.map_or(true, |starting| starting.le(version)))
+        .collect::<Vec<_>>();
 
     let mut declarations = vec![];
-    let mut previous_version = versions.next();
 
-    declarations.push(if let Some(version) = previous_version {
-        format!("[unreleased]: {repository}/compare/v{version}...HEAD")
+    declarations.push(if let Some(version) = versions.first() {
+        let link = render_link(&link_templates.unreleased, &repository, version, None);
+        format!("[unreleased]: {link}")
     } else {
         format!("[unreleased]: {repository}")
     });
 
-    for next_version in versions {
-        if let Some(version) = previous_version {
-            declarations.push(format!(
-                "[{version}]: {repository}/compare/v{next_version}...v{version}"
-            ));
-        }
-        previous_version = Some(next_version);
-    }
-
-    if let Some(version) = previous_version {
-        declarations.push(format!("[{version}]: {repository}/releases/tag/v{version}"));
+    for (index, version) in versions.iter().enumerate() {
+        let previous_version = match tags {
+            Some(tags) => tags.iter().filter(|tag| tag < version).max(),
+            None => versions.get(index + 1).copied(),
+        };
+        let link = match previous_version {
+            Some(previous_version) => render_link(
+                &link_templates.compare,
+                &repository,
+                version,
+                Some(previous_version),
+            ),
+            None => render_link(&link_templates.tag, &repository, version, None),
+        };
+        declarations.push(format!("[{version}]: {link}"));
     }
 
     declarations.join("\n")
@@ -240,8 +799,13 @@ pub(crate) fn generate_release_declarations<S: Into<String>>(
 
 #[cfg(test)]
 mod test {
-    use crate::changelog::{generate_release_declarations, Changelog};
+    use crate::changelog::{
+        append_group_entries, changes_by_group, demote_headings, generate_release_declarations,
+        normalize_change_groups, read_trailing_link_definitions, validate_changelog, Changelog,
+        LinkTemplates,
+    };
     use chrono::{TimeZone, Utc};
+    use indexmap::IndexMap;
     use semver::{BuildMetadata, Prerelease, Version};
 
     #[test]
@@ -394,6 +958,8 @@ and this project adheres to [Semantic Versioning](https://semver.org/spec/v2.0.0
         let declarations = generate_release_declarations(
             &changelog,
             "https://github.com/olivierlacan/keep-a-changelog",
+            &LinkTemplates::default(),
+            None,
             None,
         );
         assert_eq!(
@@ -422,6 +988,8 @@ and this project adheres to [Semantic Versioning](https://semver.org/spec/v2.0.0
         let declarations = generate_release_declarations(
             &changelog,
             "https://github.com/olivierlacan/keep-a-changelog",
+            &LinkTemplates::default(),
+            None,
             None,
         );
         assert_eq!(
@@ -438,6 +1006,8 @@ and this project adheres to [Semantic Versioning](https://semver.org/spec/v2.0.0
         let declarations = generate_release_declarations(
             &changelog,
             "https://github.com/olivierlacan/keep-a-changelog",
+            &LinkTemplates::default(),
+            None,
             None,
         );
         assert_eq!(
@@ -452,6 +1022,7 @@ and this project adheres to [Semantic Versioning](https://semver.org/spec/v2.0.0
         let declarations = generate_release_declarations(
             &changelog,
             "https://github.com/olivierlacan/keep-a-changelog",
+            &LinkTemplates::default(),
             Some(&Version {
                 major: 1,
                 minor: 0,
@@ -459,6 +1030,7 @@ and this project adheres to [Semantic Versioning](https://semver.org/spec/v2.0.0
                 pre: Prerelease::default(),
                 build: BuildMetadata::default(),
             }),
+            None,
         );
         assert_eq!(
             declarations,
@@ -469,6 +1041,453 @@ and this project adheres to [Semantic Versioning](https://semver.org/spec/v2.0.0
         );
     }
 
+    #[test]
+    fn test_generate_release_declarations_with_tags() {
+        let changelog = Changelog::try_from(
+            "## [Unreleased]\n\n## [1.2.0] - 2023-03-05\n\n- Foo\n\n## [1.1.0] - 2023-03-01\n\n- Bar\n",
+        )
+        .unwrap();
+        let tags = [
+            Version::new(1, 0, 0),
+            Version::new(1, 1, 1),
+            Version::new(1, 0, 5),
+        ];
+        let declarations = generate_release_declarations(
+            &changelog,
+            "https://github.com/heroku/example",
+            &LinkTemplates::default(),
+            None,
+            Some(&tags),
+        );
+        assert_eq!(
+            declarations,
+            r"[unreleased]: https://github.com/heroku/example/compare/v1.2.0...HEAD
+[1.2.0]: https://github.com/heroku/example/compare/v1.1.1...v1.2.0
+[1.1.0]: https://github.com/heroku/example/compare/v1.0.5...v1.1.0"
+        );
+    }
+
+    #[test]
+    fn test_generate_release_declarations_with_link_templates() {
+        let changelog = Changelog::try_from(
+            "## [Unreleased]\n\n## [1.2.0] - 2023-03-05\n\n- Foo\n\n## [1.1.0] - 2023-03-01\n\n- Bar\n",
+        )
+        .unwrap();
+        let declarations = generate_release_declarations(
+            &changelog,
+            "https://gitlab.com/heroku/example",
+            &LinkTemplates {
+                compare: "{repository}/-/compare/v{previous_version}...v{version}".to_string(),
+                tag: "{repository}/-/tags/v{version}".to_string(),
+                unreleased: "{repository}/-/compare/v{version}...main".to_string(),
+            },
+            None,
+            None,
+        );
+        assert_eq!(
+            declarations,
+            r"[unreleased]: https://gitlab.com/heroku/example/-/compare/v1.2.0...main
+[1.2.0]: https://gitlab.com/heroku/example/-/compare/v1.1.0...v1.2.0
+[1.1.0]: https://gitlab.com/heroku/example/-/tags/v1.1.0"
+        );
+    }
+
+    #[test]
+    fn test_demote_headings() {
+        assert_eq!(
+            demote_headings("### Added\n\n- Added a script:\n  ```\n# setup\n  ```\n\n### Fixed\n\n- #123 fixed."),
+            "#### Added\n\n- Added a script:\n  ```\n# setup\n  ```\n\n#### Fixed\n\n- #123 fixed."
+        );
+    }
+
+    #[test]
+    fn test_protected_regions() {
+        let migration_guide = "<!-- actions:protect-start -->\n## Migrating to 1.0.0\n\n### Removed settings\n\n* `FOO_VERSION` is no longer read,   \n  set `BAR_VERSION` instead.\n<!-- actions:protect-end -->";
+        let value = format!(
+            "# Changelog\n\n## [Unreleased]\n\n## [1.0.0] - 2023-02-01\n\n### Removed\n\n- Dropped `FOO_VERSION`.\n\n{migration_guide}\n\n## [0.9.0] - 2023-01-01\n\n- Initial release.\n"
+        );
+
+        let changelog = Changelog::try_from(value.as_str()).unwrap();
+        assert_eq!(changelog.releases.len(), 2);
+        assert_eq!(
+            changelog.releases["1.0.0"].body,
+            format!("### Removed\n\n- Dropped `FOO_VERSION`.\n\n{migration_guide}")
+        );
+        assert!(changelog.to_string().contains(migration_guide));
+        assert_eq!(validate_changelog(&value), Vec::<String>::new());
+
+        let body = &changelog.releases["1.0.0"].body;
+        assert_eq!(
+            demote_headings(body),
+            format!("#### Removed\n\n- Dropped `FOO_VERSION`.\n\n{migration_guide}")
+        );
+        assert_eq!(
+            changes_by_group(body),
+            IndexMap::from([(
+                "Removed".to_string(),
+                vec!["Dropped `FOO_VERSION`.".to_string()]
+            )])
+        );
+        assert_eq!(
+            append_group_entries(body, "Removed", "- Dropped `BAZ`."),
+            format!(
+                "### Removed\n\n- Dropped `FOO_VERSION`.\n- Dropped `BAZ`.\n\n{migration_guide}"
+            )
+        );
+    }
+
+    #[test]
+    fn test_protected_regions_before_the_first_release() {
+        let migration_note = "<!-- actions:protect-start -->\n> **Note**\n> Read the [migration guide](docs/MIGRATING.md) before upgrading to 2.0.0.  \n<!-- actions:protect-end -->";
+        let value = format!(
+            "# Changelog\n\nAll notable changes to this project will be documented in this file.\n\n{migration_note}\n\n## [Unreleased]\n\n- Added a thing.\n\n## [1.0.0] - 2023-02-01\n\n- Initial release.\n"
+        );
+
+        let changelog = Changelog::try_from(value.as_str()).unwrap();
+        assert_eq!(
+            changelog.protected_preamble.as_deref(),
+            Some(migration_note)
+        );
+        assert_eq!(changelog.unreleased.as_deref(), Some("- Added a thing."));
+        assert!(changelog
+            .to_string()
+            .contains(&format!("{migration_note}\n\n## [Unreleased]")));
+        assert_eq!(
+            Changelog::try_from(changelog.to_string().as_str()).unwrap(),
+            changelog
+        );
+    }
+
+    #[test]
+    fn test_released_by_comment() {
+        let value = "# Changelog\n\n## [Unreleased]\n\n## [1.0.0] - 2023-02-01\n\n<!-- released-by actions v1.2.3 run 123456 -->\n\n- Added a thing.\n\n## [0.9.0] - 2023-01-01\n\n- Initial release.\n";
+
+        let changelog = Changelog::try_from(value).unwrap();
+        assert_eq!(
+            changelog.releases["1.0.0"].released_by,
+            Some("actions v1.2.3 run 123456".to_string())
+        );
+        assert_eq!(changelog.releases["1.0.0"].body, "- Added a thing.");
+        assert_eq!(changelog.releases["0.9.0"].released_by, None);
+        assert!(changelog
+            .to_string()
+            .contains("## [1.0.0] - 2023-02-01\n\n<!-- released-by actions v1.2.3 run 123456 -->\n\n- Added a thing."));
+        assert_eq!(
+            Changelog::try_from(changelog.to_string().as_str()).unwrap(),
+            changelog
+        );
+    }
+
+    #[test]
+    fn test_normalize_change_groups() {
+        assert_eq!(
+            normalize_change_groups(
+                "Highlights of this release.\n\n### Security\n\n- Patched CVE-2024-1234.\n\n### Fixed\n\n\n### Notes\n\n- Some note.\n\n### Added\n\n- Added a thing:\n  ```\n  ### not a heading\n  ```\n"
+            ),
+            "Highlights of this release.\n\n### Added\n\n- Added a thing:\n  ```\n  ### not a heading\n  ```\n\n### Security\n\n- Patched CVE-2024-1234.\n\n### Notes\n\n- Some note."
+        );
+        assert_eq!(normalize_change_groups("### Fixed\n\n### Changed\n"), "");
+    }
+
+    #[test]
+    fn test_validate_changelog() {
+        assert_eq!(
+            validate_changelog(
+                "# Changelog\n\n## [Unreleased]\n\n### Fixed\n\n- Foo.\n\n## [1.0.0] - 2023-01-01\n\n### Improved\n\n- Bar.\n"
+            ),
+            Vec::<String>::new()
+        );
+
+        assert_eq!(
+            validate_changelog(
+                "# Changelog\n\n## [1.0.1]\n\n## [1.0.0] - 2023-02-31\n\n## [0.9.0] - 2023-01-01\n\n## [0.9.0] - 2022-12-01\n"
+            ),
+            vec![
+                "Missing `## [Unreleased]` section".to_string(),
+                "Release header `[1.0.1]` is missing a date (expected `[X.Y.Z] - YYYY-MM-DD`)".to_string(),
+                "Invalid release header `[1.0.0] - 2023-02-31`: Invalid date in release entry - Could not convert year: 2023, month: 2, day: 31 into a valid date from \"[1.0.0] - 2023-02-31\"".to_string(),
+                "Duplicate release header for version 0.9.0".to_string(),
+            ]
+        );
+
+        assert_eq!(
+            validate_changelog("## [Unreleased]\n\n### Improvements\n\n- Foo.\n"),
+            vec!["Unrecognized change group `Improvements` in the unreleased section (expected one of Added, Changed, Deprecated, Removed, Fixed, Security, Dependencies)".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_read_trailing_link_definitions() {
+        let definitions = read_trailing_link_definitions(
+            "## [Unreleased]\n\n- See [docs].\n\n[docs]: https://example.com/docs\n\n## [0.0.1] - 2023-03-05\n\n- Some change\n\n[unreleased]: https://github.com/heroku/example/compare/v0.0.1...HEAD\n[0.0.1]: https://github.com/heroku/example/releases/tag/v0.0.1\n",
+        )
+        .unwrap();
+        assert_eq!(
+            definitions,
+            "[unreleased]: https://github.com/heroku/example/compare/v0.0.1...HEAD\n[0.0.1]: https://github.com/heroku/example/releases/tag/v0.0.1"
+        );
+    }
+
+    #[test]
+    fn test_add_unreleased_entry_without_unreleased_changes() {
+        let mut changelog = Changelog::try_from("## [Unreleased]").unwrap();
+        changelog.add_unreleased_entry("Changed", "Updated `heroku/java` to `1.0.0`.");
+        assert_eq!(
+            changelog.unreleased,
+            Some("### Changed\n\n- Updated `heroku/java` to `1.0.0`.".to_string())
+        );
+    }
+
+    #[test]
+    fn test_add_unreleased_entry_to_existing_group() {
+        let mut changelog = Changelog::try_from(
+            "## [Unreleased]
+
+### Changed
+
+* Upgraded to libcnb 0.26.
+  ([#100](https://github.com/heroku/buildpacks-jvm/pull/100))
+
+### Fixed
+
+* Fixed a caching issue.
+
+## [1.0.0] - 2024-01-01
+
+- Initial release.",
+        )
+        .unwrap();
+        changelog.add_unreleased_entry("Changed", "- Updated `heroku/jvm` to `1.0.1`.");
+        changelog.add_unreleased_entry(
+            "Added",
+            "Support for Maven 4.\nThis is enabled with `maven.version`.",
+        );
+        assert_eq!(
+            changelog.unreleased,
+            Some(
+                "### Changed
+
+* Upgraded to libcnb 0.26.
+  ([#100](https://github.com/heroku/buildpacks-jvm/pull/100))
+* Updated `heroku/jvm` to `1.0.1`.
+
+### Fixed
+
+* Fixed a caching issue.
+
+### Added
+
+* Support for Maven 4.
+  This is enabled with `maven.version`."
+                    .to_string()
+            )
+        );
+    }
+
+    #[test]
+    fn test_add_unreleased_entry_to_empty_group() {
+        let mut changelog =
+            Changelog::try_from("## [Unreleased]\n\n### Changed\n\n### Fixed\n\n- A fix.").unwrap();
+        changelog.add_unreleased_entry("Changed", "A change.");
+        assert_eq!(
+            changelog.unreleased,
+            Some("### Changed\n\n- A change.\n\n### Fixed\n\n- A fix.".to_string())
+        );
+    }
+
+    #[test]
+    fn test_append_group_entries_after_code_block() {
+        let section = "### Changed
+
+- Build scripts now run with `set -e`:
+
+  ```bash
+  # run before the build
+  ### not a heading
+  ./bin/prebuild
+  ```
+
+### Fixed
+
+- A fix.
+";
+        assert_eq!(
+            append_group_entries(section, "Changed", "- Updated `heroku/jvm` to `1.0.1`."),
+            "### Changed
+
+- Build scripts now run with `set -e`:
+
+  ```bash
+  # run before the build
+  ### not a heading
+  ./bin/prebuild
+  ```
+- Updated `heroku/jvm` to `1.0.1`.
+
+### Fixed
+
+- A fix."
+        );
+    }
+
+    #[test]
+    fn test_append_group_entries_ignores_headings_in_unindented_code_blocks() {
+        let section = "### Changed
+
+- Added a `Procfile` default:
+
+```
+# Procfile
+web: bin/start
+```
+
+
+";
+        assert_eq!(
+            append_group_entries(section, "Changed", "- A change."),
+            "### Changed
+
+- Added a `Procfile` default:
+
+```
+# Procfile
+web: bin/start
+```
+
+- A change.
+
+"
+        );
+    }
+
+    #[test]
+    fn test_append_group_entries_after_nested_list() {
+        assert_eq!(
+            append_group_entries(
+                "### Changed\n\n- Updated runtimes:\n  - Node.js 22\n    - npm 10\n\n",
+                "Changed",
+                "- A change.\n- Another change."
+            ),
+            "### Changed\n\n- Updated runtimes:\n  - Node.js 22\n    - npm 10\n- A change.\n- Another change.\n"
+        );
+    }
+
+    #[test]
+    fn test_append_group_entries_matches_list_indentation() {
+        assert_eq!(
+            append_group_entries(
+                "### Changed\n\n  - A change.\n    - A detail.",
+                "Changed",
+                "- Another change.\n  With more text."
+            ),
+            "### Changed\n\n  - A change.\n    - A detail.\n  - Another change.\n    With more text."
+        );
+    }
+
+    #[test]
+    fn test_append_group_entries_to_new_group() {
+        assert_eq!(
+            append_group_entries("### Fixed\n\n- A fix.\n\n", "Changed", "- A change."),
+            "### Fixed\n\n- A fix.\n\n### Changed\n\n- A change."
+        );
+        assert_eq!(
+            append_group_entries("", "Changed", "- A change."),
+            "### Changed\n\n- A change."
+        );
+    }
+
+    #[test]
+    fn test_round_trip_preserves_hard_wrapped_release_bodies() {
+        let changelog = Changelog::try_from(HARD_WRAPPED_CHANGELOG).unwrap();
+        assert_eq!(changelog.to_string(), HARD_WRAPPED_CHANGELOG);
+    }
+
+    #[test]
+    fn test_release_bodies_keep_their_link_definitions() {
+        let changelog = Changelog::try_from(
+            "## [Unreleased]
+
+## [2.1.0] - 2023-06-01
+
+- Added support for [Yarn Berry][yarn-berry] workspaces.
+  ([#512](https://github.com/heroku/buildpacks-nodejs/pull/512))
+
+[yarn-berry]: https://yarnpkg.com/getting-started
+
+## [2.0.0] - 2023-05-01
+
+- Some change
+
+[unreleased]: https://github.com/heroku/buildpacks-nodejs/compare/v2.1.0...HEAD
+[2.1.0]: https://github.com/heroku/buildpacks-nodejs/compare/v2.0.0...v2.1.0
+[2.0.0]: https://github.com/heroku/buildpacks-nodejs/releases/tag/v2.0.0
+",
+        )
+        .unwrap();
+        assert_eq!(
+            changelog.releases.get("2.1.0").unwrap().body,
+            "- Added support for [Yarn Berry][yarn-berry] workspaces.
+  ([#512](https://github.com/heroku/buildpacks-nodejs/pull/512))
+
+[yarn-berry]: https://yarnpkg.com/getting-started"
+        );
+        assert_eq!(
+            changelog.releases.get("2.0.0").unwrap().body,
+            "- Some change"
+        );
+    }
+
+    #[test]
+    fn test_release_body_leading_indentation_is_preserved() {
+        let changelog = Changelog::try_from(
+            "## [Unreleased]\n\n## [1.0.0] - 2023-01-01\n\n    composer install --no-dev\n\n- Some change\n",
+        )
+        .unwrap();
+        assert_eq!(
+            changelog.releases.get("1.0.0").unwrap().body,
+            "    composer install --no-dev\n\n- Some change"
+        );
+    }
+
+    const HARD_WRAPPED_CHANGELOG: &str = r"# Changelog
+
+All notable changes to this project will be documented in this file.
+
+The format is based on [Keep a Changelog](https://keepachangelog.com/en/1.1.0/),
+and this project adheres to [Semantic Versioning](https://semver.org/spec/v2.0.0.html).
+
+## [Unreleased]
+
+- The default PHP version for new apps is now 8.3. Apps that need
+  an older version can still select one in `composer.json`.
+
+## [1.2.0] - 2023-11-02
+
+### Changed
+
+- Maven is now invoked with `--batch-mode` so that download progress
+  isn't written to the build log.
+  ([#622](https://github.com/heroku/buildpacks-jvm/pull/622))
+- Default OpenJDK versions:
+  * `21` now resolves to `21.0.1`
+  * `17` now resolves to `17.0.9`
+
+### Fixed
+
+- Fixed an issue where `npm ci` would be run
+  twice when a `package-lock.json`   
+  and a `yarn.lock` were both present.
+
+## [1.1.0] - 2023-10-05
+
+Some builds that relied on the previous
+behaviour need a `Procfile`:
+
+    web: java -jar target/app.jar
+
+- Initial support for Gradle 8.
+";
+
     const KEEP_A_CHANGELOG_1_0_0: &str = r#"# Changelog
 
 All notable changes to this project will be documented in this file.
@@ -695,4 +1714,27 @@ and this project adheres to [Semantic Versioning](https://semver.org/spec/v2.0.0
 - Good examples and basic guidelines, including proper date formatting.
 - Counter-examples: "What makes unicorns cry?".
 "#;
+
+    #[test]
+    fn test_changes_by_group() {
+        let body = "- Ungrouped entry.\n\n### Added\n\n- First addition.\n- Second addition\n  that wraps.\n\n### Changed\n\n* Updated `foo` to `1.2.3`.\n";
+
+        assert_eq!(
+            changes_by_group(body),
+            IndexMap::from([
+                ("Other".to_string(), vec!["Ungrouped entry.".to_string()]),
+                (
+                    "Added".to_string(),
+                    vec![
+                        "First addition.".to_string(),
+                        "Second addition\nthat wraps.".to_string()
+                    ]
+                ),
+                (
+                    "Changed".to_string(),
+                    vec!["Updated `foo` to `1.2.3`.".to_string()]
+                ),
+            ])
+        );
+    }
 }
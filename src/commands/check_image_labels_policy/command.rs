@@ -0,0 +1,104 @@
+use crate::buildpacks::read_image_labels;
+use crate::commands::check_image_labels_policy::errors::Error;
+use crate::concurrency::{try_map_concurrently, MAX_CONCURRENT_REQUESTS};
+use crate::github::actions;
+use clap::Parser;
+use std::collections::{BTreeMap, HashMap};
+
+type Result<T> = std::result::Result<T, Error>;
+
+#[derive(Parser, Debug)]
+#[command(author, version, about = "Checks that published images carry the OCI labels required by the image labels policy", long_about = None, disable_version_flag = true)]
+pub(crate) struct CheckImageLabelsPolicyArgs {
+    #[arg(long = "image", required = true)]
+    pub(crate) images: Vec<String>,
+    #[arg(long = "required-label", default_values = [
+        "org.opencontainers.image.licenses",
+        "org.opencontainers.image.source",
+        "org.opencontainers.image.revision",
+        "org.opencontainers.image.vendor",
+    ])]
+    pub(crate) required_labels: Vec<String>,
+}
+
+pub(crate) fn execute(args: &CheckImageLabelsPolicyArgs) -> Result<()> {
+    let mut violations = BTreeMap::new();
+
+    let image_labels = try_map_concurrently(&args.images, MAX_CONCURRENT_REQUESTS, |image| {
+        read_image_labels(image)
+    })
+    .map_err(Error::ReadImageLabels)?;
+
+    for (image, labels) in args.images.iter().zip(image_labels) {
+        let missing_labels = find_missing_labels(&labels, &args.required_labels);
+        if missing_labels.is_empty() {
+            eprintln!("✅️ Image labels policy satisfied: {image}");
+        } else {
+            violations.insert(image.clone(), missing_labels);
+        }
+    }
+
+    if violations.is_empty() {
+        return Ok(());
+    }
+
+    actions::set_summary(format!(
+        "## Image labels policy violations\n\n| Image | Missing labels |\n|-------|----------------|\n{}",
+        violations
+            .iter()
+            .map(|(image, labels)| format!("| `{image}` | {} |", labels.join(", ")))
+            .collect::<Vec<_>>()
+            .join("\n")
+    ))
+    .map_err(Error::WriteActionData)?;
+
+    Err(Error::PolicyViolations(violations))
+}
+
+// Labels that are present but blank don't satisfy the policy.
+fn find_missing_labels(
+    labels: &HashMap<String, String>,
+    required_labels: &[String],
+) -> Vec<String> {
+    required_labels
+        .iter()
+        .filter(|required_label| {
+            labels
+                .get(*required_label)
+                .map_or(true, |value| value.trim().is_empty())
+        })
+        .cloned()
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use crate::commands::check_image_labels_policy::command::find_missing_labels;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_find_missing_labels() {
+        let labels = HashMap::from([
+            (
+                "org.opencontainers.image.source".to_string(),
+                "https://github.com/heroku/buildpacks-nodejs".to_string(),
+            ),
+            (
+                "org.opencontainers.image.vendor".to_string(),
+                " ".to_string(),
+            ),
+        ]);
+        let required_labels = [
+            "org.opencontainers.image.licenses".to_string(),
+            "org.opencontainers.image.source".to_string(),
+            "org.opencontainers.image.vendor".to_string(),
+        ];
+        assert_eq!(
+            find_missing_labels(&labels, &required_labels),
+            vec![
+                "org.opencontainers.image.licenses".to_string(),
+                "org.opencontainers.image.vendor".to_string()
+            ]
+        );
+    }
+}
@@ -0,0 +1,21 @@
+use crate::buildpacks::ReadImageLabelsError;
+use crate::github::actions::WriteActionDataError;
+use std::collections::BTreeMap;
+
+#[derive(Debug, thiserror::Error)]
+pub(crate) enum Error {
+    #[error(transparent)]
+    ReadImageLabels(ReadImageLabelsError),
+    #[error("The following images are missing labels required by the image labels policy:\n{}", list_violations(.0))]
+    PolicyViolations(BTreeMap<String, Vec<String>>),
+    #[error(transparent)]
+    WriteActionData(WriteActionDataError),
+}
+
+fn list_violations(violations: &BTreeMap<String, Vec<String>>) -> String {
+    violations
+        .iter()
+        .map(|(image, labels)| format!("• {image}: {}", labels.join(", ")))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
@@ -0,0 +1,317 @@
+use crate::buildpacks::{
+    buildpack_changelog_path, find_releasable_buildpacks, list_image_tags, list_repositories,
+    read_buildpack_descriptor, read_buildpack_targets, read_image_mirrors_metadata,
+    read_image_repository_metadata, read_image_target_repositories_metadata,
+};
+use crate::changelog::Changelog;
+use crate::commands::check_orphaned_artifacts::errors::Error;
+use crate::commands::generate_buildpack_matrix::command::expand_distro_targets;
+use crate::commands::resolve_path;
+use crate::concurrency::{try_map_concurrently, MAX_CONCURRENT_REQUESTS};
+use crate::github::actions;
+use clap::Parser;
+use libcnb_data::buildpack::BuildpackDescriptor;
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+
+type Result<T> = std::result::Result<T, Error>;
+
+#[derive(Parser, Debug)]
+#[command(author, version, about = "Lists image repositories and tags in the registry that no buildpack produces anymore", long_about = None, disable_version_flag = true)]
+pub(crate) struct CheckOrphanedArtifactsArgs {
+    /// Checkout of a repository whose buildpacks publish images, can be repeated to cover every
+    /// repository that publishes to the namespaces. Defaults to the current directory.
+    #[arg(long)]
+    pub(crate) source_dir: Vec<PathBuf>,
+    /// Registry namespace to look for orphaned repositories in (e.g.;
+    /// `public.ecr.aws/heroku-buildpacks`). The registry must support listing its repositories.
+    #[arg(long, required = true)]
+    pub(crate) namespace: Vec<String>,
+}
+
+// Repositories and the tags that the buildpacks publish to them.
+type ProducedTags = BTreeMap<String, BTreeSet<String>>;
+
+pub(crate) fn execute(args: &CheckOrphanedArtifactsArgs) -> Result<()> {
+    let current_dir = std::env::current_dir().map_err(Error::GetCurrentDir)?;
+    let source_dirs = if args.source_dir.is_empty() {
+        vec![current_dir.clone()]
+    } else {
+        args.source_dir
+            .iter()
+            .map(|dir| resolve_path(dir, &current_dir))
+            .collect()
+    };
+
+    let mut produced_tags = ProducedTags::new();
+    for source_dir in &source_dirs {
+        for buildpack_dir in
+            find_releasable_buildpacks(source_dir, &[]).map_err(Error::FindReleasableBuildpacks)?
+        {
+            let buildpack_descriptor = read_buildpack_descriptor(&buildpack_dir)
+                .map_err(Error::ReadBuildpackDescriptor)?;
+            let versions = read_released_versions(&buildpack_dir, &buildpack_descriptor)?;
+            for (repository, tags) in buildpack_produced_tags(&buildpack_descriptor, &versions) {
+                produced_tags.entry(repository).or_default().extend(tags);
+            }
+        }
+    }
+
+    let namespace_repositories = args
+        .namespace
+        .iter()
+        .map(|namespace| list_namespace_repositories(namespace))
+        .collect::<Result<Vec<_>>>()?
+        .into_iter()
+        .flatten()
+        .collect::<BTreeSet<_>>();
+
+    let orphaned_repositories = namespace_repositories
+        .iter()
+        .filter(|repository| !produced_tags.contains_key(*repository))
+        .cloned()
+        .collect::<Vec<_>>();
+
+    let checked_repositories = namespace_repositories
+        .iter()
+        .filter(|repository| produced_tags.contains_key(*repository))
+        .collect::<Vec<_>>();
+    let repository_tags = try_map_concurrently(
+        &checked_repositories,
+        MAX_CONCURRENT_REQUESTS,
+        |repository| list_image_tags(repository),
+    )
+    .map_err(Error::ListImageTags)?;
+    let orphaned_tags = checked_repositories
+        .iter()
+        .zip(repository_tags)
+        .map(|(repository, tags)| {
+            (
+                (*repository).clone(),
+                find_orphaned_tags(&tags, &produced_tags[*repository]),
+            )
+        })
+        .filter(|(_, tags)| !tags.is_empty())
+        .collect::<BTreeMap<_, _>>();
+
+    report_orphans(&orphaned_repositories, &orphaned_tags)
+}
+
+// Every release in the changelog published images, along with the current version.
+fn read_released_versions(
+    buildpack_dir: &Path,
+    buildpack_descriptor: &BuildpackDescriptor,
+) -> Result<BTreeSet<String>> {
+    let mut versions = BTreeSet::from([buildpack_descriptor.buildpack().version.to_string()]);
+    let changelog_path = buildpack_changelog_path(buildpack_dir, buildpack_descriptor);
+    if changelog_path.exists() {
+        let contents = std::fs::read_to_string(&changelog_path)
+            .map_err(|e| Error::ReadingChangelog(changelog_path.clone(), e))?;
+        let changelog = Changelog::try_from(contents.as_str())
+            .map_err(|e| Error::ParsingChangelog(changelog_path.clone(), e))?;
+        versions.extend(changelog.releases.into_keys());
+    }
+    Ok(versions)
+}
+
+// A buildpack publishes each version under its own tag, and under a tag per target (e.g.;
+// `1.2.3_linux-arm64`) when it has several targets. Older versions may have had other targets,
+// so their tags for targets the buildpack no longer has count as orphaned.
+fn buildpack_produced_tags(
+    buildpack_descriptor: &BuildpackDescriptor,
+    versions: &BTreeSet<String>,
+) -> ProducedTags {
+    let Some(image_repository) = read_image_repository_metadata(buildpack_descriptor) else {
+        return ProducedTags::new();
+    };
+    let target_repositories = read_image_target_repositories_metadata(buildpack_descriptor);
+    let targets = expand_distro_targets(read_buildpack_targets(buildpack_descriptor));
+
+    let mut tags = versions.clone();
+    if targets.len() > 1 {
+        for version in versions {
+            for (_, name) in &targets {
+                tags.insert(format!("{version}_{name}"));
+            }
+        }
+    }
+
+    std::iter::once(image_repository)
+        .chain(target_repositories.into_values())
+        .chain(read_image_mirrors_metadata(buildpack_descriptor))
+        .map(|repository| (repository, tags.clone()))
+        .collect()
+}
+
+// The namespace's registry is the part before the first `/`, and catalogs list repositories
+// without it.
+fn list_namespace_repositories(namespace: &str) -> Result<Vec<String>> {
+    let namespace = namespace.trim_end_matches('/');
+    let registry = namespace
+        .split_once('/')
+        .map_or(namespace, |(registry, _)| registry);
+    let repositories = list_repositories(registry).map_err(Error::ListRepositories)?;
+    Ok(filter_namespace_repositories(
+        namespace,
+        registry,
+        &repositories,
+    ))
+}
+
+fn filter_namespace_repositories(
+    namespace: &str,
+    registry: &str,
+    repositories: &[String],
+) -> Vec<String> {
+    repositories
+        .iter()
+        .map(|repository| format!("{registry}/{repository}"))
+        .filter(|repository| {
+            namespace == registry || repository.starts_with(&format!("{namespace}/"))
+        })
+        .collect()
+}
+
+// Tags are orphaned when no version of any buildpack publishes them, which includes the temporary
+// tags (e.g.; `_123456`) that release runs push before an image gets its version tag.
+fn find_orphaned_tags(tags: &[String], produced_tags: &BTreeSet<String>) -> Vec<String> {
+    tags.iter()
+        .filter(|tag| !produced_tags.contains(*tag))
+        .cloned()
+        .collect()
+}
+
+fn report_orphans(
+    orphaned_repositories: &[String],
+    orphaned_tags: &BTreeMap<String, Vec<String>>,
+) -> Result<()> {
+    for repository in orphaned_repositories {
+        eprintln!("⚠️ Orphaned repository: {repository}");
+    }
+    for (repository, tags) in orphaned_tags {
+        for tag in tags {
+            eprintln!("⚠️ Orphaned tag: {repository}:{tag}");
+        }
+    }
+    if orphaned_repositories.is_empty() && orphaned_tags.is_empty() {
+        eprintln!("✅️ No orphaned repositories or tags");
+    } else {
+        actions::set_summary(format!(
+            "## Orphaned artifacts\n\n| Repository | Orphaned tags |\n|------------|---------------|\n{}",
+            orphaned_repositories
+                .iter()
+                .map(|repository| format!("| `{repository}` | all |"))
+                .chain(orphaned_tags.iter().map(|(repository, tags)| {
+                    format!("| `{repository}` | {} |", tags.join(", "))
+                }))
+                .collect::<Vec<_>>()
+                .join("\n")
+        ))
+        .map_err(Error::WriteActionData)?;
+    }
+
+    actions::set_output(
+        "orphaned_repositories",
+        serde_json::to_string(orphaned_repositories).map_err(Error::SerializingJson)?,
+    )
+    .map_err(Error::WriteActionData)?;
+    actions::set_output(
+        "orphaned_tags",
+        serde_json::to_string(orphaned_tags).map_err(Error::SerializingJson)?,
+    )
+    .map_err(Error::WriteActionData)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::commands::check_orphaned_artifacts::command::{
+        buildpack_produced_tags, filter_namespace_repositories, find_orphaned_tags, ProducedTags,
+    };
+    use libcnb_data::buildpack::BuildpackDescriptor;
+    use std::collections::BTreeSet;
+
+    fn tags(values: &[&str]) -> BTreeSet<String> {
+        values.iter().map(ToString::to_string).collect()
+    }
+
+    #[test]
+    fn test_buildpack_produced_tags() {
+        let buildpack_descriptor = toml::from_str::<BuildpackDescriptor>(
+            r#"
+api = "0.10"
+
+[buildpack]
+id = "heroku/foo"
+version = "1.1.0"
+
+[[targets]]
+os = "linux"
+arch = "amd64"
+
+[[targets]]
+os = "linux"
+arch = "arm64"
+
+[metadata.release.image]
+repository = "docker.io/heroku/buildpack-foo"
+mirrors = ["ghcr.io/heroku/buildpack-foo"]
+"#,
+        )
+        .unwrap();
+
+        let expected_tags = tags(&[
+            "1.0.0",
+            "1.0.0_linux-amd64",
+            "1.0.0_linux-arm64",
+            "1.1.0",
+            "1.1.0_linux-amd64",
+            "1.1.0_linux-arm64",
+        ]);
+        assert_eq!(
+            buildpack_produced_tags(&buildpack_descriptor, &tags(&["1.0.0", "1.1.0"])),
+            ProducedTags::from([
+                (
+                    "docker.io/heroku/buildpack-foo".to_string(),
+                    expected_tags.clone()
+                ),
+                ("ghcr.io/heroku/buildpack-foo".to_string(), expected_tags),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_filter_namespace_repositories() {
+        let repositories = [
+            "heroku-buildpacks/nodejs".to_string(),
+            "heroku-buildpacks-old/nodejs".to_string(),
+            "other/nodejs".to_string(),
+        ];
+        assert_eq!(
+            filter_namespace_repositories(
+                "public.ecr.aws/heroku-buildpacks",
+                "public.ecr.aws",
+                &repositories
+            ),
+            vec!["public.ecr.aws/heroku-buildpacks/nodejs".to_string()]
+        );
+        assert_eq!(
+            filter_namespace_repositories("public.ecr.aws", "public.ecr.aws", &repositories).len(),
+            3
+        );
+    }
+
+    #[test]
+    fn test_find_orphaned_tags() {
+        assert_eq!(
+            find_orphaned_tags(
+                &[
+                    "1.0.0".to_string(),
+                    "_123456".to_string(),
+                    "0.9.0_linux-arm64".to_string(),
+                ],
+                &tags(&["1.0.0", "1.0.0_linux-arm64"])
+            ),
+            vec!["_123456".to_string(), "0.9.0_linux-arm64".to_string()]
+        );
+    }
+}
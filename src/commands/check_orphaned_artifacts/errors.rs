@@ -0,0 +1,29 @@
+use crate::buildpacks::{
+    FindReleasableBuildpacksError, ListImageTagsError, ListRepositoriesError,
+    ReadBuildpackDescriptorError,
+};
+use crate::changelog::ChangelogError;
+use crate::github::actions::WriteActionDataError;
+use std::path::PathBuf;
+
+#[derive(Debug, thiserror::Error)]
+pub(crate) enum Error {
+    #[error("Failed to get current directory\nError: {0}")]
+    GetCurrentDir(std::io::Error),
+    #[error(transparent)]
+    FindReleasableBuildpacks(FindReleasableBuildpacksError),
+    #[error(transparent)]
+    ReadBuildpackDescriptor(ReadBuildpackDescriptorError),
+    #[error("Could not read changelog\nPath: {}\nError: {}", .0.display(), .1)]
+    ReadingChangelog(PathBuf, #[source] std::io::Error),
+    #[error("Could not parse changelog\nPath: {}\nError: {}", .0.display(), .1)]
+    ParsingChangelog(PathBuf, #[source] ChangelogError),
+    #[error(transparent)]
+    ListRepositories(ListRepositoriesError),
+    #[error(transparent)]
+    ListImageTags(ListImageTagsError),
+    #[error("Could not serialize orphaned artifacts into json\nError: {0}")]
+    SerializingJson(#[source] serde_json::Error),
+    #[error(transparent)]
+    WriteActionData(WriteActionDataError),
+}
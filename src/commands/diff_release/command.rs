@@ -0,0 +1,352 @@
+use crate::buildpacks::{
+    buildpack_changelog_path, calculate_digest, find_releasable_buildpacks,
+    read_image_repository_metadata, CalculateDigestError,
+};
+use crate::changelog::{Changelog, ReleaseEntry};
+use crate::commands::diff_release::errors::Error;
+use crate::concurrency::{try_map_concurrently, MAX_CONCURRENT_REQUESTS};
+use crate::git::{read_file_at_revision, resolve_revision};
+use crate::github::actions;
+use clap::Parser;
+use globset::Glob;
+use libcnb_data::buildpack::{BuildpackDescriptor, BuildpackId, BuildpackVersion};
+use std::path::Path;
+
+type Result<T> = std::result::Result<T, Error>;
+
+#[derive(Parser, Debug)]
+#[command(author, version, about = "Summarizes what changed for each buildpack between two release tags", long_about = None, disable_version_flag = true)]
+pub(crate) struct DiffReleaseArgs {
+    #[arg(long)]
+    pub(crate) from: String,
+    #[arg(long)]
+    pub(crate) to: String,
+    /// Leaves out buildpacks matching the given glob, by buildpack id or directory.
+    #[arg(long)]
+    pub(crate) exclude: Vec<Glob>,
+}
+
+#[derive(Debug)]
+struct BuildpackSnapshot {
+    version: BuildpackVersion,
+    image_repository: Option<String>,
+    changelog: Option<Changelog>,
+}
+
+#[derive(Debug, Eq, PartialEq)]
+struct BuildpackDiff {
+    id: BuildpackId,
+    from_version: Option<BuildpackVersion>,
+    to_version: BuildpackVersion,
+    from_image: Option<ImageDiff>,
+    to_image: Option<ImageDiff>,
+    releases: Vec<ReleaseEntry>,
+}
+
+#[derive(Debug, Eq, PartialEq)]
+struct ImageDiff {
+    reference: String,
+    digest: Option<String>,
+}
+
+pub(crate) fn execute(args: &DiffReleaseArgs) -> Result<()> {
+    let current_dir = std::env::current_dir().map_err(Error::GetCurrentDir)?;
+
+    for revision in [&args.from, &args.to] {
+        resolve_revision(&current_dir, revision)
+            .map_err(|e| Error::ResolvingRevision(revision.clone(), e))?;
+    }
+
+    // buildpacks are discovered in the current checkout and then read as of each revision
+    let buildpack_dirs = find_releasable_buildpacks(&current_dir, &args.exclude)
+        .map_err(Error::FindReleasableBuildpacks)?;
+
+    let mut diffs = vec![];
+    for buildpack_dir in buildpack_dirs {
+        let relative_dir = buildpack_dir
+            .strip_prefix(&current_dir)
+            .unwrap_or(&buildpack_dir);
+        let Some((id, to_snapshot)) = read_snapshot(&current_dir, &args.to, relative_dir)? else {
+            continue;
+        };
+        let from_snapshot =
+            read_snapshot(&current_dir, &args.from, relative_dir)?.map(|(_, snapshot)| snapshot);
+        diffs.push(diff_snapshots(id, from_snapshot.as_ref(), &to_snapshot));
+    }
+    diffs.sort_by(|a, b| a.id.cmp(&b.id));
+
+    resolve_image_digests(&mut diffs)?;
+
+    let report = render_report(&args.from, &args.to, &diffs);
+
+    actions::set_output("report", &report).map_err(Error::SetActionOutput)?;
+    actions::set_summary(report).map_err(Error::SetActionOutput)?;
+
+    Ok(())
+}
+
+// Returns `None` if the buildpack didn't exist at the given revision.
+fn read_snapshot(
+    current_dir: &Path,
+    revision: &str,
+    buildpack_dir: &Path,
+) -> Result<Option<(BuildpackId, BuildpackSnapshot)>> {
+    let buildpack_toml_path = buildpack_dir.join("buildpack.toml");
+    let Some(contents) = read_file(current_dir, revision, &buildpack_toml_path)? else {
+        return Ok(None);
+    };
+    let descriptor = toml::from_str::<BuildpackDescriptor>(&contents).map_err(|e| {
+        Error::ParsingBuildpackDescriptor(object_name(revision, &buildpack_toml_path), e)
+    })?;
+
+    let changelog_path = buildpack_changelog_path(buildpack_dir, &descriptor);
+    let changelog = read_file(current_dir, revision, &changelog_path)?
+        .map(|contents| {
+            Changelog::try_from(contents.as_str())
+                .map_err(|e| Error::ParsingChangelog(object_name(revision, &changelog_path), e))
+        })
+        .transpose()?;
+
+    Ok(Some((
+        descriptor.buildpack().id.clone(),
+        BuildpackSnapshot {
+            version: descriptor.buildpack().version.clone(),
+            image_repository: read_image_repository_metadata(&descriptor),
+            changelog,
+        },
+    )))
+}
+
+fn read_file(current_dir: &Path, revision: &str, path: &Path) -> Result<Option<String>> {
+    read_file_at_revision(current_dir, revision, path)
+        .map_err(|e| Error::ReadingFileAtRevision(object_name(revision, path), e))
+}
+
+fn object_name(revision: &str, path: &Path) -> String {
+    format!("{revision}:{}", path.display())
+}
+
+fn diff_snapshots(
+    id: BuildpackId,
+    from: Option<&BuildpackSnapshot>,
+    to: &BuildpackSnapshot,
+) -> BuildpackDiff {
+    let image = |snapshot: &BuildpackSnapshot| {
+        snapshot
+            .image_repository
+            .as_ref()
+            .map(|repository| ImageDiff {
+                reference: format!("{repository}:{}", snapshot.version),
+                digest: None,
+            })
+    };
+
+    BuildpackDiff {
+        id,
+        from_version: from.map(|snapshot| snapshot.version.clone()),
+        to_version: to.version.clone(),
+        from_image: from.and_then(image),
+        to_image: image(to),
+        releases: new_releases(
+            from.and_then(|snapshot| snapshot.changelog.as_ref()),
+            to.changelog.as_ref(),
+        ),
+    }
+}
+
+// Releases listed in the newer changelog that weren't in the older one, newest first.
+fn new_releases(from: Option<&Changelog>, to: Option<&Changelog>) -> Vec<ReleaseEntry> {
+    to.map(|to| {
+        to.releases
+            .iter()
+            .filter(|(version, _)| from.map_or(true, |from| !from.releases.contains_key(*version)))
+            .map(|(_, release)| release.clone())
+            .collect()
+    })
+    .unwrap_or_default()
+}
+
+// Images that were never published (or have since been deleted) are reported without a digest
+// rather than failing the whole report.
+fn resolve_image_digests(diffs: &mut [BuildpackDiff]) -> Result<()> {
+    let mut images = diffs
+        .iter_mut()
+        .flat_map(|diff| [diff.from_image.as_mut(), diff.to_image.as_mut()])
+        .flatten()
+        .collect::<Vec<_>>();
+
+    let references = images
+        .iter()
+        .map(|image| image.reference.clone())
+        .collect::<Vec<_>>();
+
+    let digests = try_map_concurrently(&references, MAX_CONCURRENT_REQUESTS, |reference| {
+        match calculate_digest(reference) {
+            Ok(digest) => Ok(Some(digest)),
+            Err(CalculateDigestError::ExitStatus(..)) => Ok(None),
+            Err(e) => Err(e),
+        }
+    })
+    .map_err(Error::CalculateDigest)?;
+
+    for (image, digest) in images.iter_mut().zip(digests) {
+        image.digest = digest;
+    }
+
+    Ok(())
+}
+
+fn render_report(from: &str, to: &str, diffs: &[BuildpackDiff]) -> String {
+    let sections = diffs
+        .iter()
+        .map(|diff| {
+            let version = match &diff.from_version {
+                None => format!("- Version: `{}` (new)", diff.to_version),
+                Some(from_version) if *from_version == diff.to_version => {
+                    format!("- Version: `{from_version}` (unchanged)")
+                }
+                Some(from_version) => {
+                    format!("- Version: `{from_version}` → `{}`", diff.to_version)
+                }
+            };
+
+            let image = diff.to_image.as_ref().map(|to_image| {
+                let from_digest = diff
+                    .from_image
+                    .as_ref()
+                    .and_then(|from_image| from_image.digest.as_deref());
+                let digest = match (from_digest, to_image.digest.as_deref()) {
+                    (Some(from_digest), Some(to_digest)) if from_digest == to_digest => {
+                        format!("`{to_digest}` (unchanged)")
+                    }
+                    (from_digest, to_digest) => {
+                        format!(
+                            "{} → {}",
+                            format_digest(from_digest),
+                            format_digest(to_digest)
+                        )
+                    }
+                };
+                format!(
+                    "- Image: `{}`\n- Image digest: {digest}",
+                    to_image.reference
+                )
+            });
+
+            let releases = diff
+                .releases
+                .iter()
+                .map(|release| format!("#### {}\n\n{}", release.version, release.body))
+                .collect::<Vec<_>>();
+
+            [format!("### {}", diff.id)]
+                .into_iter()
+                .chain([[Some(version), image]
+                    .into_iter()
+                    .flatten()
+                    .collect::<Vec<_>>()
+                    .join("\n")])
+                .chain(if releases.is_empty() {
+                    vec!["No changelog entries.".to_string()]
+                } else {
+                    releases
+                })
+                .collect::<Vec<_>>()
+                .join("\n\n")
+        })
+        .collect::<Vec<_>>();
+
+    [format!("## Changes from `{from}` to `{to}`")]
+        .into_iter()
+        .chain(sections)
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+fn format_digest(digest: Option<&str>) -> String {
+    digest.map_or("unknown".to_string(), |digest| format!("`{digest}`"))
+}
+
+#[cfg(test)]
+mod test {
+    use crate::changelog::Changelog;
+    use crate::commands::diff_release::command::{
+        new_releases, render_report, BuildpackDiff, ImageDiff,
+    };
+    use libcnb_data::buildpack::BuildpackVersion;
+    use libcnb_data::buildpack_id;
+
+    #[test]
+    fn test_new_releases() {
+        let from =
+            Changelog::try_from("## [Unreleased]\n\n## [3.1.0] - 2024-01-01\n\n- Foo.\n").unwrap();
+        let to = Changelog::try_from(
+            "## [Unreleased]\n\n## [3.2.0] - 2024-02-01\n\n- Bar.\n\n## [3.1.0] - 2024-01-01\n\n- Foo.\n",
+        )
+        .unwrap();
+
+        let releases = new_releases(Some(&from), Some(&to));
+        assert_eq!(
+            releases
+                .iter()
+                .map(|release| release.version.to_string())
+                .collect::<Vec<_>>(),
+            vec!["3.2.0"]
+        );
+        assert_eq!(new_releases(None, Some(&to)).len(), 2);
+        assert!(new_releases(Some(&from), None).is_empty());
+    }
+
+    #[test]
+    fn test_render_report() {
+        let diffs = vec![
+            BuildpackDiff {
+                id: buildpack_id!("heroku/java"),
+                from_version: Some(BuildpackVersion::new(3, 1, 0)),
+                to_version: BuildpackVersion::new(3, 2, 0),
+                from_image: Some(ImageDiff {
+                    reference: "docker.io/heroku/buildpack-java:3.1.0".to_string(),
+                    digest: Some("sha256:aaa".to_string()),
+                }),
+                to_image: Some(ImageDiff {
+                    reference: "docker.io/heroku/buildpack-java:3.2.0".to_string(),
+                    digest: None,
+                }),
+                releases: Changelog::try_from("## [3.2.0] - 2024-02-01\n\n- Bar.\n")
+                    .unwrap()
+                    .releases
+                    .into_values()
+                    .collect(),
+            },
+            BuildpackDiff {
+                id: buildpack_id!("heroku/maven"),
+                from_version: None,
+                to_version: BuildpackVersion::new(3, 2, 0),
+                from_image: None,
+                to_image: None,
+                releases: vec![],
+            },
+        ];
+
+        assert_eq!(
+            render_report("v3.1.0", "v3.2.0", &diffs),
+            r"## Changes from `v3.1.0` to `v3.2.0`
+
+### heroku/java
+
+- Version: `3.1.0` → `3.2.0`
+- Image: `docker.io/heroku/buildpack-java:3.2.0`
+- Image digest: `sha256:aaa` → unknown
+
+#### 3.2.0
+
+- Bar.
+
+### heroku/maven
+
+- Version: `3.2.0` (new)
+
+No changelog entries."
+        );
+    }
+}
@@ -0,0 +1,24 @@
+use crate::buildpacks::{CalculateDigestError, FindReleasableBuildpacksError};
+use crate::changelog::ChangelogError;
+use crate::git::GitError;
+use crate::github::actions::WriteActionDataError;
+
+#[derive(Debug, thiserror::Error)]
+pub(crate) enum Error {
+    #[error("Failed to get current directory\nError: {0}")]
+    GetCurrentDir(std::io::Error),
+    #[error("Could not resolve revision `{0}`\nError: {1}")]
+    ResolvingRevision(String, #[source] GitError),
+    #[error(transparent)]
+    FindReleasableBuildpacks(FindReleasableBuildpacksError),
+    #[error("Could not read file\nPath: {0}\nError: {1}")]
+    ReadingFileAtRevision(String, #[source] GitError),
+    #[error("Could not parse buildpack descriptor\nPath: {0}\nError: {1}")]
+    ParsingBuildpackDescriptor(String, #[source] toml::de::Error),
+    #[error("Could not parse changelog\nPath: {0}\nError: {1}")]
+    ParsingChangelog(String, #[source] ChangelogError),
+    #[error(transparent)]
+    CalculateDigest(CalculateDigestError),
+    #[error(transparent)]
+    SetActionOutput(WriteActionDataError),
+}
@@ -0,0 +1,306 @@
+use crate::builders::{
+    builder_file_path, read_builder_buildpack_ids, read_builder_file, resolve_builders,
+};
+use crate::buildpacks::{
+    find_releasable_buildpacks, read_buildpack_descriptor, read_buildpack_targets,
+    read_image_repository_metadata, read_owner_metadata, verify_manifest_exists, BuildpackOwner,
+    VerifyManifestError,
+};
+use crate::commands::export_catalog::errors::Error;
+use crate::commands::resolve_path;
+use crate::concurrency::{try_map_concurrently, MAX_CONCURRENT_REQUESTS};
+use crate::github::actions;
+use clap::Parser;
+use globset::Glob;
+use libcnb_data::buildpack::BuildpackDescriptor;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashSet};
+use std::path::{Path, PathBuf};
+
+type Result<T> = std::result::Result<T, Error>;
+
+#[derive(Parser, Debug)]
+#[command(author, version, about = "Exports a JSON catalog of every buildpack with its metadata, targets, images, and builders", long_about = None)]
+pub(crate) struct ExportCatalogArgs {
+    #[arg(long)]
+    pub(crate) source_dir: Option<PathBuf>,
+    /// Checks the registry for the image of each buildpack's current version.
+    #[arg(long)]
+    pub(crate) check_registry: bool,
+    #[arg(long, requires = "builder_list")]
+    pub(crate) builder_repository_path: Option<PathBuf>,
+    #[arg(long, group = "builder_list", requires = "builder_repository_path", value_delimiter = ',', num_args = 1..)]
+    pub(crate) builders: Vec<String>,
+    /// Reads the builders from a TOML file with a `[[builders]]` entry (with a `name`) for each
+    /// one, instead of `--builders`.
+    #[arg(long, group = "builder_list", requires = "builder_repository_path")]
+    pub(crate) builders_file: Option<PathBuf>,
+    /// Leaves out buildpacks matching the given glob, by buildpack id or directory.
+    #[arg(long)]
+    pub(crate) exclude: Vec<Glob>,
+    /// Writes the catalog to this file instead of setting the `catalog` output.
+    #[arg(long)]
+    pub(crate) output_file: Option<PathBuf>,
+}
+
+#[derive(Debug, Eq, PartialEq, Serialize)]
+pub(crate) struct CatalogEntry {
+    buildpack_id: String,
+    name: Option<String>,
+    version: String,
+    buildpack_dir: PathBuf,
+    kind: BuildpackKind,
+    targets: Vec<String>,
+    image_repository: Option<String>,
+    image: Option<String>,
+    owner: Option<BuildpackOwner>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    published: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    builders: Option<Vec<String>>,
+}
+
+#[derive(Debug, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum BuildpackKind {
+    Component,
+    Composite,
+}
+
+pub(crate) fn execute(args: &ExportCatalogArgs) -> Result<()> {
+    let source_dir = match &args.source_dir {
+        Some(path) => path.clone(),
+        None => std::env::current_dir().map_err(Error::GetCurrentDir)?,
+    };
+
+    let mut catalog = find_releasable_buildpacks(&source_dir, &args.exclude)
+        .map_err(Error::FindReleasableBuildpacks)?
+        .iter()
+        .map(|buildpack_dir| {
+            read_buildpack_descriptor(buildpack_dir)
+                .map_err(Error::ReadBuildpackDescriptor)
+                .map(|descriptor| catalog_entry(&descriptor, buildpack_dir, &source_dir))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    catalog.sort_by(|a, b| a.buildpack_id.cmp(&b.buildpack_id));
+
+    if args.check_registry {
+        add_registry_status(&mut catalog)?;
+    }
+
+    if let Some(builder_repository_path) = &args.builder_repository_path {
+        let builders = resolve_builders(
+            &args.builders,
+            args.builders_file
+                .as_deref()
+                .map(|builders_file| resolve_path(builders_file, &source_dir))
+                .as_deref(),
+        )
+        .map_err(Error::ReadBuildersFile)?;
+        add_builder_membership(
+            &mut catalog,
+            &resolve_path(builder_repository_path, &source_dir),
+            &builders,
+        )?;
+    }
+
+    let catalog_json = serde_json::to_string_pretty(&catalog).map_err(Error::SerializingJson)?;
+
+    match &args.output_file {
+        Some(output_file) => {
+            let output_file = resolve_path(output_file, &source_dir);
+            std::fs::write(&output_file, format!("{catalog_json}\n"))
+                .map_err(|e| Error::WritingCatalog(output_file.clone(), e))?;
+            eprintln!("✅️ Wrote catalog: {}", output_file.display());
+        }
+        None => actions::set_output("catalog", catalog_json).map_err(Error::WriteActionData)?,
+    }
+
+    Ok(())
+}
+
+fn catalog_entry(
+    buildpack_descriptor: &BuildpackDescriptor,
+    buildpack_dir: &Path,
+    source_dir: &Path,
+) -> CatalogEntry {
+    let buildpack = buildpack_descriptor.buildpack();
+    let image_repository = read_image_repository_metadata(buildpack_descriptor);
+    CatalogEntry {
+        buildpack_id: buildpack.id.to_string(),
+        name: buildpack.name.clone(),
+        version: buildpack.version.to_string(),
+        buildpack_dir: buildpack_dir
+            .strip_prefix(source_dir)
+            .unwrap_or(buildpack_dir)
+            .to_path_buf(),
+        kind: match buildpack_descriptor {
+            BuildpackDescriptor::Component(_) => BuildpackKind::Component,
+            BuildpackDescriptor::Composite(_) => BuildpackKind::Composite,
+        },
+        targets: read_buildpack_targets(buildpack_descriptor)
+            .iter()
+            .filter_map(|target| match (&target.os, &target.arch) {
+                (Some(os), Some(arch)) => Some(format!("{os}/{arch}")),
+                (Some(os), None) => Some(os.clone()),
+                (None, _) => None,
+            })
+            .collect(),
+        image: image_repository
+            .as_ref()
+            .map(|repository| format!("{repository}:{}", buildpack.version)),
+        image_repository,
+        owner: read_owner_metadata(buildpack_descriptor),
+        published: None,
+        builders: None,
+    }
+}
+
+// A buildpack is published once the image for its current version exists. Buildpacks
+// without an image repository are left as `null` since there's nothing to look up.
+fn add_registry_status(catalog: &mut [CatalogEntry]) -> Result<()> {
+    let published = try_map_concurrently(catalog, MAX_CONCURRENT_REQUESTS, |entry| {
+        entry
+            .image
+            .as_ref()
+            .map(|image| match verify_manifest_exists(image) {
+                Ok(()) => Ok(true),
+                Err(VerifyManifestError::ExitStatus(_, _)) => Ok(false),
+                Err(e) => Err(Error::VerifyManifest(e)),
+            })
+            .transpose()
+    })?;
+
+    for (entry, published) in catalog.iter_mut().zip(published) {
+        entry.published = published;
+    }
+
+    Ok(())
+}
+
+fn add_builder_membership(
+    catalog: &mut [CatalogEntry],
+    builder_repository_path: &Path,
+    builders: &[String],
+) -> Result<()> {
+    let builder_buildpack_ids = builders
+        .iter()
+        .map(|builder| {
+            read_builder_file(builder_file_path(builder_repository_path, builder))
+                .map_err(Error::ReadBuilderFile)
+                .map(|builder_file| {
+                    (
+                        builder.clone(),
+                        read_builder_buildpack_ids(&builder_file.document),
+                    )
+                })
+        })
+        .collect::<Result<BTreeMap<_, _>>>()?;
+
+    for entry in catalog {
+        entry.builders = Some(builders_containing(
+            &builder_buildpack_ids,
+            &entry.buildpack_id,
+        ));
+    }
+
+    Ok(())
+}
+
+fn builders_containing(
+    builder_buildpack_ids: &BTreeMap<String, HashSet<String>>,
+    buildpack_id: &str,
+) -> Vec<String> {
+    builder_buildpack_ids
+        .iter()
+        .filter(|(_, buildpack_ids)| buildpack_ids.contains(buildpack_id))
+        .map(|(builder, _)| builder.clone())
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use crate::buildpacks::BuildpackOwner;
+    use crate::commands::export_catalog::command::{
+        builders_containing, catalog_entry, BuildpackKind, CatalogEntry,
+    };
+    use libcnb_data::buildpack::BuildpackDescriptor;
+    use std::collections::{BTreeMap, HashSet};
+    use std::path::{Path, PathBuf};
+
+    #[test]
+    fn test_catalog_entry() {
+        let descriptor = toml::from_str::<BuildpackDescriptor>(
+            r#"
+api = "0.10"
+
+[buildpack]
+id = "heroku/nodejs"
+name = "Heroku Node.js"
+version = "3.2.1"
+
+[[order]]
+[[order.group]]
+id = "heroku/nodejs-engine"
+version = "3.2.1"
+
+[metadata.release]
+image = { repository = "docker.io/heroku/buildpack-nodejs" }
+
+[metadata.release.owner]
+team = "languages"
+
+[[metadata.targets]]
+os = "linux"
+arch = "arm64"
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            catalog_entry(
+                &descriptor,
+                Path::new("/repo/buildpacks/nodejs"),
+                Path::new("/repo")
+            ),
+            CatalogEntry {
+                buildpack_id: "heroku/nodejs".to_string(),
+                name: Some("Heroku Node.js".to_string()),
+                version: "3.2.1".to_string(),
+                buildpack_dir: PathBuf::from("buildpacks/nodejs"),
+                kind: BuildpackKind::Composite,
+                targets: vec!["linux/arm64".to_string()],
+                image_repository: Some("docker.io/heroku/buildpack-nodejs".to_string()),
+                image: Some("docker.io/heroku/buildpack-nodejs:3.2.1".to_string()),
+                owner: Some(BuildpackOwner {
+                    team: "languages".to_string(),
+                    language: None
+                }),
+                published: None,
+                builders: None,
+            }
+        );
+    }
+
+    #[test]
+    fn test_builders_containing() {
+        let builder_buildpack_ids = BTreeMap::from([
+            (
+                "builder-22".to_string(),
+                HashSet::from(["heroku/java".to_string(), "heroku/nodejs".to_string()]),
+            ),
+            (
+                "salesforce-functions".to_string(),
+                HashSet::from(["heroku/java".to_string()]),
+            ),
+        ]);
+        assert_eq!(
+            builders_containing(&builder_buildpack_ids, "heroku/java"),
+            vec!["builder-22", "salesforce-functions"]
+        );
+        assert_eq!(
+            builders_containing(&builder_buildpack_ids, "heroku/go"),
+            Vec::<String>::new()
+        );
+    }
+}
@@ -0,0 +1,28 @@
+use crate::builders::{ReadBuilderFileError, ReadBuildersFileError};
+use crate::buildpacks::{
+    FindReleasableBuildpacksError, ReadBuildpackDescriptorError, VerifyManifestError,
+};
+use crate::github::actions::WriteActionDataError;
+use std::path::PathBuf;
+
+#[derive(Debug, thiserror::Error)]
+pub(crate) enum Error {
+    #[error("Failed to get current directory\nError: {0}")]
+    GetCurrentDir(std::io::Error),
+    #[error(transparent)]
+    FindReleasableBuildpacks(FindReleasableBuildpacksError),
+    #[error(transparent)]
+    ReadBuildpackDescriptor(ReadBuildpackDescriptorError),
+    #[error(transparent)]
+    ReadBuilderFile(ReadBuilderFileError),
+    #[error(transparent)]
+    ReadBuildersFile(ReadBuildersFileError),
+    #[error(transparent)]
+    VerifyManifest(VerifyManifestError),
+    #[error("Could not serialize catalog into json\nError: {0}")]
+    SerializingJson(#[source] serde_json::Error),
+    #[error("Could not write catalog\nPath: {}\nError: {}", .0.display(), .1)]
+    WritingCatalog(PathBuf, #[source] std::io::Error),
+    #[error(transparent)]
+    WriteActionData(WriteActionDataError),
+}
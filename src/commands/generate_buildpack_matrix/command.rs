@@ -1,23 +1,48 @@
+use crate::builders::{
+    builder_file_path, read_builder_buildpack_ids, read_builder_file, resolve_builders,
+};
 use crate::buildpacks::{
-    find_releasable_buildpacks, read_buildpack_descriptor, read_image_repository_metadata,
+    buildpack_changelog_path, descriptor_path, find_releasable_buildpacks, find_skipped_buildpacks,
+    is_extension_dir, is_registered_in_cnb_registry, read_buildpack_descriptor,
+    read_buildpack_targets, read_image_repository_metadata,
+    read_image_target_repositories_metadata, read_owner_metadata,
+    validate_image_repository_metadata, BuildpackOwner,
 };
+use crate::changelog::Changelog;
 use crate::commands::generate_buildpack_matrix::errors::Error;
 use crate::commands::resolve_path;
+use crate::concurrency::{try_map_concurrently, MAX_CONCURRENT_REQUESTS};
+use crate::git::{changed_files_since, current_branch, resolve_revision, top_level_dir};
 use crate::github::actions;
-use clap::Parser;
+use clap::{Parser, ValueEnum};
+use globset::Glob;
+use lazy_static::lazy_static;
 use libcnb_data::buildpack::{BuildpackDescriptor, BuildpackId, BuildpackTarget};
-use libcnb_data::generic::GenericMetadata;
 use libcnb_package::output::{
     create_packaged_buildpack_dir_resolver, default_buildpack_directory_name,
 };
 use libcnb_package::CargoProfile;
+use regex::Regex;
+use semver::Version;
 use serde::Serialize;
-use std::collections::HashSet;
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::num::NonZeroUsize;
 use std::path::{Path, PathBuf};
+use toml::Value;
 
 type Result<T> = std::result::Result<T, Error>;
 
+lazy_static! {
+    // Private ECR repositories look like `<registry id>.dkr.ecr.<region>.amazonaws.com/<name>`.
+    static ref ECR_REPOSITORY: Regex = Regex::new(
+        r"^(?P<registry_id>\d{12})\.dkr\.ecr(?:-fips)?\.(?P<region>[a-z0-9-]+)\.amazonaws\.com(?:\.cn)?/"
+    )
+    .expect("ECR repository regex should compile");
+}
+
 #[derive(Parser, Debug)]
+#[allow(clippy::struct_excessive_bools)]
 #[command(author, version, about = "Generates a JSON list of buildpack information for each buildpack detected", long_about = None)]
 pub(crate) struct GenerateBuildpackMatrixArgs {
     #[arg(long)]
@@ -26,6 +51,108 @@ pub(crate) struct GenerateBuildpackMatrixArgs {
     pub(crate) package_dir: Option<PathBuf>,
     #[arg(long)]
     pub(crate) temporary_id: String,
+    #[arg(long, value_enum)]
+    pub(crate) group_by: Option<GroupBy>,
+    #[arg(long, value_enum, num_args = 0..=1, default_missing_value = "packaged")]
+    pub(crate) require_artifacts: Option<RequiredArtifacts>,
+    /// Only checks the artifacts required by --require-artifacts, without setting any outputs or
+    /// writing the summary (e.g.; to verify the packaged buildpacks once the matrix is generated).
+    #[arg(long, requires = "require_artifacts")]
+    pub(crate) verify_artifacts_only: bool,
+    #[arg(long)]
+    pub(crate) strict_image_repository: bool,
+    #[arg(long, requires = "builder_list")]
+    pub(crate) builder_repository_path: Option<PathBuf>,
+    #[arg(long, group = "builder_list", requires = "builder_repository_path", value_delimiter = ',', num_args = 1..)]
+    pub(crate) builders: Vec<String>,
+    /// Reads the builders from a TOML file with a `[[builders]]` entry (with a `name`) for each
+    /// one, instead of `--builders`.
+    #[arg(long, group = "builder_list", requires = "builder_repository_path")]
+    pub(crate) builders_file: Option<PathBuf>,
+    /// Leaves out buildpacks matching the given glob, by buildpack id or directory.
+    #[arg(long)]
+    pub(crate) exclude: Vec<Glob>,
+    /// Lists the component buildpacks of each composite buildpack under `dependencies`.
+    #[arg(long)]
+    pub(crate) include_dependencies: bool,
+    /// Only includes buildpacks that changed since the given git ref (e.g.; the base branch of
+    /// a pull request), including changes to local crates they depend on.
+    #[arg(long)]
+    pub(crate) changed_since: Option<String>,
+    /// Also splits the buildpacks into `buildpacks_0`, `buildpacks_1`, … outputs of at most this
+    /// many entries each, listed by index in the `chunks` output, to stay under the 256 job
+    /// limit of a GitHub Actions matrix.
+    #[arg(long)]
+    pub(crate) max_entries_per_chunk: Option<NonZeroUsize>,
+    /// Sets `publish` on every buildpack and target, to whether the workflow runs for a ref
+    /// matching one of these rules, given as `branch:<glob>` or `tag:<glob>` (e.g.; `branch:main`).
+    /// Pull requests never publish.
+    #[arg(long, value_parser = parse_publish_rule)]
+    pub(crate) publish_on: Vec<PublishRule>,
+    /// Also writes the `buildpacks` output to this file (e.g.; to upload as an artifact).
+    #[arg(long)]
+    pub(crate) output_file: Option<PathBuf>,
+    /// Sets `registered` on every buildpack to whether its current version is already in the CNB
+    /// registry index.
+    #[arg(long)]
+    pub(crate) check_registry: bool,
+    /// What to do when a buildpack can't be read (e.g.; a malformed buildpack.toml). With `skip`,
+    /// the buildpack is left out of the matrix, annotated with the error, and listed in the
+    /// `skipped_due_to_errors` output.
+    #[arg(long, value_enum, default_value_t = OnError::Fail)]
+    pub(crate) on_error: OnError,
+}
+
+#[derive(ValueEnum, Debug, Clone)]
+pub(crate) enum GroupBy {
+    Owner,
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy, Eq, PartialEq)]
+pub(crate) enum OnError {
+    /// Stops generating the matrix at the first buildpack that can't be read
+    Fail,
+    /// Leaves out buildpacks that can't be read and generates the matrix for the rest
+    Skip,
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy, Eq, PartialEq)]
+pub(crate) enum RefKind {
+    Branch,
+    Tag,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct PublishRule {
+    kind: RefKind,
+    pattern: Glob,
+}
+
+fn parse_publish_rule(value: &str) -> std::result::Result<PublishRule, String> {
+    let (kind, pattern) = value
+        .split_once(':')
+        .ok_or("expected `branch:<glob>` or `tag:<glob>`".to_string())?;
+    let kind = RefKind::from_str(kind.trim(), true)?;
+    let pattern = Glob::new(pattern.trim()).map_err(|e| e.to_string())?;
+    Ok(PublishRule { kind, pattern })
+}
+
+// The ref a workflow runs for, as described by the `GITHUB_EVENT_NAME` and `GITHUB_REF`
+// variables of GitHub Actions.
+#[derive(Debug, Eq, PartialEq)]
+enum WorkflowRef {
+    Branch(String),
+    Tag(String),
+    PullRequest,
+    Unknown,
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy, Eq, PartialEq)]
+pub(crate) enum RequiredArtifacts {
+    /// Each target's packaged output directory must exist and be non-empty
+    Packaged,
+    /// Same as `packaged`, and each target's .cnb file must also exist and be non-empty
+    CnbFiles,
 }
 
 pub(crate) fn execute(args: &GenerateBuildpackMatrixArgs) -> Result<()> {
@@ -41,36 +168,245 @@ pub(crate) fn execute(args: &GenerateBuildpackMatrixArgs) -> Result<()> {
         &source_dir,
     );
 
-    let buildpack_dirs =
-        find_releasable_buildpacks(&source_dir).map_err(Error::FindReleasableBuildpacks)?;
+    let (buildpack_dirs, buildpacks, mut buildpacks_info) =
+        read_buildpacks(args, &source_dir, &package_dir)?;
 
-    let buildpacks = buildpack_dirs
-        .iter()
-        .map(|dir| read_buildpack_descriptor(dir).map_err(Error::ReadBuildpackDescriptor))
-        .collect::<Result<Vec<_>>>()?;
+    if args.verify_artifacts_only {
+        return check_required_artifacts(&buildpacks_info, &source_dir, args.require_artifacts);
+    }
+
+    add_git_metadata(&mut buildpacks_info, &source_dir);
+
+    if args.include_dependencies {
+        add_buildpack_dependencies(&mut buildpacks_info, &buildpacks);
+    }
+
+    if let Some(changed_since) = &args.changed_since {
+        retain_changed_buildpacks(
+            &mut buildpacks_info,
+            changed_since,
+            &source_dir,
+            &buildpack_dirs,
+            &buildpacks,
+        )?;
+    }
+
+    if !args.publish_on.is_empty() {
+        set_publish_flags(&mut buildpacks_info, &args.publish_on)?;
+    }
+
+    if args.check_registry {
+        add_registry_status(&mut buildpacks_info)?;
+    }
+
+    if let Some(builder_repository_path) = &args.builder_repository_path {
+        let builders = read_builders(args, &source_dir)?;
+        let affected_builders = add_affected_builders(
+            &mut buildpacks_info,
+            &resolve_path(builder_repository_path, &source_dir),
+            &builders,
+        )?;
+        actions::set_output(
+            "affected_builders",
+            serde_json::to_string(&affected_builders).map_err(Error::SerializingJson)?,
+        )
+        .map_err(Error::WriteActionData)?;
+    }
 
-    let buildpacks_info = buildpack_dirs
+    check_required_artifacts(&buildpacks_info, &source_dir, args.require_artifacts)?;
+
+    set_buildpacks_outputs(&buildpacks_info, args.max_entries_per_chunk)?;
+    if let Some(output_file) = &args.output_file {
+        write_matrix_file(&resolve_path(output_file, &source_dir), &buildpacks_info)?;
+    }
+
+    if let Some(GroupBy::Owner) = args.group_by {
+        actions::set_output(
+            "buildpacks_by_owner",
+            serde_json::to_string(&group_by_owner(&buildpacks_info))
+                .map_err(Error::SerializingJson)?,
+        )
+        .map_err(Error::WriteActionData)?;
+    }
+
+    set_project_outputs(&source_dir, &buildpacks)
+}
+
+// Reads the descriptor and matrix entry of every releasable buildpack, leaving out the ones that
+// can't be read with `--on-error skip` so one broken buildpack doesn't block the others.
+fn read_buildpacks(
+    args: &GenerateBuildpackMatrixArgs,
+    source_dir: &Path,
+    package_dir: &Path,
+) -> Result<(Vec<PathBuf>, Vec<BuildpackDescriptor>, Vec<BuildpackInfo>)> {
+    let mut buildpack_dirs = vec![];
+    let mut buildpacks = vec![];
+    let mut buildpacks_info = vec![];
+    let mut skipped_due_to_errors = vec![];
+
+    for buildpack_dir in find_releasable_buildpacks(source_dir, &args.exclude)
+        .map_err(Error::FindReleasableBuildpacks)?
+    {
+        match read_buildpack(args, &buildpack_dir, package_dir) {
+            Ok((descriptor, info)) => {
+                buildpack_dirs.push(buildpack_dir);
+                buildpacks.push(descriptor);
+                buildpacks_info.push(info);
+            }
+            Err(error) if args.on_error == OnError::Skip => {
+                let relative_dir = buildpack_dir
+                    .strip_prefix(source_dir)
+                    .unwrap_or(&buildpack_dir);
+                eprintln!(
+                    "⚠️ Skipping buildpack that couldn't be read: {}\n{error}",
+                    relative_dir.display()
+                );
+                actions::annotate_error(
+                    &descriptor_path(relative_dir),
+                    "Buildpack left out of the matrix",
+                    &error.to_string(),
+                );
+                skipped_due_to_errors.push(relative_dir.display().to_string());
+            }
+            Err(error) => return Err(error),
+        }
+    }
+
+    if !args.verify_artifacts_only {
+        actions::set_output(
+            "skipped_due_to_errors",
+            serde_json::to_string(&skipped_due_to_errors).map_err(Error::SerializingJson)?,
+        )
+        .map_err(Error::WriteActionData)?;
+    }
+
+    Ok((buildpack_dirs, buildpacks, buildpacks_info))
+}
+
+fn check_required_artifacts(
+    buildpacks_info: &[BuildpackInfo],
+    source_dir: &Path,
+    required_artifacts: Option<RequiredArtifacts>,
+) -> Result<()> {
+    if let Some(required_artifacts) = required_artifacts {
+        let missing_artifacts =
+            find_missing_artifacts(buildpacks_info, source_dir, required_artifacts);
+        if !missing_artifacts.is_empty() {
+            Err(Error::MissingArtifacts(missing_artifacts))?;
+        }
+    }
+    Ok(())
+}
+
+fn read_buildpack(
+    args: &GenerateBuildpackMatrixArgs,
+    buildpack_dir: &Path,
+    package_dir: &Path,
+) -> Result<(BuildpackDescriptor, BuildpackInfo)> {
+    let buildpack_descriptor =
+        read_buildpack_descriptor(buildpack_dir).map_err(Error::ReadBuildpackDescriptor)?;
+    if args.strict_image_repository {
+        validate_image_repository_metadata(buildpack_dir, &buildpack_descriptor)
+            .map_err(Error::InvalidImageRepository)?;
+    }
+    let buildpack_info = read_buildpack_info(
+        &buildpack_descriptor,
+        buildpack_dir,
+        package_dir,
+        &args.temporary_id,
+    )?;
+    Ok((buildpack_descriptor, buildpack_info))
+}
+
+// Outputs that describe every buildpack in the project, regardless of which ones are in the
+// matrix.
+fn set_project_outputs(source_dir: &Path, buildpacks: &[BuildpackDescriptor]) -> Result<()> {
+    let skipped_buildpacks = read_skipped_buildpack_ids(source_dir)?;
+    actions::set_output(
+        "skipped_buildpacks",
+        serde_json::to_string(&skipped_buildpacks).map_err(Error::SerializingJson)?,
+    )
+    .map_err(Error::WriteActionData)?;
+
+    actions::set_output(
+        "dependency_graph",
+        serde_json::to_string(&dependency_graph(buildpacks)).map_err(Error::SerializingJson)?,
+    )
+    .map_err(Error::WriteActionData)?;
+
+    let version = read_fixed_version(buildpacks)?;
+    actions::set_output("version", version).map_err(Error::WriteActionData)?;
+
+    let rust_triples = buildpacks
         .iter()
-        .zip(buildpacks.iter())
-        .map(|(buildpack_dir, buildpack_descriptor)| {
-            read_buildpack_info(
-                buildpack_descriptor,
-                buildpack_dir,
-                &package_dir,
-                &args.temporary_id,
-            )
-        })
-        .collect::<Result<Vec<_>>>()?;
+        .flat_map(read_buildpack_targets)
+        .filter_map(|t| rust_triple(&t).ok())
+        .collect::<HashSet<String>>();
+
+    actions::set_output(
+        "rust_triples",
+        serde_json::to_string(&rust_triples).map_err(Error::SerializingJson)?,
+    )
+    .map_err(Error::WriteActionData)?;
 
+    Ok(())
+}
+
+fn set_buildpacks_outputs(
+    buildpacks_info: &[BuildpackInfo],
+    max_entries_per_chunk: Option<NonZeroUsize>,
+) -> Result<()> {
     let buildpacks_json =
         serde_json::to_string_pretty(&buildpacks_info).map_err(Error::SerializingJson)?;
 
     actions::set_output("buildpacks", &buildpacks_json).map_err(Error::WriteActionData)?;
+    if let Some(max_entries_per_chunk) = max_entries_per_chunk {
+        set_chunk_outputs(buildpacks_info, max_entries_per_chunk)?;
+    }
     actions::set_summary(format!(
         "<details><summary>Buildpack Matrix</summary>\n\n```json\n{buildpacks_json}\n```\n</details>"
     ))
-    .map_err(Error::WriteActionData)?;
+    .map_err(Error::WriteActionData)
+}
+
+fn write_matrix_file(path: &Path, buildpacks_info: &[BuildpackInfo]) -> Result<()> {
+    let buildpacks_json =
+        serde_json::to_string_pretty(&buildpacks_info).map_err(Error::SerializingJson)?;
+    std::fs::write(path, format!("{buildpacks_json}\n"))
+        .map_err(|e| Error::WritingMatrix(path.to_path_buf(), e))?;
+    eprintln!("✅️ Wrote matrix: {}", path.display());
+    Ok(())
+}
 
+// An empty matrix still gets a single (empty) chunk so workflows always have a chunk to read.
+fn set_chunk_outputs(
+    buildpacks_info: &[BuildpackInfo],
+    max_entries_per_chunk: NonZeroUsize,
+) -> Result<()> {
+    let chunks = chunk_entries(buildpacks_info, max_entries_per_chunk);
+    for (index, chunk) in chunks.iter().enumerate() {
+        actions::set_output(
+            format!("buildpacks_{index}"),
+            serde_json::to_string(chunk).map_err(Error::SerializingJson)?,
+        )
+        .map_err(Error::WriteActionData)?;
+    }
+    actions::set_output(
+        "chunks",
+        serde_json::to_string(&(0..chunks.len()).collect::<Vec<_>>())
+            .map_err(Error::SerializingJson)?,
+    )
+    .map_err(Error::WriteActionData)
+}
+
+fn chunk_entries<T>(entries: &[T], max_entries_per_chunk: NonZeroUsize) -> Vec<&[T]> {
+    if entries.is_empty() {
+        return vec![entries];
+    }
+    entries.chunks(max_entries_per_chunk.get()).collect()
+}
+
+fn read_fixed_version(buildpacks: &[BuildpackDescriptor]) -> Result<String> {
     let versions = buildpacks
         .iter()
         .map(|buildpack_descriptor| buildpack_descriptor.buildpack().version.to_string())
@@ -80,50 +416,108 @@ pub(crate) fn execute(args: &GenerateBuildpackMatrixArgs) -> Result<()> {
         Err(Error::FixedVersion(versions.clone()))?;
     }
 
-    let version = versions
+    versions
         .iter()
         .next()
-        .ok_or(Error::FixedVersion(versions.clone()))?;
-
-    actions::set_output("version", version).map_err(Error::WriteActionData)?;
+        .cloned()
+        .ok_or(Error::FixedVersion(versions.clone()))
+}
 
-    let rust_triples = buildpacks
+fn read_skipped_buildpack_ids(source_dir: &Path) -> Result<Vec<String>> {
+    let buildpack_ids = find_skipped_buildpacks(source_dir)
+        .map_err(Error::FindReleasableBuildpacks)?
         .iter()
-        .flat_map(read_buildpack_targets)
-        .filter_map(|t| rust_triple(&t).ok())
-        .collect::<HashSet<String>>();
+        .map(|dir| {
+            read_buildpack_descriptor(dir)
+                .map_err(Error::ReadBuildpackDescriptor)
+                .map(|buildpack_descriptor| buildpack_descriptor.buildpack().id.to_string())
+        })
+        .collect::<Result<Vec<_>>>()?;
 
-    actions::set_output(
-        "rust_triples",
-        serde_json::to_string(&rust_triples).map_err(Error::SerializingJson)?,
-    )
-    .map_err(Error::WriteActionData)?;
+    for buildpack_id in &buildpack_ids {
+        eprintln!("⚠️ Skipping buildpack with `[metadata.release] skip = true`: {buildpack_id}");
+    }
 
-    Ok(())
+    Ok(buildpack_ids)
 }
 
 #[derive(Serialize)]
 pub(crate) struct BuildpackInfo {
     buildpack_id: String,
     buildpack_version: String,
+    previous_version: Option<String>,
     buildpack_type: BuildpackType,
+    artifact_type: ArtifactType,
     buildpack_dir: PathBuf,
+    commit_sha: Option<String>,
+    source_ref: Option<String>,
+    source_url: Option<String>,
     targets: Vec<TargetInfo>,
     image_repository: String,
     stable_tag: String,
     temporary_tag: String,
+    aws_region: Option<String>,
+    ecr_registry_id: Option<String>,
+    owner: Option<BuildpackOwner>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    affected_builders: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    dependencies: Option<Vec<DependencyInfo>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    publish: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    registered: Option<bool>,
+    manifest: ManifestInfo,
+    requires_manifest: bool,
+}
+
+// What's needed to assemble the per-target images into a multi-arch manifest list.
+#[derive(Debug, Eq, PartialEq, Serialize)]
+pub(crate) struct ManifestInfo {
+    platforms: Vec<String>,
+    images: Vec<ManifestImageInfo>,
+}
+
+#[derive(Debug, Eq, PartialEq, Serialize)]
+pub(crate) struct ManifestImageInfo {
+    platform: String,
+    stable_tag: String,
+    temporary_tag: String,
+}
+
+#[derive(Debug, Eq, PartialEq, Serialize)]
+pub(crate) struct DependencyInfo {
+    buildpack_id: String,
+    buildpack_version: String,
+}
+
+#[derive(Debug, Eq, PartialEq, Serialize)]
+struct DependencyGraphNode {
+    buildpack_id: String,
+    dependencies: Vec<String>,
 }
 
 #[derive(Serialize)]
 pub(crate) struct TargetInfo {
     os: Option<String>,
     arch: Option<String>,
+    distros: Vec<DistroInfo>,
     rust_triple: Option<String>,
     oci_target: String,
     cnb_file: String,
+    image_repository: String,
     stable_tag: String,
     temporary_tag: String,
     output_dir: PathBuf,
+    cache_key_fingerprint: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    publish: Option<bool>,
+}
+
+#[derive(Debug, Eq, PartialEq, Serialize)]
+pub(crate) struct DistroInfo {
+    name: String,
+    version: String,
 }
 
 #[derive(Clone, Debug, PartialEq, Serialize)]
@@ -134,6 +528,13 @@ enum BuildpackType {
     Libcnb,
 }
 
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum ArtifactType {
+    Buildpack,
+    Extension,
+}
+
 pub(crate) fn read_buildpack_info(
     buildpack_descriptor: &BuildpackDescriptor,
     buildpack_dir: &Path,
@@ -142,78 +543,741 @@ pub(crate) fn read_buildpack_info(
 ) -> Result<BuildpackInfo> {
     let version = buildpack_descriptor.buildpack().version.to_string();
     let image_repository = read_image_repository_metadata(buildpack_descriptor).ok_or(
-        Error::MissingImageRepositoryMetadata(buildpack_dir.join("buildpack.toml")),
+        Error::MissingImageRepositoryMetadata(descriptor_path(buildpack_dir)),
     )?;
-    let targets = read_buildpack_targets(buildpack_descriptor);
+    let target_repositories = read_image_target_repositories_metadata(buildpack_descriptor);
+    let targets = expand_distro_targets(read_buildpack_targets(buildpack_descriptor));
     let buildpack_type = buildpack_type(buildpack_descriptor, buildpack_dir)?;
+    let cargo_lock_hash = cargo_lock_hash(buildpack_dir)?;
+    let buildpack_dir_hash = buildpack_dir_hash(buildpack_dir, package_dir)?;
+    let (ecr_registry_id, aws_region) = parse_ecr_repository(&image_repository).unzip();
+    let target_infos = targets
+        .iter()
+        .map(|(target, name)| {
+            let suffix = if targets.len() > 1 {
+                Some(name.clone())
+            } else {
+                None
+            };
+            let target_repository = target_repositories
+                .get(&oci_target(target))
+                .unwrap_or(&image_repository);
+            Ok(TargetInfo {
+                cnb_file: cnb_file(&buildpack_descriptor.buildpack().id, suffix.as_deref()),
+                os: target.os.clone(),
+                oci_target: oci_target(target),
+                arch: target.arch.clone(),
+                distros: target
+                    .distros
+                    .iter()
+                    .map(|distro| DistroInfo {
+                        name: distro.name.clone(),
+                        version: distro.version.clone(),
+                    })
+                    .collect(),
+                output_dir: target_output_dir(
+                    &buildpack_descriptor.buildpack().id,
+                    &buildpack_type,
+                    package_dir,
+                    target,
+                )?,
+                rust_triple: rust_triple(target).ok(),
+                cache_key_fingerprint: cache_key_fingerprint(&[
+                    &oci_target(target),
+                    &rust_triple(target).unwrap_or_default(),
+                    &cargo_lock_hash,
+                    &buildpack_dir_hash,
+                ]),
+                stable_tag: generate_tag(target_repository, &version, suffix.as_deref()),
+                temporary_tag: generate_tag(
+                    target_repository,
+                    &format!("_{temporary_id}"),
+                    suffix.as_deref(),
+                ),
+                image_repository: target_repository.clone(),
+                publish: None,
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+
     Ok(BuildpackInfo {
         buildpack_id: buildpack_descriptor.buildpack().id.to_string(),
         buildpack_version: version.clone(),
+        previous_version: read_previous_version(buildpack_dir, buildpack_descriptor)?,
         buildpack_dir: buildpack_dir.into(),
+        commit_sha: None,
+        source_ref: None,
+        source_url: None,
         buildpack_type: buildpack_type.clone(),
-        targets: read_buildpack_targets(buildpack_descriptor)
-            .iter()
-            .map(|target| {
-                let suffix = if targets.len() > 1 {
-                    Some(target_name(target))
-                } else {
-                    None
-                };
-                Ok(TargetInfo {
-                    cnb_file: cnb_file(&buildpack_descriptor.buildpack().id, suffix.as_deref()),
-                    os: target.os.clone(),
-                    oci_target: oci_target(target),
-                    arch: target.arch.clone(),
-                    output_dir: target_output_dir(
-                        &buildpack_descriptor.buildpack().id,
-                        &buildpack_type,
-                        package_dir,
-                        target,
-                    )?,
-                    rust_triple: rust_triple(target).ok(),
-                    stable_tag: generate_tag(&image_repository, &version, suffix.as_deref()),
-                    temporary_tag: generate_tag(
-                        &image_repository,
-                        &format!("_{temporary_id}"),
-                        suffix.as_deref(),
-                    ),
-                })
-            })
-            .collect::<Result<Vec<_>>>()?,
+        artifact_type: if is_extension_dir(buildpack_dir) {
+            ArtifactType::Extension
+        } else {
+            ArtifactType::Buildpack
+        },
+        manifest: manifest_info(&target_infos),
+        requires_manifest: target_infos.len() > 1,
+        targets: target_infos,
         stable_tag: generate_tag(&image_repository, &version, None),
         temporary_tag: generate_tag(&image_repository, &format!("_{temporary_id}"), None),
         image_repository,
+        aws_region,
+        ecr_registry_id,
+        owner: read_owner_metadata(buildpack_descriptor),
+        affected_builders: None,
+        dependencies: None,
+        publish: None,
+        registered: None,
     })
 }
 
-// Reads targets from buildpacks while ensuring each buildpack returns at least
-// one target (libcnb assumes a linux/amd64 target by default, even if no
-// targets are defined).
-fn read_buildpack_targets(buildpack_descriptor: &BuildpackDescriptor) -> Vec<BuildpackTarget> {
-    let mut targets = match buildpack_descriptor {
-        BuildpackDescriptor::Component(descriptor) => descriptor.targets.clone(),
-        BuildpackDescriptor::Composite(descriptor) => {
-            read_metadata_targets(descriptor.metadata.clone()).unwrap_or_default()
+// Where the buildpacks' sources come from, for image labels and provenance attestations.
+#[derive(Debug, Default)]
+struct GitMetadata {
+    commit_sha: Option<String>,
+    source_ref: Option<String>,
+    repository_url: Option<String>,
+    top_level_dir: Option<PathBuf>,
+}
+
+impl GitMetadata {
+    // Links to the buildpack's directory as of the commit, or the root of the repository for a
+    // buildpack at the top level.
+    fn source_url(&self, buildpack_dir: &Path) -> Option<String> {
+        let repository_url = self.repository_url.as_ref()?;
+        let commit_sha = self.commit_sha.as_ref()?;
+        let path = self
+            .top_level_dir
+            .as_ref()
+            .and_then(|top_level_dir| {
+                canonical_path(buildpack_dir)
+                    .strip_prefix(canonical_path(top_level_dir))
+                    .ok()
+                    .map(|path| {
+                        path.components()
+                            .map(|component| component.as_os_str().to_string_lossy())
+                            .collect::<Vec<_>>()
+                            .join("/")
+                    })
+            })
+            .unwrap_or_default();
+        if path.is_empty() {
+            Some(format!("{repository_url}/tree/{commit_sha}"))
+        } else {
+            Some(format!("{repository_url}/tree/{commit_sha}/{path}"))
         }
+    }
+}
+
+// GitHub Actions describes the commit a workflow runs for with its `GITHUB_*` variables. Outside
+// of a workflow the commit and branch are read from git instead, and are left out when the source
+// directory isn't in a git repository.
+fn read_git_metadata(source_dir: &Path, lookup: impl Fn(&str) -> Option<String>) -> GitMetadata {
+    let server_url = lookup("GITHUB_SERVER_URL").unwrap_or("https://github.com".to_string());
+    GitMetadata {
+        commit_sha: lookup("GITHUB_SHA").or_else(|| resolve_revision(source_dir, "HEAD").ok()),
+        source_ref: lookup("GITHUB_REF").or_else(|| {
+            current_branch(source_dir)
+                .ok()
+                .filter(|branch| branch != "HEAD")
+                .map(|branch| format!("refs/heads/{branch}"))
+        }),
+        repository_url: lookup("GITHUB_REPOSITORY")
+            .map(|repository| format!("{}/{repository}", server_url.trim_end_matches('/'))),
+        top_level_dir: top_level_dir(source_dir)
+            .ok()
+            .or_else(|| lookup("GITHUB_WORKSPACE").map(PathBuf::from)),
+    }
+}
+
+fn add_git_metadata(buildpacks_info: &mut [BuildpackInfo], source_dir: &Path) {
+    let git_metadata = read_git_metadata(source_dir, |name| std::env::var(name).ok());
+    for buildpack_info in buildpacks_info.iter_mut() {
+        buildpack_info
+            .commit_sha
+            .clone_from(&git_metadata.commit_sha);
+        buildpack_info
+            .source_ref
+            .clone_from(&git_metadata.source_ref);
+        buildpack_info.source_url = git_metadata.source_url(&buildpack_info.buildpack_dir);
+    }
+}
+
+// Buildpacks without a changelog (or without any releases in it) have no previous version.
+fn read_previous_version(
+    buildpack_dir: &Path,
+    buildpack_descriptor: &BuildpackDescriptor,
+) -> Result<Option<String>> {
+    let changelog_path = buildpack_changelog_path(buildpack_dir, buildpack_descriptor);
+    let contents = match std::fs::read_to_string(&changelog_path) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(Error::ReadingChangelog(changelog_path, e)),
     };
-    if targets.is_empty() {
-        targets.push(BuildpackTarget {
-            os: Some("linux".into()),
-            arch: Some("amd64".into()),
-            variant: None,
-            distros: vec![],
-        });
+    let changelog = Changelog::try_from(contents.as_str())
+        .map_err(|e| Error::ParsingChangelog(changelog_path, e))?;
+    Ok(previous_version(
+        &changelog,
+        &buildpack_descriptor.buildpack().version.to_string(),
+    ))
+}
+
+// The newest release older than the current version, so it's the same whether or not the current
+// version has been added to the changelog yet.
+fn previous_version(changelog: &Changelog, version: &str) -> Option<String> {
+    let version = Version::parse(version).ok()?;
+    changelog
+        .releases
+        .values()
+        .map(|release| &release.version)
+        .filter(|release_version| **release_version < version)
+        .max()
+        .map(ToString::to_string)
+}
+
+// Platforms are listed once each, in target order, as docker `--platform` values.
+fn manifest_info(target_infos: &[TargetInfo]) -> ManifestInfo {
+    let mut platforms = Vec::<String>::new();
+    for target_info in target_infos {
+        if !platforms.contains(&target_info.oci_target) {
+            platforms.push(target_info.oci_target.clone());
+        }
+    }
+    ManifestInfo {
+        platforms,
+        images: target_infos
+            .iter()
+            .map(|target_info| ManifestImageInfo {
+                platform: target_info.oci_target.clone(),
+                stable_tag: target_info.stable_tag.clone(),
+                temporary_tag: target_info.temporary_tag.clone(),
+            })
+            .collect(),
+    }
+}
+
+// Leaves out buildpacks without changes since the given ref and sets `any_changed` so later jobs
+// can be skipped when nothing needs to be packaged.
+fn retain_changed_buildpacks(
+    buildpacks_info: &mut Vec<BuildpackInfo>,
+    changed_since: &str,
+    source_dir: &Path,
+    buildpack_dirs: &[PathBuf],
+    buildpacks: &[BuildpackDescriptor],
+) -> Result<()> {
+    let changed_files = changed_files_since(source_dir, changed_since)
+        .map_err(|e| Error::ListingChangedFiles(changed_since.to_string(), e))?;
+    let changed_buildpack_ids =
+        find_changed_buildpack_ids(&changed_files, buildpack_dirs, buildpacks)?;
+
+    buildpacks_info.retain(|buildpack_info| {
+        changed_buildpack_ids.contains(buildpack_info.buildpack_id.as_str())
+    });
+    for buildpack_info in buildpacks_info.iter() {
+        eprintln!(
+            "✅️ Changed since {changed_since}: {}",
+            buildpack_info.buildpack_id
+        );
+    }
+
+    actions::set_output("any_changed", (!buildpacks_info.is_empty()).to_string())
+        .map_err(Error::WriteActionData)
+}
+
+// A buildpack has changed when a file in its directory, or in a local crate it depends on (e.g.;
+// a crate shared across the Cargo workspace), has. Composite buildpacks change along with any of
+// the buildpacks in their order groups.
+fn find_changed_buildpack_ids(
+    changed_files: &[PathBuf],
+    buildpack_dirs: &[PathBuf],
+    buildpacks: &[BuildpackDescriptor],
+) -> Result<HashSet<String>> {
+    let mut changed_buildpack_ids = HashSet::new();
+    for (buildpack_dir, buildpack_descriptor) in buildpack_dirs.iter().zip(buildpacks) {
+        let source_dirs = std::iter::once(canonical_path(buildpack_dir))
+            .chain(read_local_crate_dirs(buildpack_dir)?)
+            .collect::<Vec<_>>();
+        if changed_files
+            .iter()
+            .any(|file| source_dirs.iter().any(|dir| file.starts_with(dir)))
+        {
+            changed_buildpack_ids.insert(buildpack_descriptor.buildpack().id.to_string());
+        }
+    }
+
+    loop {
+        let changed_composites = buildpacks
+            .iter()
+            .filter_map(|buildpack_descriptor| match buildpack_descriptor {
+                BuildpackDescriptor::Composite(composite) => Some(composite),
+                BuildpackDescriptor::Component(_) => None,
+            })
+            .filter(|composite| !changed_buildpack_ids.contains(composite.buildpack.id.as_str()))
+            .filter(|composite| {
+                composite
+                    .order
+                    .iter()
+                    .flat_map(|order| &order.group)
+                    .any(|group| changed_buildpack_ids.contains(group.id.as_str()))
+            })
+            .map(|composite| composite.buildpack.id.to_string())
+            .collect::<Vec<_>>();
+        if changed_composites.is_empty() {
+            return Ok(changed_buildpack_ids);
+        }
+        changed_buildpack_ids.extend(changed_composites);
+    }
+}
+
+// Follows the path dependencies of a crate, including ones declared in `[workspace.dependencies]`
+// and inherited with `workspace = true`, to every local crate it depends on.
+fn read_local_crate_dirs(crate_dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut crate_dirs = Vec::<PathBuf>::new();
+    let mut pending_dirs = vec![crate_dir.to_path_buf()];
+    while let Some(dir) = pending_dirs.pop() {
+        let Some(manifest) = read_cargo_manifest(&dir)? else {
+            continue;
+        };
+        let workspace = find_workspace_manifest(&dir)?;
+        for dependency_dir in path_dependency_dirs(&dir, &manifest, workspace.as_ref()) {
+            if !crate_dirs.contains(&dependency_dir) {
+                crate_dirs.push(dependency_dir.clone());
+                pending_dirs.push(dependency_dir);
+            }
+        }
+    }
+    Ok(crate_dirs)
+}
+
+fn path_dependency_dirs(
+    crate_dir: &Path,
+    manifest: &toml::Table,
+    workspace: Option<&(PathBuf, toml::Table)>,
+) -> Vec<PathBuf> {
+    ["dependencies", "build-dependencies"]
+        .iter()
+        .filter_map(|section| manifest.get(*section).and_then(Value::as_table))
+        .flatten()
+        .filter_map(|(name, dependency)| {
+            if dependency.get("workspace").and_then(Value::as_bool) == Some(true) {
+                let (workspace_dir, workspace_manifest) = workspace?;
+                workspace_manifest
+                    .get("workspace")
+                    .and_then(|workspace| workspace.get("dependencies"))
+                    .and_then(|dependencies| dependencies.get(name))
+                    .and_then(|dependency| dependency.get("path"))
+                    .and_then(Value::as_str)
+                    .map(|path| workspace_dir.join(path))
+            } else {
+                dependency
+                    .get("path")
+                    .and_then(Value::as_str)
+                    .map(|path| crate_dir.join(path))
+            }
+        })
+        .map(|dir| canonical_path(&dir))
+        .collect()
+}
+
+// The closest manifest with a `[workspace]` table, starting from the crate itself.
+fn find_workspace_manifest(crate_dir: &Path) -> Result<Option<(PathBuf, toml::Table)>> {
+    for dir in crate_dir.ancestors() {
+        if let Some(manifest) = read_cargo_manifest(dir)? {
+            if manifest.contains_key("workspace") {
+                return Ok(Some((dir.to_path_buf(), manifest)));
+            }
+        }
+    }
+    Ok(None)
+}
+
+fn read_cargo_manifest(dir: &Path) -> Result<Option<toml::Table>> {
+    let path = dir.join("Cargo.toml");
+    if !path.is_file() {
+        return Ok(None);
+    }
+    std::fs::read_to_string(&path)
+        .map_err(|e| Error::ReadingCargoManifest(path.clone(), e))?
+        .parse::<toml::Table>()
+        .map(Some)
+        .map_err(|e| Error::ParsingCargoManifest(path, e))
+}
+
+// Changed files are reported by git as absolute paths without symlinks or `..` components, so
+// directories are compared in the same form.
+fn canonical_path(path: &Path) -> PathBuf {
+    path.canonicalize().unwrap_or_else(|_| path.to_path_buf())
+}
+
+fn add_registry_status(buildpacks_info: &mut [BuildpackInfo]) -> Result<()> {
+    let registered = try_map_concurrently(buildpacks_info, MAX_CONCURRENT_REQUESTS, |info| {
+        is_registered_in_cnb_registry(&info.buildpack_id, &info.buildpack_version)
+            .map_err(Error::CheckCnbRegistry)
+    })?;
+
+    for (buildpack_info, registered) in buildpacks_info.iter_mut().zip(registered) {
+        if registered {
+            eprintln!(
+                "✅️ {} {} is already in the CNB registry",
+                buildpack_info.buildpack_id, buildpack_info.buildpack_version
+            );
+        }
+        buildpack_info.registered = Some(registered);
+    }
+
+    Ok(())
+}
+
+fn set_publish_flags(
+    buildpacks_info: &mut [BuildpackInfo],
+    publish_rules: &[PublishRule],
+) -> Result<()> {
+    let workflow_ref = read_workflow_ref(
+        std::env::var("GITHUB_EVENT_NAME").ok().as_deref(),
+        std::env::var("GITHUB_REF").ok().as_deref(),
+    );
+    let publish = should_publish(publish_rules, &workflow_ref);
+    eprintln!("✅️ Resolved publish to {publish} for {workflow_ref:?}");
+
+    for buildpack_info in buildpacks_info.iter_mut() {
+        buildpack_info.publish = Some(publish);
+        for target in &mut buildpack_info.targets {
+            target.publish = Some(publish);
+        }
+    }
+
+    actions::set_output("publish", publish.to_string()).map_err(Error::WriteActionData)
+}
+
+fn read_workflow_ref(event_name: Option<&str>, git_ref: Option<&str>) -> WorkflowRef {
+    // `pull_request_target` workflows run with the ref of the base branch
+    if matches!(event_name, Some("pull_request" | "pull_request_target")) {
+        return WorkflowRef::PullRequest;
+    }
+
+    let Some(git_ref) = git_ref else {
+        return WorkflowRef::Unknown;
     };
-    targets
+    if let Some(branch) = git_ref.strip_prefix("refs/heads/") {
+        WorkflowRef::Branch(branch.to_string())
+    } else if let Some(tag) = git_ref.strip_prefix("refs/tags/") {
+        WorkflowRef::Tag(tag.to_string())
+    } else if git_ref.starts_with("refs/pull/") {
+        WorkflowRef::PullRequest
+    } else {
+        WorkflowRef::Unknown
+    }
+}
+
+fn should_publish(publish_rules: &[PublishRule], workflow_ref: &WorkflowRef) -> bool {
+    publish_rules
+        .iter()
+        .any(|publish_rule| match (publish_rule.kind, workflow_ref) {
+            (RefKind::Branch, WorkflowRef::Branch(name))
+            | (RefKind::Tag, WorkflowRef::Tag(name)) => {
+                publish_rule.pattern.compile_matcher().is_match(name)
+            }
+            _ => false,
+        })
+}
+
+// Maps each owning team to the ids of the buildpacks it owns. Buildpacks without
+// `[metadata.release.owner]` are listed under `unowned`.
+fn group_by_owner(buildpacks_info: &[BuildpackInfo]) -> BTreeMap<String, Vec<String>> {
+    let mut groups = BTreeMap::<String, Vec<String>>::new();
+    for buildpack_info in buildpacks_info {
+        let team = buildpack_info
+            .owner
+            .as_ref()
+            .map_or("unowned", |owner| owner.team.as_str());
+        groups
+            .entry(team.to_string())
+            .or_default()
+            .push(buildpack_info.buildpack_id.clone());
+    }
+    groups
+}
+
+fn add_buildpack_dependencies(
+    buildpacks_info: &mut [BuildpackInfo],
+    buildpacks: &[BuildpackDescriptor],
+) {
+    let buildpacks_by_id = buildpacks
+        .iter()
+        .map(|buildpack_descriptor| {
+            (
+                buildpack_descriptor.buildpack().id.clone(),
+                buildpack_descriptor,
+            )
+        })
+        .collect::<HashMap<_, _>>();
+
+    for (buildpack_info, buildpack_descriptor) in buildpacks_info.iter_mut().zip(buildpacks) {
+        if let BuildpackDescriptor::Composite(_) = buildpack_descriptor {
+            buildpack_info.dependencies = Some(flatten_dependencies(
+                buildpack_descriptor,
+                &buildpacks_by_id,
+            ));
+        }
+    }
+}
+
+// Lists every buildpack referenced by the order groups of a composite buildpack, following
+// references to other composites in the project. Each buildpack is listed once, after the
+// buildpacks it depends on, so the list is also a valid publishing order.
+fn flatten_dependencies(
+    buildpack_descriptor: &BuildpackDescriptor,
+    buildpacks_by_id: &HashMap<BuildpackId, &BuildpackDescriptor>,
+) -> Vec<DependencyInfo> {
+    fn visit(
+        buildpack_descriptor: &BuildpackDescriptor,
+        buildpacks_by_id: &HashMap<BuildpackId, &BuildpackDescriptor>,
+        visited: &mut HashSet<BuildpackId>,
+        dependencies: &mut Vec<DependencyInfo>,
+    ) {
+        let BuildpackDescriptor::Composite(composite) = buildpack_descriptor else {
+            return;
+        };
+        for group in composite.order.iter().flat_map(|order| &order.group) {
+            if !visited.insert(group.id.clone()) {
+                continue;
+            }
+            if let Some(dependency) = buildpacks_by_id.get(&group.id) {
+                visit(dependency, buildpacks_by_id, visited, dependencies);
+            }
+            dependencies.push(DependencyInfo {
+                buildpack_id: group.id.to_string(),
+                buildpack_version: group.version.to_string(),
+            });
+        }
+    }
+
+    let mut visited = HashSet::from([buildpack_descriptor.buildpack().id.clone()]);
+    let mut dependencies = vec![];
+    visit(
+        buildpack_descriptor,
+        buildpacks_by_id,
+        &mut visited,
+        &mut dependencies,
+    );
+    dependencies
+}
+
+// Lists each buildpack of the project along with the buildpacks of the project that its order
+// groups reference. Buildpacks are listed after the ones they depend on, so components can be
+// published before the composites that reference them.
+fn dependency_graph(buildpacks: &[BuildpackDescriptor]) -> Vec<DependencyGraphNode> {
+    fn visit(
+        buildpack_descriptor: &BuildpackDescriptor,
+        buildpacks_by_id: &HashMap<&BuildpackId, &BuildpackDescriptor>,
+        visited: &mut HashSet<BuildpackId>,
+        nodes: &mut Vec<DependencyGraphNode>,
+    ) {
+        if !visited.insert(buildpack_descriptor.buildpack().id.clone()) {
+            return;
+        }
+        let mut dependencies = vec![];
+        if let BuildpackDescriptor::Composite(composite) = buildpack_descriptor {
+            for group in composite.order.iter().flat_map(|order| &order.group) {
+                let Some(dependency) = buildpacks_by_id.get(&group.id) else {
+                    continue;
+                };
+                visit(dependency, buildpacks_by_id, visited, nodes);
+                if !dependencies.contains(&group.id.to_string()) {
+                    dependencies.push(group.id.to_string());
+                }
+            }
+        }
+        nodes.push(DependencyGraphNode {
+            buildpack_id: buildpack_descriptor.buildpack().id.to_string(),
+            dependencies,
+        });
+    }
+
+    let buildpacks_by_id = buildpacks
+        .iter()
+        .map(|buildpack_descriptor| (&buildpack_descriptor.buildpack().id, buildpack_descriptor))
+        .collect::<HashMap<_, _>>();
+    let mut visited = HashSet::new();
+    let mut nodes = vec![];
+    for buildpack_descriptor in buildpacks {
+        visit(
+            buildpack_descriptor,
+            &buildpacks_by_id,
+            &mut visited,
+            &mut nodes,
+        );
+    }
+    nodes
+}
+
+fn read_builders(args: &GenerateBuildpackMatrixArgs, source_dir: &Path) -> Result<Vec<String>> {
+    let builders_file = args
+        .builders_file
+        .as_deref()
+        .map(|builders_file| resolve_path(builders_file, source_dir));
+    resolve_builders(&args.builders, builders_file.as_deref()).map_err(Error::ReadBuildersFile)
+}
+
+// Records which of the given builders reference each buildpack and returns every builder
+// affected by the release.
+fn add_affected_builders(
+    buildpacks_info: &mut [BuildpackInfo],
+    builder_repository_path: &Path,
+    builders: &[String],
+) -> Result<BTreeSet<String>> {
+    let builder_references = read_builder_references(builder_repository_path, builders)?;
+    let mut all_affected_builders = BTreeSet::new();
+    for buildpack_info in buildpacks_info {
+        let affected_builders =
+            find_affected_builders(&buildpack_info.buildpack_id, &builder_references);
+        all_affected_builders.extend(affected_builders.iter().cloned());
+        buildpack_info.affected_builders = Some(affected_builders);
+    }
+    Ok(all_affected_builders)
 }
 
-fn generate_tag(repo: &str, tag: &str, suffix: Option<&str>) -> String {
+// Reads the ids of the buildpacks referenced by each of the given builders.
+fn read_builder_references(
+    builder_repository_path: &Path,
+    builders: &[String],
+) -> Result<Vec<(String, HashSet<String>)>> {
+    builders
+        .iter()
+        .map(|builder| {
+            read_builder_file(builder_file_path(builder_repository_path, builder))
+                .map_err(Error::ReadBuilderFile)
+                .map(|builder_file| {
+                    (
+                        builder.clone(),
+                        read_builder_buildpack_ids(&builder_file.document),
+                    )
+                })
+        })
+        .collect()
+}
+
+fn find_affected_builders(
+    buildpack_id: &str,
+    builder_references: &[(String, HashSet<String>)],
+) -> Vec<String> {
+    builder_references
+        .iter()
+        .filter(|(_, buildpack_ids)| buildpack_ids.contains(buildpack_id))
+        .map(|(builder, _)| builder.clone())
+        .collect()
+}
+
+// Lists every target whose packaged artifacts are missing or empty. `.cnb` files are
+// resolved relative to the source directory, which is where they get packaged.
+fn find_missing_artifacts(
+    buildpacks_info: &[BuildpackInfo],
+    source_dir: &Path,
+    required_artifacts: RequiredArtifacts,
+) -> Vec<String> {
+    let mut missing_artifacts = vec![];
+    for buildpack_info in buildpacks_info {
+        for target in &buildpack_info.targets {
+            let mut required_paths = vec![("output directory", target.output_dir.clone())];
+            if required_artifacts == RequiredArtifacts::CnbFiles {
+                required_paths.push(("cnb file", source_dir.join(&target.cnb_file)));
+            }
+            for (artifact, path) in required_paths {
+                if is_missing_or_empty(&path) {
+                    missing_artifacts.push(format!(
+                        "{} ({}) {artifact} is missing or empty: {}",
+                        buildpack_info.buildpack_id,
+                        target.oci_target,
+                        path.display()
+                    ));
+                }
+            }
+        }
+    }
+    missing_artifacts
+}
+
+fn is_missing_or_empty(path: &Path) -> bool {
+    if path.is_dir() {
+        std::fs::read_dir(path).map_or(true, |mut entries| entries.next().is_none())
+    } else {
+        std::fs::metadata(path).map_or(true, |metadata| metadata.len() == 0)
+    }
+}
+
+// Combines the inputs of a target's compile job into a single value that can be used
+// in `actions/cache` keys, so cached builds are invalidated exactly when an input changes.
+fn cache_key_fingerprint(inputs: &[&str]) -> String {
+    let mut hasher = Sha256::new();
+    for input in inputs {
+        hasher.update(input.as_bytes());
+        hasher.update([0]);
+    }
+    format!("{:x}", hasher.finalize())
+}
+
+// Hashes the nearest Cargo.lock at or above the buildpack directory. Buildpacks that
+// aren't part of a Cargo workspace (e.g.; bash buildpacks) hash to an empty string.
+fn cargo_lock_hash(buildpack_dir: &Path) -> Result<String> {
+    match buildpack_dir
+        .ancestors()
+        .map(|dir| dir.join("Cargo.lock"))
+        .find(|path| path.is_file())
+    {
+        Some(cargo_lock) => std::fs::read(&cargo_lock)
+            .map(|contents| format!("{:x}", Sha256::digest(contents)))
+            .map_err(|e| Error::ReadingFingerprintInput(cargo_lock, e)),
+        None => Ok(String::new()),
+    }
+}
+
+// Hashes the path and contents of every file in the buildpack directory, skipping the
+// package directory and anything excluded by ignore files (e.g.; build output in `target/`).
+fn buildpack_dir_hash(buildpack_dir: &Path, package_dir: &Path) -> Result<String> {
+    let package_dir = package_dir.to_path_buf();
+    let mut files = ignore::WalkBuilder::new(buildpack_dir)
+        .filter_entry(move |entry| !entry.path().starts_with(&package_dir))
+        .build()
+        .map(|entry| {
+            entry
+                .map(ignore::DirEntry::into_path)
+                .map_err(|e| Error::WalkingBuildpackDir(buildpack_dir.to_path_buf(), e))
+        })
+        .filter(|entry| entry.as_ref().map_or(true, |path| path.is_file()))
+        .collect::<Result<Vec<_>>>()?;
+    files.sort();
+
+    let mut hasher = Sha256::new();
+    for file in files {
+        let contents =
+            std::fs::read(&file).map_err(|e| Error::ReadingFingerprintInput(file.clone(), e))?;
+        let relative_path = file.strip_prefix(buildpack_dir).unwrap_or(&file);
+        hasher.update(relative_path.to_string_lossy().as_bytes());
+        hasher.update([0]);
+        hasher.update(Sha256::digest(contents));
+    }
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+// Buildpacks with several targets publish an image per target, tagged with the target's name
+// (e.g.; `1.2.3_linux-arm64`).
+pub(crate) fn generate_tag(repo: &str, tag: &str, suffix: Option<&str>) -> String {
     suffix.map_or_else(
         || format!("{repo}:{tag}"),
         |suffix| format!("{repo}:{tag}_{suffix}"),
     )
 }
 
+// Returns the registry id and region of a private ECR repository so workflows can assume the
+// publishing role and log in to the registry without parsing the repository themselves.
+fn parse_ecr_repository(repository: &str) -> Option<(String, String)> {
+    ECR_REPOSITORY.captures(repository).map(|captures| {
+        (
+            captures["registry_id"].to_string(),
+            captures["region"].to_string(),
+        )
+    })
+}
+
 fn cnb_file(buildpack_id: &BuildpackId, suffix: Option<&str>) -> String {
     let name = default_buildpack_directory_name(buildpack_id);
     suffix.map_or_else(
@@ -223,7 +1287,7 @@ fn cnb_file(buildpack_id: &BuildpackId, suffix: Option<&str>) -> String {
 }
 
 // Returns the OCI target name for a buildpack target. Currently ignores distros.
-fn oci_target(target: &BuildpackTarget) -> String {
+pub(crate) fn oci_target(target: &BuildpackTarget) -> String {
     match (target.os.as_deref(), target.arch.as_deref()) {
         (Some(os), Some(arch)) => format!("{os}/{arch}"),
         (Some(os), None) => os.to_string(),
@@ -231,6 +1295,41 @@ fn oci_target(target: &BuildpackTarget) -> String {
     }
 }
 
+// A target that declares several distros (e.g.; for buildpacks specific to a base image) is
+// split into a target per distro, each named with a distro suffix like `linux-amd64-ubuntu-22.04`
+// so its image tags and .cnb files are distinct. The split targets still share the packaged output
+// directory of their platform. Returns each target along with its name.
+pub(crate) fn expand_distro_targets(
+    targets: Vec<BuildpackTarget>,
+) -> Vec<(BuildpackTarget, String)> {
+    targets
+        .into_iter()
+        .flat_map(|target| {
+            if target.distros.len() < 2 {
+                let name = target_name(&target);
+                return vec![(target, name)];
+            }
+            target
+                .distros
+                .iter()
+                .map(|distro| {
+                    let name = format!(
+                        "{}-{}-{}",
+                        target_name(&target),
+                        distro.name,
+                        distro.version
+                    );
+                    let distro_target = BuildpackTarget {
+                        distros: vec![distro.clone()],
+                        ..target.clone()
+                    };
+                    (distro_target, name)
+                })
+                .collect()
+        })
+        .collect()
+}
+
 // Returns the target naming suffix for image tags and .cnb files.
 fn target_name(target: &BuildpackTarget) -> String {
     match (target.os.as_deref(), target.arch.as_deref()) {
@@ -275,6 +1374,15 @@ fn buildpack_type(
     buildpack_descriptor: &BuildpackDescriptor,
     buildpack_dir: &Path,
 ) -> Result<BuildpackType> {
+    // extensions are always packaged as-is, like bash buildpacks
+    if is_extension_dir(buildpack_dir) {
+        return if has_extension_bin_files(buildpack_dir) {
+            Ok(BuildpackType::Bash)
+        } else {
+            Err(Error::UnknownType(buildpack_dir.into()))
+        };
+    }
+
     match (
         buildpack_descriptor,
         has_cargo_toml(buildpack_dir),
@@ -303,36 +1411,27 @@ fn has_bin_files(buildpack_dir: &Path) -> bool {
         .all(|file| buildpack_dir.join("bin").join(file).exists())
 }
 
-// Project descriptors for composite buildpacks don't support `[[targets]]`,
-// but this project needs a way to determine what targets to package composite
-// buildpacks for. This function reads `[[targets]]` out of a project
-// descriptor's metadata (which is unrestricted) instead.
-fn read_metadata_targets(md: GenericMetadata) -> Option<Vec<BuildpackTarget>> {
-    let get_toml_string = |table: &toml::Table, key: &str| -> Option<String> {
-        Some(table.get(key)?.as_str()?.to_string())
-    };
-    Some(
-        md?.get("targets")?
-            .as_array()?
-            .iter()
-            .filter_map(|tgt_value| {
-                let tgt_table = tgt_value.as_table()?;
-                Some(BuildpackTarget {
-                    os: get_toml_string(tgt_table, "os"),
-                    arch: get_toml_string(tgt_table, "arch"),
-                    variant: get_toml_string(tgt_table, "variant"),
-                    distros: vec![],
-                })
-            })
-            .collect(),
-    )
+fn has_extension_bin_files(extension_dir: &Path) -> bool {
+    ["detect", "generate"]
+        .iter()
+        .all(|file| extension_dir.join("bin").join(file).exists())
 }
 
 #[cfg(test)]
 mod tests {
     use super::read_buildpack_info;
-    use crate::commands::generate_buildpack_matrix::command::BuildpackType;
+    use crate::buildpacks::{read_buildpack_descriptor, BuildpackOwner};
+    use crate::changelog::Changelog;
+    use crate::commands::generate_buildpack_matrix::command::{
+        chunk_entries, dependency_graph, find_affected_builders, find_changed_buildpack_ids,
+        find_missing_artifacts, flatten_dependencies, group_by_owner, parse_ecr_repository,
+        parse_publish_rule, previous_version, read_git_metadata, read_workflow_ref, should_publish,
+        ArtifactType, BuildpackType, DependencyGraphNode, DependencyInfo, DistroInfo,
+        ManifestImageInfo, ManifestInfo, RequiredArtifacts, WorkflowRef,
+    };
     use libcnb_data::buildpack::BuildpackDescriptor;
+    use std::collections::{BTreeMap, HashMap, HashSet};
+    use std::num::NonZeroUsize;
     use std::{
         fs::{create_dir_all, OpenOptions},
         path::PathBuf,
@@ -401,6 +1500,100 @@ mod tests {
                 "./packaged-fake/x86_64-unknown-linux-musl/release/heroku_fakeymcfakeface"
             )
         );
+        assert!(bp_info.requires_manifest);
+        assert_eq!(
+            bp_info.manifest,
+            ManifestInfo {
+                platforms: vec!["linux/amd64".to_string(), "linux/arm64".to_string()],
+                images: vec![
+                    ManifestImageInfo {
+                        platform: "linux/amd64".to_string(),
+                        stable_tag: "docker.io/heroku/buildpack-fakey:1.2.3_linux-amd64"
+                            .to_string(),
+                        temporary_tag: "docker.io/heroku/buildpack-fakey:_918273_linux-amd64"
+                            .to_string(),
+                    },
+                    ManifestImageInfo {
+                        platform: "linux/arm64".to_string(),
+                        stable_tag: "docker.io/heroku/buildpack-fakey:1.2.3_linux-arm64"
+                            .to_string(),
+                        temporary_tag: "docker.io/heroku/buildpack-fakey:_918273_linux-arm64"
+                            .to_string(),
+                    },
+                ],
+            }
+        );
+    }
+
+    #[test]
+    fn read_buildpack_with_multiple_distros() {
+        let bp_descriptor: BuildpackDescriptor = toml::from_str(
+            r#"
+                api = "0.10"
+                [buildpack]
+                id = "heroku/fakeymcfakeface"
+                version = "1.2.3"
+                [[targets]]
+                os = "linux"
+                arch = "amd64"
+                [[targets.distros]]
+                name = "ubuntu"
+                version = "22.04"
+                [[targets.distros]]
+                name = "ubuntu"
+                version = "24.04"
+                [[targets]]
+                os = "linux"
+                arch = "arm64"
+                [[targets.distros]]
+                name = "ubuntu"
+                version = "24.04"
+                [metadata.release]
+                image = { repository = "docker.io/heroku/buildpack-fakey" }
+            "#,
+        )
+        .expect("expected buildpack descriptor to parse");
+        let bp_dir = tempdir().expect("Error creating tempdir");
+        std::fs::write(bp_dir.path().join("Cargo.toml"), "[package]")
+            .expect("Couldn't write dummy Cargo.toml");
+
+        let bp_info = read_buildpack_info(
+            &bp_descriptor,
+            bp_dir.path(),
+            &PathBuf::from("./packaged-fake"),
+            "918273",
+        )
+        .expect("Expected to read buildpack info");
+
+        assert_eq!(
+            bp_info
+                .targets
+                .iter()
+                .map(|target| (target.stable_tag.as_str(), target.cnb_file.as_str()))
+                .collect::<Vec<_>>(),
+            vec![
+                (
+                    "docker.io/heroku/buildpack-fakey:1.2.3_linux-amd64-ubuntu-22.04",
+                    "heroku_fakeymcfakeface_linux-amd64-ubuntu-22.04.cnb"
+                ),
+                (
+                    "docker.io/heroku/buildpack-fakey:1.2.3_linux-amd64-ubuntu-24.04",
+                    "heroku_fakeymcfakeface_linux-amd64-ubuntu-24.04.cnb"
+                ),
+                (
+                    "docker.io/heroku/buildpack-fakey:1.2.3_linux-arm64",
+                    "heroku_fakeymcfakeface_linux-arm64.cnb"
+                ),
+            ]
+        );
+        assert_eq!(
+            bp_info.targets[1].distros,
+            vec![DistroInfo {
+                name: "ubuntu".to_string(),
+                version: "24.04".to_string()
+            }]
+        );
+        assert_eq!(bp_info.targets[1].oci_target, "linux/amd64");
     }
 
     #[test]
@@ -436,6 +1629,8 @@ mod tests {
         assert_eq!(bp_info.buildpack_id, "heroku/fakeymcfakeface");
         assert_eq!(bp_info.buildpack_type, BuildpackType::Bash);
         assert_eq!(bp_info.stable_tag, "docker.io/heroku/buildpack-fakey:3.2.1");
+        assert!(!bp_info.requires_manifest);
+        assert_eq!(bp_info.manifest.platforms, vec!["linux/amd64".to_string()]);
         assert_eq!(
             bp_info.targets[0].temporary_tag,
             "docker.io/heroku/buildpack-fakey:_1928273"
@@ -490,4 +1685,576 @@ mod tests {
             )
         );
     }
+
+    #[test]
+    fn group_buildpacks_by_owner() {
+        let package_dir = PathBuf::from("./packaged-fake");
+        let bp_dir = tempdir().expect("Error creating tempdir");
+        let bp_infos = [
+            ("heroku/nodejs", Some("languages")),
+            ("heroku/python", Some("languages")),
+            ("heroku/experimental", None),
+        ]
+        .into_iter()
+        .map(|(id, team)| {
+            let owner = team.map_or(String::new(), |team| {
+                format!("owner = {{ team = \"{team}\", language = \"any\" }}")
+            });
+            let bp_descriptor: BuildpackDescriptor = toml::from_str(&format!(
+                r#"
+                    api = "0.10"
+                    [buildpack]
+                    id = "{id}"
+                    version = "1.0.0"
+                    [[order]]
+                    [[order.group]]
+                    id = "heroku/procfile"
+                    version = "1.0.0"
+                    [metadata.release]
+                    image = {{ repository = "docker.io/heroku/buildpack-fakey" }}
+                    {owner}
+                "#
+            ))
+            .expect("expected buildpack descriptor to parse");
+            read_buildpack_info(&bp_descriptor, bp_dir.path(), &package_dir, "1928273")
+                .expect("Expected to read buildpack info")
+        })
+        .collect::<Vec<_>>();
+
+        assert_eq!(
+            bp_infos[0].owner,
+            Some(BuildpackOwner {
+                team: "languages".to_string(),
+                language: Some("any".to_string())
+            })
+        );
+        assert_eq!(bp_infos[2].owner, None);
+        assert_eq!(
+            group_by_owner(&bp_infos),
+            BTreeMap::from([
+                (
+                    "languages".to_string(),
+                    vec!["heroku/nodejs".to_string(), "heroku/python".to_string()]
+                ),
+                (
+                    "unowned".to_string(),
+                    vec!["heroku/experimental".to_string()]
+                ),
+            ])
+        );
+    }
+
+    #[test]
+    fn report_missing_artifacts() {
+        let bp_descriptor: BuildpackDescriptor = toml::from_str(
+            r#"
+                api = "0.10"
+                [buildpack]
+                id = "heroku/fakeymcfakeface"
+                version = "1.2.3"
+                [[order]]
+                [[order.group]]
+                id = "heroku/procfile"
+                version = "1.0.0"
+                [[metadata.targets]]
+                os = "linux"
+                arch = "amd64"
+                [[metadata.targets]]
+                os = "linux"
+                arch = "arm64"
+                [metadata.release]
+                image = { repository = "docker.io/heroku/buildpack-fakey" }
+            "#,
+        )
+        .expect("expected buildpack descriptor to parse");
+        let source_dir = tempdir().expect("Error creating tempdir");
+        let package_dir = source_dir.path().join("packaged");
+        let bp_info = read_buildpack_info(&bp_descriptor, source_dir.path(), &package_dir, "1")
+            .expect("Expected to read buildpack info");
+
+        create_dir_all(&bp_info.targets[0].output_dir).expect("Couldn't create output dir");
+        std::fs::write(
+            bp_info.targets[0].output_dir.join("buildpack.toml"),
+            "api = \"0.10\"",
+        )
+        .expect("Couldn't write dummy buildpack.toml");
+        create_dir_all(&bp_info.targets[1].output_dir).expect("Couldn't create output dir");
+
+        let bp_infos = [bp_info];
+        assert_eq!(
+            find_missing_artifacts(&bp_infos, source_dir.path(), RequiredArtifacts::Packaged),
+            vec![format!(
+                "heroku/fakeymcfakeface (linux/arm64) output directory is missing or empty: {}",
+                bp_infos[0].targets[1].output_dir.display()
+            )]
+        );
+        assert_eq!(
+            find_missing_artifacts(&bp_infos, source_dir.path(), RequiredArtifacts::CnbFiles).len(),
+            3
+        );
+    }
+
+    #[test]
+    fn read_extension_info() {
+        let extension_dir = tempdir().expect("Error creating tempdir");
+        create_dir_all(extension_dir.path().join("bin")).expect("Couldn't create bin dir");
+        for file in ["detect", "generate"] {
+            std::fs::write(extension_dir.path().join("bin").join(file), "")
+                .expect("Couldn't write bin file");
+        }
+        std::fs::write(
+            extension_dir.path().join("extension.toml"),
+            r#"
+                api = "0.10"
+                [extension]
+                id = "heroku/apt-extension"
+                version = "1.2.3"
+                [metadata.release]
+                image = { repository = "docker.io/heroku/extension-apt" }
+            "#,
+        )
+        .expect("Couldn't write extension.toml");
+
+        let descriptor = read_buildpack_descriptor(extension_dir.path())
+            .expect("Expected to read extension descriptor");
+        let info = read_buildpack_info(
+            &descriptor,
+            extension_dir.path(),
+            &extension_dir.path().join("packaged"),
+            "1",
+        )
+        .expect("Expected to read extension info");
+
+        assert_eq!(info.buildpack_id, "heroku/apt-extension");
+        assert_eq!(info.artifact_type, ArtifactType::Extension);
+        assert_eq!(info.buildpack_type, BuildpackType::Bash);
+        assert_eq!(info.stable_tag, "docker.io/heroku/extension-apt:1.2.3");
+    }
+
+    #[test]
+    fn read_buildpack_info_with_target_repositories() {
+        let bp_descriptor: BuildpackDescriptor = toml::from_str(
+            r#"
+                api = "0.10"
+                [buildpack]
+                id = "heroku/fakeymcfakeface"
+                version = "1.2.3"
+                [[order]]
+                [[order.group]]
+                id = "heroku/procfile"
+                version = "1.0.0"
+                [[metadata.targets]]
+                os = "linux"
+                arch = "amd64"
+                [[metadata.targets]]
+                os = "linux"
+                arch = "arm64"
+                [metadata.release.image]
+                repository = "docker.io/heroku/buildpack-fakey"
+                [metadata.release.image.targets]
+                "linux/arm64" = "docker.io/heroku/buildpack-fakey-arm64"
+            "#,
+        )
+        .expect("expected buildpack descriptor to parse");
+        let source_dir = tempdir().expect("Error creating tempdir");
+        let bp_info = read_buildpack_info(
+            &bp_descriptor,
+            source_dir.path(),
+            &source_dir.path().join("packaged"),
+            "1",
+        )
+        .expect("Expected to read buildpack info");
+
+        assert_eq!(bp_info.stable_tag, "docker.io/heroku/buildpack-fakey:1.2.3");
+        assert_eq!(
+            bp_info
+                .targets
+                .iter()
+                .map(|target| (
+                    target.image_repository.as_str(),
+                    target.stable_tag.as_str(),
+                    target.temporary_tag.as_str()
+                ))
+                .collect::<Vec<_>>(),
+            vec![
+                (
+                    "docker.io/heroku/buildpack-fakey",
+                    "docker.io/heroku/buildpack-fakey:1.2.3_linux-amd64",
+                    "docker.io/heroku/buildpack-fakey:_1_linux-amd64"
+                ),
+                (
+                    "docker.io/heroku/buildpack-fakey-arm64",
+                    "docker.io/heroku/buildpack-fakey-arm64:1.2.3_linux-arm64",
+                    "docker.io/heroku/buildpack-fakey-arm64:_1_linux-arm64"
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_ecr_repository_fields() {
+        assert_eq!(
+            parse_ecr_repository(
+                "123456789012.dkr.ecr.us-east-1.amazonaws.com/heroku/buildpack-java"
+            ),
+            Some(("123456789012".to_string(), "us-east-1".to_string()))
+        );
+        assert_eq!(
+            parse_ecr_repository("123456789012.dkr.ecr-fips.us-gov-west-1.amazonaws.com/buildpack"),
+            Some(("123456789012".to_string(), "us-gov-west-1".to_string()))
+        );
+        assert_eq!(
+            parse_ecr_repository("public.ecr.aws/heroku/buildpack-java"),
+            None
+        );
+        assert_eq!(
+            parse_ecr_repository("docker.io/heroku/buildpack-java"),
+            None
+        );
+    }
+
+    #[test]
+    fn cache_key_fingerprint_tracks_target_and_sources() {
+        let bp_descriptor: BuildpackDescriptor = toml::from_str(
+            r#"
+                api = "0.10"
+                [buildpack]
+                id = "heroku/fakeymcfakeface"
+                version = "1.2.3"
+                [[targets]]
+                os="linux"
+                arch="amd64"
+                [[targets]]
+                os="linux"
+                arch="arm64"
+                [metadata.release]
+                image = { repository = "docker.io/heroku/buildpack-fakey" }
+            "#,
+        )
+        .expect("expected buildpack descriptor to parse");
+        let bp_dir = tempdir().expect("Error creating tempdir");
+        let package_dir = bp_dir.path().join("packaged");
+        std::fs::write(bp_dir.path().join("Cargo.toml"), "[package]")
+            .expect("Couldn't write dummy Cargo.toml");
+
+        let fingerprints = || {
+            read_buildpack_info(&bp_descriptor, bp_dir.path(), &package_dir, "1")
+                .expect("Expected to read buildpack info")
+                .targets
+                .into_iter()
+                .map(|target| target.cache_key_fingerprint)
+                .collect::<Vec<_>>()
+        };
+
+        let original = fingerprints();
+        assert_ne!(original[0], original[1]);
+
+        create_dir_all(&package_dir).expect("Couldn't create package dir");
+        std::fs::write(package_dir.join("buildpack.toml"), "api = \"0.10\"")
+            .expect("Couldn't write packaged buildpack.toml");
+        assert_eq!(fingerprints(), original);
+
+        std::fs::write(
+            bp_dir.path().join("Cargo.toml"),
+            "[package]\nname = \"fakey\"",
+        )
+        .expect("Couldn't write dummy Cargo.toml");
+        let changed = fingerprints();
+        assert_ne!(changed[0], original[0]);
+        assert_ne!(changed[1], original[1]);
+    }
+
+    #[test]
+    fn find_builders_affected_by_buildpack() {
+        let builder_references = [
+            (
+                "builder-22".to_string(),
+                HashSet::from(["heroku/java".to_string(), "heroku/nodejs".to_string()]),
+            ),
+            (
+                "salesforce-functions".to_string(),
+                HashSet::from(["heroku/nodejs-function".to_string()]),
+            ),
+            (
+                "builder-24".to_string(),
+                HashSet::from(["heroku/java".to_string()]),
+            ),
+        ];
+        assert_eq!(
+            find_affected_builders("heroku/java", &builder_references),
+            vec!["builder-22".to_string(), "builder-24".to_string()]
+        );
+        assert!(find_affected_builders("heroku/python", &builder_references).is_empty());
+    }
+
+    #[test]
+    fn find_buildpacks_changed_through_local_crates() {
+        let project_dir = tempdir().expect("Error creating tempdir");
+        let project_dir = project_dir.path().canonicalize().unwrap();
+        let write = |path: &str, contents: &str| {
+            let path = project_dir.join(path);
+            create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, contents).unwrap();
+        };
+        write(
+            "Cargo.toml",
+            "[workspace]\nmembers = [\"buildpacks/*\"]\n[workspace.dependencies]\nshared = { path = \"crates/shared\" }",
+        );
+        write(
+            "buildpacks/java/Cargo.toml",
+            "[package]\nname = \"java\"\n[dependencies]\nshared = { workspace = true }",
+        );
+        write(
+            "buildpacks/gradle/Cargo.toml",
+            "[package]\nname = \"gradle\"\n[dependencies]\nserde = \"1\"",
+        );
+        write(
+            "crates/shared/Cargo.toml",
+            "[package]\nname = \"shared\"\n[dependencies]\nutils = { path = \"../utils\" }",
+        );
+        write("crates/utils/Cargo.toml", "[package]\nname = \"utils\"");
+
+        let descriptor = |contents: &str| -> BuildpackDescriptor {
+            toml::from_str(&format!("api = \"0.10\"\n{contents}")).unwrap()
+        };
+        let buildpacks = [
+            descriptor("[buildpack]\nid = \"heroku/java\"\nversion = \"1.0.0\""),
+            descriptor("[buildpack]\nid = \"heroku/gradle\"\nversion = \"1.0.0\""),
+            descriptor(
+                "[buildpack]\nid = \"heroku/jvm\"\nversion = \"1.0.0\"\n[[order]]\n[[order.group]]\nid = \"heroku/gradle\"\nversion = \"1.0.0\"",
+            ),
+        ];
+        let buildpack_dirs = [
+            project_dir.join("buildpacks/java"),
+            project_dir.join("buildpacks/gradle"),
+            project_dir.join("buildpacks/jvm"),
+        ];
+        let changed_ids = |files: &[&str]| {
+            let changed_files = files
+                .iter()
+                .map(|file| project_dir.join(file))
+                .collect::<Vec<_>>();
+            let mut changed_ids =
+                find_changed_buildpack_ids(&changed_files, &buildpack_dirs, &buildpacks)
+                    .unwrap()
+                    .into_iter()
+                    .collect::<Vec<_>>();
+            changed_ids.sort();
+            changed_ids
+        };
+
+        assert_eq!(changed_ids(&["crates/utils/src/lib.rs"]), ["heroku/java"]);
+        assert_eq!(
+            changed_ids(&["buildpacks/gradle/src/main.rs"]),
+            ["heroku/gradle", "heroku/jvm"]
+        );
+        assert!(changed_ids(&["README.md", "buildpacks/javascript/CHANGELOG.md"]).is_empty());
+    }
+
+    #[test]
+    fn chunk_matrix_entries() {
+        let max_entries = NonZeroUsize::new(2).unwrap();
+        assert_eq!(
+            chunk_entries(&[1, 2, 3, 4, 5], max_entries),
+            vec![&[1, 2][..], &[3, 4], &[5]]
+        );
+        assert_eq!(chunk_entries(&[1, 2], max_entries), vec![&[1, 2][..]]);
+        assert_eq!(chunk_entries::<u8>(&[], max_entries), vec![&[] as &[u8]]);
+    }
+
+    #[test]
+    fn resolve_publish_from_workflow_ref() {
+        assert_eq!(
+            read_workflow_ref(Some("push"), Some("refs/heads/main")),
+            WorkflowRef::Branch("main".to_string())
+        );
+        assert_eq!(
+            read_workflow_ref(Some("push"), Some("refs/tags/v1.2.3")),
+            WorkflowRef::Tag("v1.2.3".to_string())
+        );
+        assert_eq!(
+            read_workflow_ref(Some("pull_request_target"), Some("refs/heads/main")),
+            WorkflowRef::PullRequest
+        );
+        assert_eq!(read_workflow_ref(None, None), WorkflowRef::Unknown);
+
+        let publish_rules = [
+            parse_publish_rule("branch:main").unwrap(),
+            parse_publish_rule("tag:v*").unwrap(),
+        ];
+        assert!(should_publish(
+            &publish_rules,
+            &WorkflowRef::Branch("main".to_string())
+        ));
+        assert!(should_publish(
+            &publish_rules,
+            &WorkflowRef::Tag("v1.2.3".to_string())
+        ));
+        assert!(!should_publish(
+            &publish_rules,
+            &WorkflowRef::Branch("v1.2.3".to_string())
+        ));
+        assert!(!should_publish(&publish_rules, &WorkflowRef::PullRequest));
+        assert!(parse_publish_rule("main").is_err());
+        assert!(parse_publish_rule("commit:abc").is_err());
+    }
+
+    #[test]
+    fn flatten_dependencies_of_nested_orders() {
+        let nodejs: BuildpackDescriptor = toml::from_str(
+            r#"
+                api = "0.10"
+                [buildpack]
+                id = "heroku/nodejs"
+                version = "2.0.0"
+                [[order]]
+                [[order.group]]
+                id = "heroku/nodejs-engine"
+                version = "2.0.0"
+                [[order.group]]
+                id = "heroku/nodejs-npm"
+                version = "2.0.0"
+                [[order]]
+                [[order.group]]
+                id = "heroku/nodejs-engine"
+                version = "2.0.0"
+                [[order.group]]
+                id = "heroku/procfile"
+                version = "3.0.0"
+                optional = true
+            "#,
+        )
+        .unwrap();
+        let nodejs_npm: BuildpackDescriptor = toml::from_str(
+            r#"
+                api = "0.10"
+                [buildpack]
+                id = "heroku/nodejs-npm"
+                version = "2.0.0"
+                [[order]]
+                [[order.group]]
+                id = "heroku/nodejs-npm-install"
+                version = "2.0.0"
+            "#,
+        )
+        .unwrap();
+        let buildpacks_by_id = HashMap::from([
+            (nodejs.buildpack().id.clone(), &nodejs),
+            (nodejs_npm.buildpack().id.clone(), &nodejs_npm),
+        ]);
+
+        let dependency = |id: &str, version: &str| DependencyInfo {
+            buildpack_id: id.to_string(),
+            buildpack_version: version.to_string(),
+        };
+        assert_eq!(
+            flatten_dependencies(&nodejs, &buildpacks_by_id),
+            vec![
+                dependency("heroku/nodejs-engine", "2.0.0"),
+                dependency("heroku/nodejs-npm-install", "2.0.0"),
+                dependency("heroku/nodejs-npm", "2.0.0"),
+                dependency("heroku/procfile", "3.0.0"),
+            ]
+        );
+    }
+
+    #[test]
+    fn read_git_metadata_from_actions_env() {
+        let workspace = tempdir().unwrap();
+        let buildpack_dir = workspace.path().join("buildpacks/nodejs");
+        create_dir_all(&buildpack_dir).unwrap();
+        let env = HashMap::from([
+            ("GITHUB_SERVER_URL", "https://github.com"),
+            ("GITHUB_REPOSITORY", "heroku/buildpacks-nodejs"),
+            ("GITHUB_SHA", "0123456789abcdef"),
+            ("GITHUB_REF", "refs/tags/v1.2.3"),
+            ("GITHUB_WORKSPACE", workspace.path().to_str().unwrap()),
+        ]);
+
+        let git_metadata = read_git_metadata(workspace.path(), |name| {
+            env.get(name).map(ToString::to_string)
+        });
+        assert_eq!(git_metadata.commit_sha.as_deref(), Some("0123456789abcdef"));
+        assert_eq!(git_metadata.source_ref.as_deref(), Some("refs/tags/v1.2.3"));
+        assert_eq!(
+            git_metadata.source_url(&buildpack_dir).as_deref(),
+            Some("https://github.com/heroku/buildpacks-nodejs/tree/0123456789abcdef/buildpacks/nodejs")
+        );
+        assert_eq!(
+            git_metadata.source_url(workspace.path()).as_deref(),
+            Some("https://github.com/heroku/buildpacks-nodejs/tree/0123456789abcdef")
+        );
+
+        let git_metadata = read_git_metadata(workspace.path(), |_| None);
+        assert_eq!(git_metadata.commit_sha, None);
+        assert_eq!(git_metadata.source_url(&buildpack_dir), None);
+    }
+
+    #[test]
+    fn find_previous_changelog_version() {
+        let changelog = Changelog::try_from(
+            "# Changelog
+
+## [Unreleased]
+
+## [1.1.0] - 2024-06-01
+
+- Added a thing.
+
+## [1.0.0] - 2024-05-01
+
+- Initial release.
+",
+        )
+        .unwrap();
+
+        assert_eq!(
+            previous_version(&changelog, "1.2.0"),
+            Some("1.1.0".to_string())
+        );
+        assert_eq!(
+            previous_version(&changelog, "1.1.0"),
+            Some("1.0.0".to_string())
+        );
+        assert_eq!(previous_version(&changelog, "1.0.0"), None);
+    }
+
+    #[test]
+    fn sort_dependency_graph_by_dependencies() {
+        let buildpack = |id: &str, dependencies: &[&str]| -> BuildpackDescriptor {
+            let order =
+                if dependencies.is_empty() {
+                    "[[targets]]\nos = \"linux\"\narch = \"amd64\"\n".to_string()
+                } else {
+                    dependencies.iter().fold("[[order]]\n".to_string(), |order, dependency| {
+                    format!("{order}[[order.group]]\nid = \"{dependency}\"\nversion = \"1.0.0\"\n")
+                })
+                };
+            toml::from_str(&format!(
+                "api = \"0.10\"\n[buildpack]\nid = \"{id}\"\nversion = \"1.0.0\"\n{order}"
+            ))
+            .unwrap()
+        };
+        let buildpacks = [
+            buildpack(
+                "heroku/java",
+                &["heroku/jvm", "heroku/maven", "heroku/procfile"],
+            ),
+            buildpack("heroku/jvm", &[]),
+            buildpack("heroku/maven", &["heroku/jvm"]),
+        ];
+
+        let node = |id: &str, dependencies: &[&str]| DependencyGraphNode {
+            buildpack_id: id.to_string(),
+            dependencies: dependencies.iter().map(ToString::to_string).collect(),
+        };
+        assert_eq!(
+            dependency_graph(&buildpacks),
+            vec![
+                node("heroku/jvm", &[]),
+                node("heroku/maven", &["heroku/jvm"]),
+                node("heroku/java", &["heroku/jvm", "heroku/maven"]),
+            ]
+        );
+    }
 }
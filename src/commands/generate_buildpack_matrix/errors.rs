@@ -1,4 +1,10 @@
-use crate::buildpacks::{FindReleasableBuildpacksError, ReadBuildpackDescriptorError};
+use crate::builders::{ReadBuilderFileError, ReadBuildersFileError};
+use crate::buildpacks::{
+    CheckCnbRegistryError, FindReleasableBuildpacksError, InvalidImageRepositoryError,
+    ReadBuildpackDescriptorError,
+};
+use crate::changelog::ChangelogError;
+use crate::git::GitError;
 use crate::github::actions::WriteActionDataError;
 use libcnb_data::buildpack::BuildpackTarget;
 use std::collections::HashSet;
@@ -14,12 +20,38 @@ pub(crate) enum Error {
     ReadBuildpackDescriptor(ReadBuildpackDescriptorError),
     #[error("The following buildpack is missing the metadata.release.image.repository entry\nPath: {}", .0.display())]
     MissingImageRepositoryMetadata(PathBuf),
+    #[error(transparent)]
+    InvalidImageRepository(InvalidImageRepositoryError),
+    #[error(transparent)]
+    ReadBuilderFile(ReadBuilderFileError),
+    #[error(transparent)]
+    ReadBuildersFile(ReadBuildersFileError),
+    #[error("Could not read changelog\nPath: {}\nError: {}", .0.display(), .1)]
+    ReadingChangelog(PathBuf, #[source] std::io::Error),
+    #[error("Could not parse changelog\nPath: {}\nError: {}", .0.display(), .1)]
+    ParsingChangelog(PathBuf, #[source] ChangelogError),
+    #[error(transparent)]
+    CheckCnbRegistry(CheckCnbRegistryError),
     #[error("Could not serialize buildpacks into json\nError: {0}")]
     SerializingJson(#[source] serde_json::Error),
     #[error("Expected all buildpacks to have the same version but multiple versions were found:\n{}", list_versions(.0))]
     FixedVersion(HashSet<String>),
     #[error(transparent)]
     WriteActionData(WriteActionDataError),
+    #[error("Could not write matrix\nPath: {}\nError: {}", .0.display(), .1)]
+    WritingMatrix(PathBuf, #[source] std::io::Error),
+    #[error("I/O error while hashing buildpack directory\nPath: {0}\nError: {1}")]
+    WalkingBuildpackDir(PathBuf, #[source] ignore::Error),
+    #[error("Could not read file while generating cache key fingerprint\nPath: {0}\nError: {1}")]
+    ReadingFingerprintInput(PathBuf, #[source] std::io::Error),
+    #[error("Expected packaged artifacts for every buildpack target but the following were not found:\n{}", list_missing_artifacts(.0))]
+    MissingArtifacts(Vec<String>),
+    #[error("Could not list the files changed since {0}\nError: {1}")]
+    ListingChangedFiles(String, #[source] GitError),
+    #[error("Could not read Cargo manifest\nPath: {}\nError: {}", .0.display(), .1)]
+    ReadingCargoManifest(PathBuf, #[source] std::io::Error),
+    #[error("Could not parse Cargo manifest\nPath: {}\nError: {}", .0.display(), .1)]
+    ParsingCargoManifest(PathBuf, #[source] toml::de::Error),
     #[error("Unknown target configuration. Couldn't determine a rust triple for {0:?}.")]
     UnknownRustTarget(BuildpackTarget),
     #[error("Couldn't determine buildpack type. Found evidence for two or more buildpack types (bash, composite, libcnb.rs) in {0}.")]
@@ -37,3 +69,11 @@ fn list_versions(versions: &HashSet<String>) -> String {
         .collect::<Vec<_>>()
         .join("\n")
 }
+
+fn list_missing_artifacts(missing_artifacts: &[String]) -> String {
+    missing_artifacts
+        .iter()
+        .map(|missing_artifact| format!("• {missing_artifact}"))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
@@ -1,11 +1,21 @@
-use crate::buildpacks::{find_releasable_buildpacks, read_buildpack_descriptor};
-use crate::changelog::Changelog;
+use crate::buildpacks::{
+    buildpack_changelog_path, find_releasable_buildpacks, read_buildpack_descriptor,
+};
+use crate::changelog::{changes_by_group, Changelog};
 use crate::commands::generate_changelog::errors::Error;
 use crate::github::actions;
-use clap::Parser;
-use libcnb_data::buildpack::BuildpackId;
-use std::collections::{BTreeMap, HashMap};
-use std::path::PathBuf;
+use crate::github::pull_requests::pull_request_author;
+use clap::{Parser, ValueEnum};
+use globset::Glob;
+use indexmap::IndexMap;
+use lazy_static::lazy_static;
+use libcnb_data::buildpack::{BuildpackDescriptor, BuildpackId};
+use regex::Regex;
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
 
 type Result<T> = std::result::Result<T, Error>;
 
@@ -16,6 +26,45 @@ pub(crate) struct GenerateChangelogArgs {
     pub(crate) unreleased: bool,
     #[arg(long, group = "section")]
     pub(crate) version: Option<String>,
+    #[arg(long)]
+    pub(crate) with_contributors: bool,
+    /// Repository (e.g.; heroku/buildpacks-nodejs) that bare `#123` pull request references
+    /// belong to. Defaults to `GITHUB_REPOSITORY`.
+    #[arg(long)]
+    pub(crate) repository: Option<String>,
+    #[arg(long, value_enum, default_value_t = ChangelogFormat::Markdown, conflicts_with = "with_contributors")]
+    pub(crate) format: ChangelogFormat,
+    /// File that the `jsonl` format is written to, one buildpack per line.
+    #[arg(long, required_if_eq("format", "jsonl"))]
+    pub(crate) output_file: Option<PathBuf>,
+    /// Leaves out buildpacks matching the given glob, by buildpack id or directory.
+    #[arg(long)]
+    pub(crate) exclude: Vec<Glob>,
+    /// Heading of each buildpack's section, with `{id}` and `{version}` placeholders (e.g.;
+    /// `{id} {version}`). The version is the one given with `--version`, or else the buildpack's
+    /// current version.
+    #[arg(long, default_value = DEFAULT_HEADING_TEMPLATE)]
+    pub(crate) heading_template: String,
+    /// Appends an HTML anchor named after the buildpack id (e.g.;
+    /// `<a id="heroku-nodejs-engine"></a>`) to each buildpack's heading, so links to a section
+    /// keep working when the heading text changes.
+    #[arg(long)]
+    pub(crate) heading_anchors: bool,
+}
+
+const DEFAULT_HEADING_TEMPLATE: &str = "{id}";
+
+#[derive(ValueEnum, Debug, Clone, Copy, Eq, PartialEq)]
+pub(crate) enum ChangelogFormat {
+    Markdown,
+    Jsonl,
+}
+
+#[derive(Serialize, Debug, Eq, PartialEq)]
+struct ChangelogLine {
+    buildpack_id: String,
+    version: Option<String>,
+    entries: IndexMap<String, Vec<String>>,
 }
 
 enum ChangelogEntryType {
@@ -31,28 +80,72 @@ enum ChangelogEntry {
 
 pub(crate) fn execute(args: GenerateChangelogArgs) -> Result<()> {
     let current_dir = std::env::current_dir().map_err(Error::GetCurrentDir)?;
-    let buildpack_dirs =
-        find_releasable_buildpacks(&current_dir).map_err(Error::FindReleasableBuildpacks)?;
+    let buildpack_dirs = find_releasable_buildpacks(&current_dir, &args.exclude)
+        .map_err(Error::FindReleasableBuildpacks)?;
 
     let changelog_entry_type = match args.version {
         Some(version) => ChangelogEntryType::Version(version),
         None => ChangelogEntryType::Unreleased,
     };
 
-    let changes_by_buildpack = buildpack_dirs
+    let buildpack_descriptors = buildpack_dirs
         .iter()
         .map(|dir| {
             read_buildpack_descriptor(dir)
                 .map_err(Error::ReadBuildpackDescriptor)
-                .map(|buildpack_descriptor| buildpack_descriptor.buildpack().id.clone())
-                .and_then(|buildpack_id| {
-                    read_changelog_entry(&dir.join("CHANGELOG.md"), &changelog_entry_type)
-                        .map(|contents| (buildpack_id, contents))
-                })
+                .map(|buildpack_descriptor| (dir, buildpack_descriptor))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    if let (ChangelogFormat::Jsonl, Some(output_file)) = (args.format, &args.output_file) {
+        return write_changelog_lines(output_file, &buildpack_descriptors, &changelog_entry_type);
+    }
+
+    let changes_by_buildpack = buildpack_descriptors
+        .iter()
+        .map(|(dir, buildpack_descriptor)| {
+            read_changelog_entry(
+                &buildpack_changelog_path(dir, buildpack_descriptor),
+                &changelog_entry_type,
+            )
+            .map(|contents| (buildpack_descriptor.buildpack().id.clone(), contents))
         })
         .collect::<Result<HashMap<_, _>>>()?;
 
-    let changelog = generate_changelog(&changes_by_buildpack);
+    let headings = buildpack_descriptors
+        .iter()
+        .map(|(_, buildpack_descriptor)| {
+            let buildpack = buildpack_descriptor.buildpack();
+            let version = match &changelog_entry_type {
+                ChangelogEntryType::Version(version) => version.clone(),
+                ChangelogEntryType::Unreleased => buildpack.version.to_string(),
+            };
+            let heading = format_heading(&args.heading_template, &buildpack.id, &version);
+            let heading = if args.heading_anchors {
+                format!("{heading} {}", heading_anchor(&buildpack.id))
+            } else {
+                heading
+            };
+            (buildpack.id.clone(), heading)
+        })
+        .collect::<HashMap<_, _>>();
+
+    let mut changelog = generate_changelog(&changes_by_buildpack, &headings);
+
+    if args.with_contributors {
+        let repository = args
+            .repository
+            .or_else(|| std::env::var("GITHUB_REPOSITORY").ok());
+        let pull_requests = changes_by_buildpack
+            .values()
+            .filter_map(|changes| match changes {
+                ChangelogEntry::Changes(value) => Some(value.as_str()),
+                _ => None,
+            })
+            .flat_map(|value| find_pull_request_references(value, repository.as_deref()))
+            .collect::<BTreeSet<_>>();
+        changelog.push_str(&generate_contributors(&find_contributors(&pull_requests)));
+    }
 
     actions::set_output("changelog", changelog).map_err(Error::SetActionOutput)?;
 
@@ -87,15 +180,49 @@ fn read_changelog_entry(
     })
 }
 
-fn generate_changelog(changes_by_buildpack: &HashMap<BuildpackId, ChangelogEntry>) -> String {
+fn format_heading(template: &str, buildpack_id: &BuildpackId, version: &str) -> String {
+    template
+        .replace("{id}", buildpack_id.as_str())
+        .replace("{version}", version)
+}
+
+// Anchor ids are the buildpack id with anything other than letters and digits replaced by `-`
+// (e.g.; `heroku/nodejs-engine` becomes `heroku-nodejs-engine`).
+fn heading_anchor(buildpack_id: &BuildpackId) -> String {
+    let anchor_id = buildpack_id
+        .as_str()
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_lowercase()
+            } else {
+                '-'
+            }
+        })
+        .collect::<String>();
+    format!("<a id=\"{anchor_id}\"></a>")
+}
+
+// Sections are ordered by buildpack id and headed by the buildpack id unless another heading is
+// given for it.
+fn generate_changelog(
+    changes_by_buildpack: &HashMap<BuildpackId, ChangelogEntry>,
+    headings: &HashMap<BuildpackId, String>,
+) -> String {
     let changelog = changes_by_buildpack
         .iter()
-        .map(|(buildpack_id, changes)| (buildpack_id.to_string(), changes))
+        .map(|(buildpack_id, changes)| {
+            let heading = headings
+                .get(buildpack_id)
+                .cloned()
+                .unwrap_or_else(|| buildpack_id.to_string());
+            (buildpack_id.to_string(), (heading, changes))
+        })
         .collect::<BTreeMap<_, _>>()
-        .into_iter()
-        .filter_map(|(buildpack_id, changes)| match changes {
-            ChangelogEntry::Empty => Some(format!("## {buildpack_id}\n\n- No changes.")),
-            ChangelogEntry::Changes(value) => Some(format!("## {buildpack_id}\n\n{value}")),
+        .into_values()
+        .filter_map(|(heading, changes)| match changes {
+            ChangelogEntry::Empty => Some(format!("## {heading}\n\n- No changes.")),
+            ChangelogEntry::Changes(value) => Some(format!("## {heading}\n\n{value}")),
             ChangelogEntry::VersionNotPresent => None,
         })
         .collect::<Vec<_>>()
@@ -103,10 +230,142 @@ fn generate_changelog(changes_by_buildpack: &HashMap<BuildpackId, ChangelogEntry
     format!("{}\n\n", changelog.trim())
 }
 
+fn generate_changelog_line(
+    buildpack_id: &BuildpackId,
+    changes: &ChangelogEntry,
+    version: Option<&str>,
+) -> Option<ChangelogLine> {
+    let entries = match changes {
+        ChangelogEntry::Empty => IndexMap::new(),
+        ChangelogEntry::Changes(value) => changes_by_group(value),
+        ChangelogEntry::VersionNotPresent => return None,
+    };
+    Some(ChangelogLine {
+        buildpack_id: buildpack_id.to_string(),
+        version: version.map(ToString::to_string),
+        entries,
+    })
+}
+
+// Buildpacks are written in id order, one at a time: each changelog is read, serialized, and
+// written before the next one is read, so large repositories don't need the whole changelog in
+// memory. The file isn't subject to the size limits of `GITHUB_OUTPUT` either.
+fn write_changelog_lines(
+    path: &Path,
+    buildpack_descriptors: &[(&PathBuf, BuildpackDescriptor)],
+    changelog_entry_type: &ChangelogEntryType,
+) -> Result<()> {
+    let version = match changelog_entry_type {
+        ChangelogEntryType::Version(version) => Some(version.as_str()),
+        ChangelogEntryType::Unreleased => None,
+    };
+    let mut buildpack_descriptors = buildpack_descriptors.iter().collect::<Vec<_>>();
+    buildpack_descriptors
+        .sort_by_key(|(_, buildpack_descriptor)| buildpack_descriptor.buildpack().id.to_string());
+
+    let file = File::create(path).map_err(|e| Error::WritingOutputFile(path.to_path_buf(), e))?;
+    let mut writer = BufWriter::new(file);
+    for (dir, buildpack_descriptor) in buildpack_descriptors {
+        let changes = read_changelog_entry(
+            &buildpack_changelog_path(dir, buildpack_descriptor),
+            changelog_entry_type,
+        )?;
+        let Some(line) =
+            generate_changelog_line(&buildpack_descriptor.buildpack().id, &changes, version)
+        else {
+            continue;
+        };
+        serde_json::to_writer(&mut writer, &line).map_err(Error::SerializingJson)?;
+        writeln!(writer).map_err(|e| Error::WritingOutputFile(path.to_path_buf(), e))?;
+    }
+    writer
+        .flush()
+        .map_err(|e| Error::WritingOutputFile(path.to_path_buf(), e))
+}
+
+// Finds links to pull requests (e.g.; `https://github.com/heroku/buildpacks-nodejs/pull/512`)
+// and, when the repository is known, bare references like `(#512)`, which may also be issues. A
+// `#512` used as the text of a markdown link is skipped since the link itself is matched.
+fn find_pull_request_references(value: &str, repository: Option<&str>) -> Vec<(String, u64)> {
+    lazy_static! {
+        static ref PULL_REQUEST_URL: Regex =
+            Regex::new(r"https://github\.com/([\w.-]+/[\w.-]+)/pull/(\d+)")
+                .expect("Should be a valid regex");
+        static ref PULL_REQUEST_NUMBER: Regex =
+            Regex::new(r"(?:^|[\s(])#(\d+)\b").expect("Should be a valid regex");
+    }
+
+    let linked = PULL_REQUEST_URL
+        .captures_iter(value)
+        .filter_map(|captures| {
+            captures[2]
+                .parse()
+                .ok()
+                .map(|number| (captures[1].to_string(), number))
+        });
+
+    let bare = repository.into_iter().flat_map(|repository| {
+        PULL_REQUEST_NUMBER
+            .captures_iter(value)
+            .filter_map(move |captures| {
+                captures[1]
+                    .parse()
+                    .ok()
+                    .map(|number| (repository.to_string(), number))
+            })
+    });
+
+    linked.chain(bare).collect()
+}
+
+// A reference that turns out to be an issue, or that can't be looked up, only costs its author
+// a mention so it's skipped with a warning rather than failing the changelog.
+fn find_contributors(pull_requests: &BTreeSet<(String, u64)>) -> BTreeSet<String> {
+    let mut contributors = BTreeSet::new();
+    for (repository, number) in pull_requests {
+        match pull_request_author(repository, *number) {
+            Ok(Some(author)) => {
+                contributors.insert(author);
+            }
+            Ok(None) => {
+                eprintln!(
+                    "⚠️ Skipped {repository}#{number}, it's an issue rather than a pull request"
+                );
+            }
+            Err(e) => {
+                eprintln!(
+                    "⚠️ Skipped {repository}#{number}, its author couldn't be looked up\n{e}"
+                );
+            }
+        }
+    }
+    contributors
+}
+
+// Bot accounts (e.g.; dependabot) aren't included in release shout-outs.
+fn generate_contributors(contributors: &BTreeSet<String>) -> String {
+    let contributors = contributors
+        .iter()
+        .filter(|login| !login.is_empty() && !login.ends_with("[bot]"))
+        .map(|login| format!("- @{login}"))
+        .collect::<Vec<_>>();
+    if contributors.is_empty() {
+        String::new()
+    } else {
+        format!("## Contributors\n\n{}\n\n", contributors.join("\n"))
+    }
+}
+
 #[cfg(test)]
 mod test {
-    use crate::commands::generate_changelog::command::{generate_changelog, ChangelogEntry};
+    use crate::commands::generate_changelog::command::{
+        find_pull_request_references, format_heading, generate_changelog, generate_changelog_line,
+        generate_contributors, heading_anchor, ChangelogEntry, ChangelogLine,
+    };
+    use indexmap::IndexMap;
+    use libcnb_data::buildpack::BuildpackId;
     use libcnb_data::buildpack_id;
+    use std::collections::BTreeSet;
     use std::collections::HashMap;
 
     #[test]
@@ -125,7 +384,7 @@ mod test {
         ]);
 
         assert_eq!(
-            generate_changelog(&values),
+            generate_changelog(&values, &HashMap::new()),
             r"## a
 
 - change a.1
@@ -142,4 +401,122 @@ mod test {
 "
         );
     }
+
+    #[test]
+    fn test_generating_changelog_with_headings() {
+        let values = HashMap::from([
+            (
+                buildpack_id!("heroku/nodejs-engine"),
+                ChangelogEntry::Changes("- change a.1".to_string()),
+            ),
+            (buildpack_id!("heroku/nodejs-npm"), ChangelogEntry::Empty),
+        ]);
+        let headings = HashMap::from([(
+            buildpack_id!("heroku/nodejs-engine"),
+            format_heading(
+                "{id} {version}",
+                &buildpack_id!("heroku/nodejs-engine"),
+                "3.2.1",
+            ),
+        )]);
+
+        assert_eq!(
+            generate_changelog(&values, &headings),
+            r"## heroku/nodejs-engine 3.2.1
+
+- change a.1
+
+## heroku/nodejs-npm
+
+- No changes.
+
+"
+        );
+    }
+
+    #[test]
+    fn test_heading_anchor() {
+        assert_eq!(
+            heading_anchor(&buildpack_id!("heroku/nodejs-engine")),
+            r#"<a id="heroku-nodejs-engine"></a>"#
+        );
+        assert_eq!(
+            heading_anchor(&buildpack_id!("Heroku/Go.Modules")),
+            r#"<a id="heroku-go-modules"></a>"#
+        );
+    }
+
+    #[test]
+    fn test_find_pull_request_references() {
+        let changes =
+            "- Added Yarn 4 support. ([#512](https://github.com/heroku/buildpacks-nodejs/pull/512))
+- Fixed a cache issue (#498).
+- Updated `heroku/nodejs-engine` to `3.0.0`. #notanumber";
+
+        assert_eq!(
+            find_pull_request_references(changes, Some("heroku/buildpacks-nodejs")),
+            vec![
+                ("heroku/buildpacks-nodejs".to_string(), 512),
+                ("heroku/buildpacks-nodejs".to_string(), 498)
+            ]
+        );
+        assert_eq!(
+            find_pull_request_references(changes, None),
+            vec![("heroku/buildpacks-nodejs".to_string(), 512)]
+        );
+    }
+
+    #[test]
+    fn test_generating_contributors() {
+        let contributors = BTreeSet::from([
+            "edmorley".to_string(),
+            "dependabot[bot]".to_string(),
+            "colincasey".to_string(),
+        ]);
+        assert_eq!(
+            generate_contributors(&contributors),
+            "## Contributors\n\n- @colincasey\n- @edmorley\n\n"
+        );
+        assert_eq!(generate_contributors(&BTreeSet::new()), "");
+    }
+
+    #[test]
+    fn test_generating_changelog_lines() {
+        let values = HashMap::from([
+            (
+                buildpack_id!("c"),
+                ChangelogEntry::Changes("### Added\n\n- Support for `foo`.".to_string()),
+            ),
+            (buildpack_id!("a"), ChangelogEntry::Empty),
+            (buildpack_id!("b"), ChangelogEntry::VersionNotPresent),
+        ]);
+
+        let line = |id: BuildpackId, version: Option<&str>| {
+            generate_changelog_line(&id, &values[&id], version)
+        };
+        assert_eq!(
+            line(buildpack_id!("a"), Some("1.0.0")),
+            Some(ChangelogLine {
+                buildpack_id: "a".to_string(),
+                version: Some("1.0.0".to_string()),
+                entries: IndexMap::new(),
+            })
+        );
+        assert_eq!(line(buildpack_id!("b"), Some("1.0.0")), None);
+        assert_eq!(
+            line(buildpack_id!("c"), Some("1.0.0")),
+            Some(ChangelogLine {
+                buildpack_id: "c".to_string(),
+                version: Some("1.0.0".to_string()),
+                entries: IndexMap::from([(
+                    "Added".to_string(),
+                    vec!["Support for `foo`.".to_string()]
+                )]),
+            })
+        );
+        assert_eq!(
+            serde_json::to_string(&line(buildpack_id!("c"), None)).unwrap(),
+            r#"{"buildpack_id":"c","version":null,"entries":{"Added":["Support for `foo`."]}}"#
+        );
+    }
 }
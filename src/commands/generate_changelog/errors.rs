@@ -15,6 +15,10 @@ pub(crate) enum Error {
     ReadingChangelog(PathBuf, #[source] std::io::Error),
     #[error("Could not parse changelog\nPath: {0}\nError: {1}")]
     ParsingChangelog(PathBuf, #[source] ChangelogError),
+    #[error("Could not serialize changelog into json\nError: {0}")]
+    SerializingJson(#[source] serde_json::Error),
+    #[error("Could not write changelog\nPath: {0}\nError: {1}")]
+    WritingOutputFile(PathBuf, #[source] std::io::Error),
     #[error(transparent)]
     SetActionOutput(WriteActionDataError),
 }
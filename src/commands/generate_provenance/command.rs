@@ -0,0 +1,460 @@
+use crate::buildpacks::calculate_digest;
+use crate::commands::generate_provenance::errors::Error;
+use crate::commands::resolve_path;
+use crate::github::actions;
+use clap::Parser;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+type Result<T> = std::result::Result<T, Error>;
+
+const IN_TOTO_STATEMENT_TYPE: &str = "https://in-toto.io/Statement/v1";
+const SLSA_PROVENANCE_PREDICATE_TYPE: &str = "https://slsa.dev/provenance/v1";
+const GITHUB_WORKFLOW_BUILD_TYPE: &str = "https://actions.github.io/buildtypes/workflow/v1";
+const IN_TOTO_MEDIA_TYPE: &str = "application/vnd.in-toto+json";
+
+#[derive(Parser, Debug)]
+#[command(author, version, about = "Generates SLSA provenance statements for published images", long_about = None, disable_version_flag = true)]
+pub(crate) struct GenerateProvenanceArgs {
+    #[arg(long = "image", required = true)]
+    pub(crate) images: Vec<String>,
+    #[arg(long = "base-image")]
+    pub(crate) base_images: Vec<String>,
+    #[arg(long)]
+    pub(crate) output_dir: Option<PathBuf>,
+    #[arg(long)]
+    pub(crate) submit: bool,
+}
+
+pub(crate) fn execute(args: &GenerateProvenanceArgs) -> Result<()> {
+    let current_dir = std::env::current_dir().map_err(Error::GetCurrentDir)?;
+    let output_dir = resolve_path(
+        match &args.output_dir {
+            Some(path) => path,
+            None => Path::new("./provenance"),
+        },
+        &current_dir,
+    );
+
+    let github_context = GithubContext::from_env(|name| std::env::var(name).ok())?;
+
+    let base_images = args
+        .base_images
+        .iter()
+        .map(|reference| resolve_image(reference))
+        .collect::<Result<Vec<_>>>()?;
+
+    std::fs::create_dir_all(&output_dir)
+        .map_err(|e| Error::CreatingOutputDir(output_dir.clone(), e))?;
+
+    let mut provenance_files = vec![];
+
+    for reference in &args.images {
+        let image = resolve_image(reference)?;
+        let provenance_file = output_dir.join(provenance_file_name(&image));
+        // tags pointing to the same digest share one provenance statement
+        if provenance_files.contains(&provenance_file) {
+            continue;
+        }
+
+        let statement = generate_statement(&image, &base_images, &github_context);
+        let contents = serde_json::to_string_pretty(&statement).map_err(Error::SerializingJson)?;
+
+        std::fs::write(&provenance_file, contents)
+            .map_err(|e| Error::WritingProvenance(provenance_file.clone(), e))?;
+        eprintln!(
+            "✅️ Generated provenance for {reference}: {}",
+            provenance_file.display()
+        );
+
+        if args.submit {
+            submit_provenance(&image, &provenance_file)?;
+            eprintln!("✅️ Attached provenance to {}", image.pinned_reference());
+        }
+
+        provenance_files.push(provenance_file);
+    }
+
+    actions::set_output(
+        "provenance_files",
+        serde_json::to_string(&provenance_files).map_err(Error::SerializingJson)?,
+    )
+    .map_err(Error::WriteActionData)?;
+
+    Ok(())
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+struct ResolvedImage {
+    name: String,
+    algorithm: String,
+    digest: String,
+}
+
+impl ResolvedImage {
+    fn pinned_reference(&self) -> String {
+        format!("{}@{}:{}", self.name, self.algorithm, self.digest)
+    }
+}
+
+fn resolve_image(reference: &str) -> Result<ResolvedImage> {
+    let digest = match reference.split_once('@') {
+        Some((_, digest)) => digest.to_string(),
+        None => calculate_digest(reference).map_err(Error::CalculateDigest)?,
+    };
+    parse_image(reference, &digest)
+}
+
+fn parse_image(reference: &str, digest: &str) -> Result<ResolvedImage> {
+    let (algorithm, digest) = digest
+        .split_once(':')
+        .filter(|(algorithm, digest)| !algorithm.is_empty() && !digest.is_empty())
+        .ok_or(Error::InvalidDigest(
+            reference.to_string(),
+            digest.to_string(),
+        ))?;
+
+    Ok(ResolvedImage {
+        name: image_name(reference).to_string(),
+        algorithm: algorithm.to_string(),
+        digest: digest.to_string(),
+    })
+}
+
+// Strips any digest or tag from an image reference. A `:` before the last `/` belongs to a
+// registry port, not a tag.
+fn image_name(reference: &str) -> &str {
+    let name = reference
+        .split_once('@')
+        .map_or(reference, |(name, _)| name);
+    match name.rfind(':') {
+        Some(index) if !name[index..].contains('/') => &name[..index],
+        _ => name,
+    }
+}
+
+// Named after the digest as well, since several tags of a repository can be given.
+fn provenance_file_name(image: &ResolvedImage) -> String {
+    let name = format!("{}-{}-{}", image.name, image.algorithm, image.digest)
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+        .collect::<String>();
+    format!("{name}.intoto.json")
+}
+
+fn submit_provenance(image: &ResolvedImage, provenance_file: &Path) -> Result<()> {
+    let pinned_reference = image.pinned_reference();
+    let status = Command::new("oras")
+        .args(["attach", "--artifact-type", IN_TOTO_MEDIA_TYPE])
+        .arg(&pinned_reference)
+        .arg(format!(
+            "{}:{IN_TOTO_MEDIA_TYPE}",
+            provenance_file.display()
+        ))
+        .status()
+        .map_err(|e| Error::SubmitCommandFailure(pinned_reference.clone(), e))?;
+
+    if status.success() {
+        Ok(())
+    } else {
+        Err(Error::SubmitExitStatus(pinned_reference, status))
+    }
+}
+
+// The builder id uses the workflow ref, which is the same value GitHub issues in the
+// `job_workflow_ref` claim of the workflow's OIDC token.
+#[derive(Debug)]
+struct GithubContext {
+    server_url: String,
+    repository: String,
+    repository_id: String,
+    repository_owner_id: String,
+    sha: String,
+    git_ref: String,
+    workflow_ref: String,
+    event_name: String,
+    run_id: String,
+    run_attempt: String,
+}
+
+impl GithubContext {
+    fn from_env(lookup: impl Fn(&str) -> Option<String>) -> Result<Self> {
+        let var = |name: &str| {
+            lookup(name).ok_or_else(|| Error::MissingEnvironmentVariable(name.to_string()))
+        };
+        Ok(GithubContext {
+            server_url: var("GITHUB_SERVER_URL")?,
+            repository: var("GITHUB_REPOSITORY")?,
+            repository_id: var("GITHUB_REPOSITORY_ID")?,
+            repository_owner_id: var("GITHUB_REPOSITORY_OWNER_ID")?,
+            sha: var("GITHUB_SHA")?,
+            git_ref: var("GITHUB_REF")?,
+            workflow_ref: var("GITHUB_WORKFLOW_REF")?,
+            event_name: var("GITHUB_EVENT_NAME")?,
+            run_id: var("GITHUB_RUN_ID")?,
+            run_attempt: var("GITHUB_RUN_ATTEMPT")?,
+        })
+    }
+
+    fn workflow_path(&self) -> &str {
+        let path = self
+            .workflow_ref
+            .strip_prefix(&format!("{}/", self.repository))
+            .unwrap_or(&self.workflow_ref);
+        path.split_once('@').map_or(path, |(path, _)| path)
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct Statement {
+    #[serde(rename = "_type")]
+    kind: &'static str,
+    subject: Vec<ResourceDescriptor>,
+    #[serde(rename = "predicateType")]
+    predicate_type: &'static str,
+    predicate: Provenance,
+}
+
+#[derive(Debug, Serialize)]
+struct ResourceDescriptor {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    uri: Option<String>,
+    digest: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Provenance {
+    build_definition: BuildDefinition,
+    run_details: RunDetails,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct BuildDefinition {
+    build_type: &'static str,
+    external_parameters: serde_json::Value,
+    internal_parameters: serde_json::Value,
+    resolved_dependencies: Vec<ResourceDescriptor>,
+}
+
+#[derive(Debug, Serialize)]
+struct RunDetails {
+    builder: Builder,
+    metadata: RunMetadata,
+}
+
+#[derive(Debug, Serialize)]
+struct Builder {
+    id: String,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct RunMetadata {
+    invocation_id: String,
+}
+
+fn generate_statement(
+    image: &ResolvedImage,
+    base_images: &[ResolvedImage],
+    github_context: &GithubContext,
+) -> Statement {
+    let GithubContext {
+        server_url,
+        repository,
+        sha,
+        git_ref,
+        ..
+    } = github_context;
+
+    let source = ResourceDescriptor {
+        name: None,
+        uri: Some(format!("git+{server_url}/{repository}@{git_ref}")),
+        digest: BTreeMap::from([("gitCommit".to_string(), sha.clone())]),
+    };
+
+    let resolved_dependencies = std::iter::once(source)
+        .chain(base_images.iter().map(|base_image| ResourceDescriptor {
+            name: None,
+            uri: Some(format!("pkg:docker/{}", base_image.name)),
+            digest: BTreeMap::from([(base_image.algorithm.clone(), base_image.digest.clone())]),
+        }))
+        .collect();
+
+    Statement {
+        kind: IN_TOTO_STATEMENT_TYPE,
+        subject: vec![ResourceDescriptor {
+            name: Some(image.name.clone()),
+            uri: None,
+            digest: BTreeMap::from([(image.algorithm.clone(), image.digest.clone())]),
+        }],
+        predicate_type: SLSA_PROVENANCE_PREDICATE_TYPE,
+        predicate: Provenance {
+            build_definition: BuildDefinition {
+                build_type: GITHUB_WORKFLOW_BUILD_TYPE,
+                external_parameters: serde_json::json!({
+                    "workflow": {
+                        "ref": git_ref,
+                        "repository": format!("{server_url}/{repository}"),
+                        "path": github_context.workflow_path(),
+                    }
+                }),
+                internal_parameters: serde_json::json!({
+                    "github": {
+                        "event_name": github_context.event_name,
+                        "repository_id": github_context.repository_id,
+                        "repository_owner_id": github_context.repository_owner_id,
+                    }
+                }),
+                resolved_dependencies,
+            },
+            run_details: RunDetails {
+                builder: Builder {
+                    id: format!("{server_url}/{}", github_context.workflow_ref),
+                },
+                metadata: RunMetadata {
+                    invocation_id: format!(
+                        "{server_url}/{repository}/actions/runs/{}/attempts/{}",
+                        github_context.run_id, github_context.run_attempt
+                    ),
+                },
+            },
+        },
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::commands::generate_provenance::command::{
+        generate_statement, image_name, parse_image, provenance_file_name, GithubContext,
+    };
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_image_name() {
+        assert_eq!(
+            image_name("docker.io/heroku/buildpack-java:1.0.0"),
+            "docker.io/heroku/buildpack-java"
+        );
+        assert_eq!(
+            image_name("localhost:5000/heroku/buildpack-java@sha256:abc"),
+            "localhost:5000/heroku/buildpack-java"
+        );
+        assert_eq!(
+            image_name("localhost:5000/heroku/buildpack-java"),
+            "localhost:5000/heroku/buildpack-java"
+        );
+    }
+
+    #[test]
+    fn test_parse_image_with_invalid_digest() {
+        assert!(parse_image("docker.io/heroku/buildpack-java:1.0.0", "abc").is_err());
+    }
+
+    #[test]
+    fn test_provenance_file_name_for_tags_of_one_repository() {
+        let image = parse_image("docker.io/heroku/buildpack-java:1.0.0", "sha256:abc").unwrap();
+        let other_image = parse_image(
+            "docker.io/heroku/buildpack-java:1.0.0_linux-arm64",
+            "sha256:def",
+        )
+        .unwrap();
+
+        assert_eq!(
+            provenance_file_name(&image),
+            "docker-io-heroku-buildpack-java-sha256-abc.intoto.json"
+        );
+        assert_eq!(
+            provenance_file_name(&other_image),
+            "docker-io-heroku-buildpack-java-sha256-def.intoto.json"
+        );
+    }
+
+    #[test]
+    fn test_github_context_requires_environment() {
+        let env = HashMap::from([("GITHUB_SERVER_URL", "https://github.com")]);
+        assert!(GithubContext::from_env(|name| env.get(name).map(ToString::to_string)).is_err());
+    }
+
+    #[test]
+    fn test_generate_statement() {
+        let env = HashMap::from([
+            ("GITHUB_SERVER_URL", "https://github.com"),
+            ("GITHUB_REPOSITORY", "heroku/buildpacks-java"),
+            ("GITHUB_REPOSITORY_ID", "123"),
+            ("GITHUB_REPOSITORY_OWNER_ID", "456"),
+            ("GITHUB_SHA", "d2b8c3a"),
+            ("GITHUB_REF", "refs/heads/main"),
+            (
+                "GITHUB_WORKFLOW_REF",
+                "heroku/buildpacks-java/.github/workflows/release.yml@refs/heads/main",
+            ),
+            ("GITHUB_EVENT_NAME", "workflow_dispatch"),
+            ("GITHUB_RUN_ID", "789"),
+            ("GITHUB_RUN_ATTEMPT", "1"),
+        ]);
+        let github_context =
+            GithubContext::from_env(|name| env.get(name).map(ToString::to_string)).unwrap();
+        let image = parse_image("docker.io/heroku/buildpack-java:1.0.0", "sha256:abc").unwrap();
+        let base_image = parse_image("heroku/heroku:24", "sha256:def").unwrap();
+
+        assert_eq!(
+            provenance_file_name(&image),
+            "docker-io-heroku-buildpack-java-sha256-abc.intoto.json"
+        );
+
+        let statement = generate_statement(&image, &[base_image], &github_context);
+        assert_eq!(
+            serde_json::to_value(statement).unwrap(),
+            serde_json::json!({
+                "_type": "https://in-toto.io/Statement/v1",
+                "subject": [
+                    { "name": "docker.io/heroku/buildpack-java", "digest": { "sha256": "abc" } }
+                ],
+                "predicateType": "https://slsa.dev/provenance/v1",
+                "predicate": {
+                    "buildDefinition": {
+                        "buildType": "https://actions.github.io/buildtypes/workflow/v1",
+                        "externalParameters": {
+                            "workflow": {
+                                "ref": "refs/heads/main",
+                                "repository": "https://github.com/heroku/buildpacks-java",
+                                "path": ".github/workflows/release.yml"
+                            }
+                        },
+                        "internalParameters": {
+                            "github": {
+                                "event_name": "workflow_dispatch",
+                                "repository_id": "123",
+                                "repository_owner_id": "456"
+                            }
+                        },
+                        "resolvedDependencies": [
+                            {
+                                "uri": "git+https://github.com/heroku/buildpacks-java@refs/heads/main",
+                                "digest": { "gitCommit": "d2b8c3a" }
+                            },
+                            {
+                                "uri": "pkg:docker/heroku/heroku",
+                                "digest": { "sha256": "def" }
+                            }
+                        ]
+                    },
+                    "runDetails": {
+                        "builder": {
+                            "id": "https://github.com/heroku/buildpacks-java/.github/workflows/release.yml@refs/heads/main"
+                        },
+                        "metadata": {
+                            "invocationId": "https://github.com/heroku/buildpacks-java/actions/runs/789/attempts/1"
+                        }
+                    }
+                }
+            })
+        );
+    }
+}
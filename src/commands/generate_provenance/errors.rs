@@ -0,0 +1,28 @@
+use crate::buildpacks::CalculateDigestError;
+use crate::github::actions::WriteActionDataError;
+use std::path::PathBuf;
+use std::process::ExitStatus;
+
+#[derive(Debug, thiserror::Error)]
+pub(crate) enum Error {
+    #[error("Failed to get current directory\nError: {0}")]
+    GetCurrentDir(std::io::Error),
+    #[error("Missing environment variable {0}, provenance can only be generated from a GitHub Actions workflow")]
+    MissingEnvironmentVariable(String),
+    #[error(transparent)]
+    CalculateDigest(CalculateDigestError),
+    #[error("Invalid image digest for {0}\nDigest: {1}")]
+    InvalidDigest(String, String),
+    #[error("Could not serialize provenance into json\nError: {0}")]
+    SerializingJson(#[source] serde_json::Error),
+    #[error("Could not create provenance directory\nPath: {0}\nError: {1}")]
+    CreatingOutputDir(PathBuf, #[source] std::io::Error),
+    #[error("Could not write provenance\nPath: {0}\nError: {1}")]
+    WritingProvenance(PathBuf, #[source] std::io::Error),
+    #[error("Failed to execute oras attach for {0}\nError: {1}")]
+    SubmitCommandFailure(String, #[source] std::io::Error),
+    #[error("Command oras attach for {0} exited with a non-zero status\nStatus: {1}")]
+    SubmitExitStatus(String, ExitStatus),
+    #[error(transparent)]
+    WriteActionData(WriteActionDataError),
+}
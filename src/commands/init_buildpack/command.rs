@@ -0,0 +1,331 @@
+use crate::buildpacks::{find_releasable_buildpacks, read_buildpack_descriptor};
+use crate::changelog::Changelog;
+use crate::commands::init_buildpack::errors::Error;
+use crate::commands::resolve_path;
+use clap::{Parser, ValueEnum};
+use indexmap::IndexMap;
+use libcnb_data::buildpack::{BuildpackId, BuildpackVersion};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+type Result<T> = std::result::Result<T, Error>;
+
+#[derive(Parser, Debug)]
+#[command(author, version, about = "Scaffolds a new buildpack that the release workflows will pick up", long_about = None, disable_version_flag = true)]
+pub(crate) struct InitBuildpackArgs {
+    #[arg(long)]
+    pub(crate) id: String,
+    #[arg(long = "type", value_enum, default_value_t = BuildpackKind::Libcnb)]
+    pub(crate) kind: BuildpackKind,
+    #[arg(long)]
+    pub(crate) path: Option<PathBuf>,
+    #[arg(long)]
+    pub(crate) version: Option<String>,
+    #[arg(long)]
+    pub(crate) image_repository: Option<String>,
+    #[arg(long)]
+    pub(crate) owner_team: Option<String>,
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy, Eq, PartialEq)]
+pub(crate) enum BuildpackKind {
+    Bash,
+    Libcnb,
+}
+
+pub(crate) struct BuildpackTemplate {
+    pub(crate) id: BuildpackId,
+    pub(crate) version: BuildpackVersion,
+    pub(crate) kind: BuildpackKind,
+    pub(crate) image_repository: String,
+    pub(crate) owner_team: Option<String>,
+}
+
+pub(crate) fn execute(args: &InitBuildpackArgs) -> Result<()> {
+    let current_dir = std::env::current_dir().map_err(Error::GetCurrentDir)?;
+
+    let id =
+        BuildpackId::from_str(&args.id).map_err(|_| Error::InvalidBuildpackId(args.id.clone()))?;
+
+    let version = match &args.version {
+        Some(version) => BuildpackVersion::try_from(version.clone())
+            .map_err(|_| Error::InvalidBuildpackVersion(version.clone()))?,
+        None => read_existing_version(&current_dir)?,
+    };
+
+    let buildpack_dir = args.path.as_ref().map_or_else(
+        || current_dir.join("buildpacks").join(buildpack_name(&id)),
+        |path| resolve_path(path, &current_dir),
+    );
+
+    let image_repository = args
+        .image_repository
+        .clone()
+        .unwrap_or_else(|| default_image_repository(&id));
+
+    scaffold_buildpack(
+        &buildpack_dir,
+        &BuildpackTemplate {
+            id,
+            version,
+            kind: args.kind,
+            image_repository,
+            owner_team: args.owner_team.clone(),
+        },
+    )?;
+
+    eprintln!("✅️ Created buildpack: {}", buildpack_dir.display());
+
+    Ok(())
+}
+
+// New buildpacks are released together with the existing ones, which all share a single
+// version (see `prepare-release`), so the scaffolded buildpack starts out at that version.
+fn read_existing_version(dir: &Path) -> Result<BuildpackVersion> {
+    let versions = find_releasable_buildpacks(dir, &[])
+        .map_err(Error::FindReleasableBuildpacks)?
+        .iter()
+        .map(|buildpack_dir| {
+            read_buildpack_descriptor(buildpack_dir)
+                .map_err(Error::ReadBuildpackDescriptor)
+                .map(|descriptor| descriptor.buildpack().version.clone())
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let unique_versions = versions
+        .iter()
+        .map(ToString::to_string)
+        .collect::<HashSet<_>>();
+
+    match (versions.first(), unique_versions.len()) {
+        (None, _) => Ok(BuildpackVersion::new(0, 1, 0)),
+        (Some(version), 1) => Ok(version.clone()),
+        _ => Err(Error::NoFixedVersion(unique_versions)),
+    }
+}
+
+fn buildpack_name(id: &BuildpackId) -> &str {
+    id.as_str().rsplit('/').next().unwrap_or(id.as_str())
+}
+
+fn default_image_repository(id: &BuildpackId) -> String {
+    match id.as_str().rsplit_once('/') {
+        Some((namespace, name)) => format!("docker.io/{namespace}/buildpack-{name}"),
+        None => format!("docker.io/heroku/buildpack-{id}"),
+    }
+}
+
+pub(crate) fn scaffold_buildpack(dir: &Path, template: &BuildpackTemplate) -> Result<()> {
+    if dir
+        .read_dir()
+        .is_ok_and(|mut entries| entries.next().is_some())
+    {
+        Err(Error::DirectoryNotEmpty(dir.to_path_buf()))?;
+    }
+
+    write_file(&dir.join("buildpack.toml"), &buildpack_toml(template))?;
+
+    write_file(
+        &dir.join("CHANGELOG.md"),
+        &Changelog {
+            protected_preamble: None,
+            unreleased: None,
+            releases: IndexMap::new(),
+        }
+        .to_string(),
+    )?;
+
+    match template.kind {
+        BuildpackKind::Bash => {
+            write_executable(
+                &dir.join("bin").join("detect"),
+                "#!/usr/bin/env bash\n\nset -euo pipefail\n\nexit 0\n",
+            )?;
+            write_executable(
+                &dir.join("bin").join("build"),
+                "#!/usr/bin/env bash\n\nset -euo pipefail\n",
+            )?;
+        }
+        BuildpackKind::Libcnb => {
+            write_file(&dir.join("Cargo.toml"), &cargo_toml(&template.id))?;
+            write_file(&dir.join("src").join("main.rs"), &main_rs(&template.id))?;
+        }
+    }
+
+    Ok(())
+}
+
+fn buildpack_toml(template: &BuildpackTemplate) -> String {
+    let targets = match template.kind {
+        BuildpackKind::Bash => vec!["amd64"],
+        BuildpackKind::Libcnb => vec!["amd64", "arm64"],
+    };
+
+    let targets = targets
+        .iter()
+        .map(|arch| format!("[[targets]]\nos = \"linux\"\narch = \"{arch}\"\n"))
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    let owner = template
+        .owner_team
+        .as_ref()
+        .map(|team| format!("\n[metadata.release.owner]\nteam = \"{team}\"\n"))
+        .unwrap_or_default();
+
+    format!(
+        "api = \"0.10\"\n\n[buildpack]\nid = \"{}\"\nversion = \"{}\"\n\n{targets}\n[metadata.release]\nimage = {{ repository = \"{}\" }}\n{owner}",
+        template.id, template.version, template.image_repository
+    )
+}
+
+fn cargo_toml(id: &BuildpackId) -> String {
+    format!(
+        "[package]\nname = \"{}\"\nedition = \"2021\"\npublish = false\n\n[dependencies]\nlibcnb = \"0.26\"\n",
+        id.as_str().replace('/', "-")
+    )
+}
+
+fn main_rs(id: &BuildpackId) -> String {
+    let struct_name = format!(
+        "{}Buildpack",
+        buildpack_name(id)
+            .split(['-', '_'])
+            .map(|word| {
+                let mut chars = word.chars();
+                chars.next().map_or_else(String::new, |first| {
+                    first.to_uppercase().chain(chars).collect::<String>()
+                })
+            })
+            .collect::<String>()
+    );
+
+    format!(
+        r"use libcnb::build::{{BuildContext, BuildResult, BuildResultBuilder}};
+use libcnb::detect::{{DetectContext, DetectResult, DetectResultBuilder}};
+use libcnb::generic::{{GenericError, GenericMetadata, GenericPlatform}};
+use libcnb::{{buildpack_main, Buildpack}};
+
+pub(crate) struct {struct_name};
+
+impl Buildpack for {struct_name} {{
+    type Platform = GenericPlatform;
+    type Metadata = GenericMetadata;
+    type Error = GenericError;
+
+    fn detect(&self, _context: DetectContext<Self>) -> libcnb::Result<DetectResult, Self::Error> {{
+        DetectResultBuilder::pass().build()
+    }}
+
+    fn build(&self, _context: BuildContext<Self>) -> libcnb::Result<BuildResult, Self::Error> {{
+        BuildResultBuilder::new().build()
+    }}
+}}
+
+buildpack_main!({struct_name});
+"
+    )
+}
+
+fn write_file(path: &Path, contents: &str) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .map_err(|e| Error::CreatingDirectory(parent.to_path_buf(), e))?;
+    }
+    std::fs::write(path, contents).map_err(|e| Error::WritingFile(path.to_path_buf(), e))
+}
+
+fn write_executable(path: &Path, contents: &str) -> Result<()> {
+    write_file(path, contents)?;
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o755))
+            .map_err(|e| Error::WritingFile(path.to_path_buf(), e))?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use crate::buildpacks::{
+        find_releasable_buildpacks, read_buildpack_descriptor, read_image_repository_metadata,
+        read_owner_metadata, BuildpackOwner,
+    };
+    use crate::commands::init_buildpack::command::{
+        default_image_repository, scaffold_buildpack, BuildpackKind, BuildpackTemplate,
+    };
+    use crate::commands::init_buildpack::errors::Error;
+    use libcnb_data::buildpack::{BuildpackId, BuildpackVersion};
+    use std::str::FromStr;
+
+    fn template(kind: BuildpackKind) -> BuildpackTemplate {
+        BuildpackTemplate {
+            id: BuildpackId::from_str("heroku/nodejs-corepack").unwrap(),
+            version: BuildpackVersion::new(1, 2, 3),
+            kind,
+            image_repository: "docker.io/heroku/buildpack-nodejs-corepack".to_string(),
+            owner_team: Some("languages".to_string()),
+        }
+    }
+
+    #[test]
+    fn test_scaffold_buildpack_is_releasable() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let libcnb_dir = tmp_dir.path().join("buildpacks/libcnb");
+        let bash_dir = tmp_dir.path().join("buildpacks/bash");
+
+        scaffold_buildpack(&libcnb_dir, &template(BuildpackKind::Libcnb)).unwrap();
+        scaffold_buildpack(&bash_dir, &template(BuildpackKind::Bash)).unwrap();
+
+        let mut releasable = find_releasable_buildpacks(tmp_dir.path(), &[]).unwrap();
+        releasable.sort();
+        assert_eq!(releasable, vec![bash_dir.clone(), libcnb_dir.clone()]);
+
+        let descriptor = read_buildpack_descriptor(&libcnb_dir).unwrap();
+        assert_eq!(descriptor.buildpack().version.to_string(), "1.2.3");
+        assert_eq!(
+            read_image_repository_metadata(&descriptor),
+            Some("docker.io/heroku/buildpack-nodejs-corepack".to_string())
+        );
+        assert_eq!(
+            read_owner_metadata(&descriptor),
+            Some(BuildpackOwner {
+                team: "languages".to_string(),
+                language: None
+            })
+        );
+        assert!(libcnb_dir.join("Cargo.toml").exists());
+        assert!(std::fs::read_to_string(libcnb_dir.join("src/main.rs"))
+            .unwrap()
+            .contains("buildpack_main!(NodejsCorepackBuildpack);"));
+
+        assert!(bash_dir.join("bin/detect").exists());
+        assert!(bash_dir.join("bin/build").exists());
+        assert!(std::fs::read_to_string(bash_dir.join("CHANGELOG.md"))
+            .unwrap()
+            .ends_with("## [Unreleased]\n"));
+    }
+
+    #[test]
+    fn test_scaffold_buildpack_refuses_non_empty_directory() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        std::fs::write(tmp_dir.path().join("README.md"), "").unwrap();
+
+        match scaffold_buildpack(tmp_dir.path(), &template(BuildpackKind::Bash)).unwrap_err() {
+            Error::DirectoryNotEmpty(path) => assert_eq!(path, tmp_dir.path()),
+            e => panic!("Unexpected error: {e}"),
+        }
+    }
+
+    #[test]
+    fn test_default_image_repository() {
+        assert_eq!(
+            default_image_repository(&BuildpackId::from_str("heroku/java").unwrap()),
+            "docker.io/heroku/buildpack-java"
+        );
+    }
+}
@@ -0,0 +1,33 @@
+use crate::buildpacks::{FindReleasableBuildpacksError, ReadBuildpackDescriptorError};
+use std::collections::HashSet;
+use std::path::PathBuf;
+
+#[derive(Debug, thiserror::Error)]
+pub(crate) enum Error {
+    #[error("Failed to get current directory\nError: {0}")]
+    GetCurrentDir(std::io::Error),
+    #[error("Invalid buildpack id `{0}` for argument --id")]
+    InvalidBuildpackId(String),
+    #[error("Invalid buildpack version `{0}` for argument --version, versions must be in the form X.Y.Z")]
+    InvalidBuildpackVersion(String),
+    #[error(transparent)]
+    FindReleasableBuildpacks(FindReleasableBuildpacksError),
+    #[error(transparent)]
+    ReadBuildpackDescriptor(ReadBuildpackDescriptorError),
+    #[error("Existing buildpacks have different versions, use --version to pick one:\n{}", list_versions(.0))]
+    NoFixedVersion(HashSet<String>),
+    #[error("Buildpack directory already exists and is not empty\nPath: {0}")]
+    DirectoryNotEmpty(PathBuf),
+    #[error("Could not create directory\nPath: {0}\nError: {1}")]
+    CreatingDirectory(PathBuf, #[source] std::io::Error),
+    #[error("Could not write file\nPath: {0}\nError: {1}")]
+    WritingFile(PathBuf, #[source] std::io::Error),
+}
+
+fn list_versions(versions: &HashSet<String>) -> String {
+    versions
+        .iter()
+        .map(|version| format!("• {version}"))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
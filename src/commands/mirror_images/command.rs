@@ -0,0 +1,232 @@
+use crate::buildpacks::{
+    calculate_digest, copy_image, descriptor_path, find_releasable_buildpacks,
+    read_buildpack_descriptor, read_buildpack_targets, read_image_mirrors_metadata,
+    read_image_repository_metadata, read_image_target_repositories_metadata,
+};
+use crate::commands::generate_buildpack_matrix::command::{
+    expand_distro_targets, generate_tag, oci_target,
+};
+use crate::commands::mirror_images::errors::Error;
+use crate::concurrency::{try_map_concurrently, MAX_CONCURRENT_REQUESTS};
+use clap::Parser;
+use globset::Glob;
+use libcnb_data::buildpack::BuildpackDescriptor;
+use std::path::Path;
+use std::time::Duration;
+
+type Result<T> = std::result::Result<T, Error>;
+
+#[derive(Parser, Debug)]
+#[command(author, version, about = "Copies the published images of each buildpack to the mirror repositories in metadata.release.image.mirrors", long_about = None, disable_version_flag = true)]
+pub(crate) struct MirrorImagesArgs {
+    /// Leaves out buildpacks matching the given glob, by buildpack id or directory.
+    #[arg(long)]
+    pub(crate) exclude: Vec<Glob>,
+    /// How many times to try each copy before giving up.
+    #[arg(long, default_value_t = 3, value_parser = clap::value_parser!(u32).range(1..))]
+    pub(crate) attempts: u32,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct ImageCopy {
+    source: String,
+    destination: String,
+}
+
+// Registries occasionally fail pushes with transient errors, so failed attempts are retried after
+// waiting this long, doubling every time.
+const RETRY_DELAY: Duration = Duration::from_secs(2);
+
+pub(crate) fn execute(args: &MirrorImagesArgs) -> Result<()> {
+    let current_dir = std::env::current_dir().map_err(Error::GetCurrentDir)?;
+    let buildpack_dirs = find_releasable_buildpacks(&current_dir, &args.exclude)
+        .map_err(Error::FindReleasableBuildpacks)?;
+
+    let mut image_copies = vec![];
+    for buildpack_dir in &buildpack_dirs {
+        let buildpack_descriptor =
+            read_buildpack_descriptor(buildpack_dir).map_err(Error::ReadBuildpackDescriptor)?;
+        image_copies.extend(mirror_image_copies(buildpack_dir, &buildpack_descriptor)?);
+    }
+
+    if image_copies.is_empty() {
+        eprintln!("✅️ No mirror repositories configured");
+        return Ok(());
+    }
+
+    try_map_concurrently(&image_copies, MAX_CONCURRENT_REQUESTS, |image_copy| {
+        retry(args.attempts, RETRY_DELAY, || mirror_image(image_copy))
+    })?;
+
+    Ok(())
+}
+
+// Each stable tag is copied to every mirror under the same tag. Buildpacks with several targets
+// have the stable tag of every target copied along with the manifest list.
+fn mirror_image_copies(
+    buildpack_dir: &Path,
+    buildpack_descriptor: &BuildpackDescriptor,
+) -> Result<Vec<ImageCopy>> {
+    let mirrors = read_image_mirrors_metadata(buildpack_descriptor);
+    if mirrors.is_empty() {
+        return Ok(vec![]);
+    }
+
+    let version = buildpack_descriptor.buildpack().version.to_string();
+    let image_repository = read_image_repository_metadata(buildpack_descriptor).ok_or(
+        Error::MissingImageRepositoryMetadata(descriptor_path(buildpack_dir)),
+    )?;
+    let target_repositories = read_image_target_repositories_metadata(buildpack_descriptor);
+    let targets = expand_distro_targets(read_buildpack_targets(buildpack_descriptor));
+
+    let mut tags = vec![];
+    if targets.len() > 1 {
+        for (target, name) in &targets {
+            let target_repository = target_repositories
+                .get(&oci_target(target))
+                .unwrap_or(&image_repository);
+            tags.push((target_repository, format!("{version}_{name}")));
+        }
+    }
+    tags.push((&image_repository, version));
+
+    Ok(mirrors
+        .iter()
+        .flat_map(|mirror| {
+            tags.iter().map(|(repository, tag)| ImageCopy {
+                source: generate_tag(repository, tag, None),
+                destination: generate_tag(mirror, tag, None),
+            })
+        })
+        .collect())
+}
+
+// The copy is checked by comparing digests, which `crane copy` keeps the same for both images and
+// manifest lists.
+fn mirror_image(image_copy: &ImageCopy) -> Result<()> {
+    copy_image(&image_copy.source, &image_copy.destination).map_err(Error::CopyImage)?;
+    let expected = calculate_digest(&image_copy.source).map_err(Error::CalculateDigest)?;
+    let actual = calculate_digest(&image_copy.destination).map_err(Error::CalculateDigest)?;
+    if expected != actual {
+        return Err(Error::DigestMismatch(
+            image_copy.source.clone(),
+            image_copy.destination.clone(),
+            expected,
+            actual,
+        ));
+    }
+    eprintln!(
+        "✅️ Mirrored {} to {} ({actual})",
+        image_copy.source, image_copy.destination
+    );
+    Ok(())
+}
+
+fn retry<T, F>(attempts: u32, delay: Duration, f: F) -> Result<T>
+where
+    F: Fn() -> Result<T>,
+{
+    let mut delay = delay;
+    let mut attempt = 1;
+    loop {
+        match f() {
+            Ok(value) => return Ok(value),
+            Err(error) if attempt < attempts => {
+                eprintln!(
+                    "⚠️ Attempt {attempt} of {attempts} failed, retrying in {}s\n{error}",
+                    delay.as_secs()
+                );
+                std::thread::sleep(delay);
+                delay *= 2;
+                attempt += 1;
+            }
+            Err(error) => return Err(error),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::commands::mirror_images::command::{mirror_image_copies, retry, ImageCopy};
+    use crate::commands::mirror_images::errors::Error;
+    use libcnb_data::buildpack::BuildpackDescriptor;
+    use std::cell::Cell;
+    use std::path::Path;
+    use std::time::Duration;
+
+    fn image_copy(source: &str, destination: &str) -> ImageCopy {
+        ImageCopy {
+            source: source.to_string(),
+            destination: destination.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_mirror_image_copies() {
+        let buildpack_descriptor = toml::from_str::<BuildpackDescriptor>(
+            r#"
+api = "0.10"
+
+[buildpack]
+id = "heroku/foo"
+version = "1.2.3"
+
+[[targets]]
+os = "linux"
+arch = "amd64"
+
+[[targets]]
+os = "linux"
+arch = "arm64"
+
+[metadata.release.image]
+repository = "docker.io/heroku/buildpack-foo"
+mirrors = ["ghcr.io/heroku/buildpack-foo"]
+
+[metadata.release.image.targets]
+"linux/arm64" = "docker.io/heroku/buildpack-foo-arm64"
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            mirror_image_copies(Path::new("."), &buildpack_descriptor).unwrap(),
+            vec![
+                image_copy(
+                    "docker.io/heroku/buildpack-foo:1.2.3_linux-amd64",
+                    "ghcr.io/heroku/buildpack-foo:1.2.3_linux-amd64"
+                ),
+                image_copy(
+                    "docker.io/heroku/buildpack-foo-arm64:1.2.3_linux-arm64",
+                    "ghcr.io/heroku/buildpack-foo:1.2.3_linux-arm64"
+                ),
+                image_copy(
+                    "docker.io/heroku/buildpack-foo:1.2.3",
+                    "ghcr.io/heroku/buildpack-foo:1.2.3"
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_retry() {
+        let calls = Cell::new(0);
+        let result = retry(3, Duration::ZERO, || {
+            calls.set(calls.get() + 1);
+            if calls.get() < 3 {
+                Err(Error::MissingImageRepositoryMetadata(".".into()))
+            } else {
+                Ok(calls.get())
+            }
+        });
+        assert_eq!(result.unwrap(), 3);
+
+        calls.set(0);
+        let result = retry(2, Duration::ZERO, || {
+            calls.set(calls.get() + 1);
+            Err::<(), _>(Error::MissingImageRepositoryMetadata(".".into()))
+        });
+        assert!(result.is_err());
+        assert_eq!(calls.get(), 2);
+    }
+}
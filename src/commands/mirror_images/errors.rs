@@ -0,0 +1,23 @@
+use crate::buildpacks::{
+    CalculateDigestError, CopyImageError, FindReleasableBuildpacksError,
+    ReadBuildpackDescriptorError,
+};
+use std::path::PathBuf;
+
+#[derive(Debug, thiserror::Error)]
+pub(crate) enum Error {
+    #[error("Failed to get current directory\nError: {0}")]
+    GetCurrentDir(#[source] std::io::Error),
+    #[error(transparent)]
+    FindReleasableBuildpacks(FindReleasableBuildpacksError),
+    #[error(transparent)]
+    ReadBuildpackDescriptor(ReadBuildpackDescriptorError),
+    #[error("The following buildpack is missing the metadata.release.image.repository entry\nPath: {}", .0.display())]
+    MissingImageRepositoryMetadata(PathBuf),
+    #[error(transparent)]
+    CopyImage(CopyImageError),
+    #[error(transparent)]
+    CalculateDigest(CalculateDigestError),
+    #[error("Digest of mirrored image {1} doesn't match {0}\nExpected: {2}\nActual: {3}")]
+    DigestMismatch(String, String, String, String),
+}
@@ -1,9 +1,19 @@
 use std::path::{Path, PathBuf};
 
+pub(crate) mod check_image_labels_policy;
+pub(crate) mod check_orphaned_artifacts;
+pub(crate) mod diff_release;
+pub(crate) mod export_catalog;
 pub(crate) mod generate_buildpack_matrix;
 pub(crate) mod generate_changelog;
+pub(crate) mod generate_provenance;
+pub(crate) mod init_buildpack;
+pub(crate) mod mirror_images;
+pub(crate) mod preflight;
 pub(crate) mod prepare_release;
+pub(crate) mod selftest;
 pub(crate) mod update_builder;
+pub(crate) mod watch;
 
 pub(crate) fn resolve_path(path: &Path, base: &Path) -> PathBuf {
     if path.is_absolute() {
@@ -0,0 +1,390 @@
+use crate::buildpacks::{
+    find_releasable_buildpacks, read_buildpack_descriptor, read_image_repository_metadata,
+    read_image_target_repositories_metadata,
+};
+use crate::commands::preflight::errors::{Error, PreflightFailure};
+use clap::{Parser, ValueEnum};
+use lazy_static::lazy_static;
+use regex::Regex;
+use semver::Version;
+use std::collections::BTreeSet;
+use std::path::Path;
+use std::process::Command;
+
+type Result<T> = std::result::Result<T, Error>;
+
+#[derive(Parser, Debug)]
+#[command(author, version, about = "Checks the tools, credentials, and disk space a pipeline phase needs before it runs", long_about = None, disable_version_flag = true)]
+pub(crate) struct PreflightArgs {
+    #[arg(long, value_enum)]
+    pub(crate) phase: PipelinePhase,
+    /// Image repository the phase pushes to or pulls from, checked for working registry
+    /// credentials. Defaults to the image repositories of the buildpacks in the current directory.
+    #[arg(long)]
+    pub(crate) image_repository: Vec<String>,
+    /// Repository (e.g.; heroku/buildpacks-nodejs) that the GitHub token needs access to.
+    /// Defaults to `GITHUB_REPOSITORY`.
+    #[arg(long)]
+    pub(crate) repository: Option<String>,
+    /// Free disk space (in GiB) needed in the current directory.
+    #[arg(long, default_value_t = 10)]
+    pub(crate) min_free_disk_space: u64,
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy, Eq, PartialEq)]
+pub(crate) enum PipelinePhase {
+    PrepareRelease,
+    Release,
+    UpdateBuilder,
+}
+
+struct Tool {
+    name: &'static str,
+    version_args: &'static [&'static str],
+    min_version: Version,
+    remediation: &'static str,
+}
+
+const GIT: Tool = Tool {
+    name: "git",
+    version_args: &["--version"],
+    min_version: Version::new(2, 0, 0),
+    remediation: "Install git on the runner.",
+};
+
+const GH: Tool = Tool {
+    name: "gh",
+    version_args: &["--version"],
+    min_version: Version::new(2, 0, 0),
+    remediation: "Install the GitHub CLI (https://cli.github.com) on the runner.",
+};
+
+const CRANE: Tool = Tool {
+    name: "crane",
+    version_args: &["version"],
+    min_version: Version::new(0, 12, 0),
+    remediation: "Install crane with the `buildpacks/github-actions/setup-tools` action.",
+};
+
+// `pack buildpack package --target` (used to package multi-arch buildpacks) needs pack 0.33.0.
+const PACK: Tool = Tool {
+    name: "pack",
+    version_args: &["--version"],
+    min_version: Version::new(0, 33, 0),
+    remediation: "Install pack with the `buildpacks/github-actions/setup-pack` action.",
+};
+
+const PREPARE_RELEASE_TOOLS: &[Tool] = &[GIT, GH];
+const RELEASE_TOOLS: &[Tool] = &[CRANE, PACK, GH];
+const UPDATE_BUILDER_TOOLS: &[Tool] = &[GIT, CRANE, GH];
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+enum RegistryAccess {
+    Pull,
+    Push,
+}
+
+impl PipelinePhase {
+    fn tools(self) -> &'static [Tool] {
+        match self {
+            PipelinePhase::PrepareRelease => PREPARE_RELEASE_TOOLS,
+            PipelinePhase::Release => RELEASE_TOOLS,
+            PipelinePhase::UpdateBuilder => UPDATE_BUILDER_TOOLS,
+        }
+    }
+
+    fn registry_access(self) -> Option<RegistryAccess> {
+        match self {
+            PipelinePhase::PrepareRelease => None,
+            PipelinePhase::Release => Some(RegistryAccess::Push),
+            PipelinePhase::UpdateBuilder => Some(RegistryAccess::Pull),
+        }
+    }
+}
+
+// Every check runs (rather than stopping at the first failure) so all the missing prerequisites
+// are reported at once.
+pub(crate) fn execute(args: &PreflightArgs) -> Result<()> {
+    let current_dir = std::env::current_dir().map_err(Error::GetCurrentDir)?;
+
+    let mut checks = args
+        .phase
+        .tools()
+        .iter()
+        .map(check_tool)
+        .collect::<Vec<_>>();
+
+    if let Some(access) = args.phase.registry_access() {
+        let image_repositories = if args.image_repository.is_empty() {
+            read_image_repositories(&current_dir)?
+        } else {
+            args.image_repository.iter().cloned().collect()
+        };
+        checks.extend(
+            image_repositories
+                .iter()
+                .map(|image_repository| check_registry_access(image_repository, access)),
+        );
+    }
+
+    let repository = args
+        .repository
+        .clone()
+        .or_else(|| std::env::var("GITHUB_REPOSITORY").ok());
+    checks.push(check_github_token(repository.as_deref()));
+
+    checks.push(check_free_disk_space(
+        &current_dir,
+        args.min_free_disk_space,
+    ));
+
+    let mut failures = vec![];
+    for check in checks {
+        match check {
+            Ok(passed) => eprintln!("✅️ {passed}"),
+            Err(failure) => failures.push(failure),
+        }
+    }
+
+    if failures.is_empty() {
+        Ok(())
+    } else {
+        Err(Error::PreflightFailed(failures))
+    }
+}
+
+fn read_image_repositories(current_dir: &Path) -> Result<BTreeSet<String>> {
+    let mut image_repositories = BTreeSet::new();
+    for dir in
+        find_releasable_buildpacks(current_dir, &[]).map_err(Error::FindReleasableBuildpacks)?
+    {
+        let buildpack_descriptor =
+            read_buildpack_descriptor(&dir).map_err(Error::ReadBuildpackDescriptor)?;
+        image_repositories.extend(read_image_repository_metadata(&buildpack_descriptor));
+        image_repositories
+            .extend(read_image_target_repositories_metadata(&buildpack_descriptor).into_values());
+    }
+    Ok(image_repositories)
+}
+
+type CheckResult = std::result::Result<String, PreflightFailure>;
+
+fn check_tool(tool: &Tool) -> CheckResult {
+    let output = Command::new(tool.name)
+        .args(tool.version_args)
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .ok_or_else(|| {
+            PreflightFailure::new(format!("{} is not installed", tool.name), tool.remediation)
+        })?;
+
+    let version =
+        parse_tool_version(&String::from_utf8_lossy(&output.stdout)).ok_or_else(|| {
+            PreflightFailure::new(
+                format!("Could not determine the version of {}", tool.name),
+                tool.remediation,
+            )
+        })?;
+
+    if version < tool.min_version {
+        Err(PreflightFailure::new(
+            format!(
+                "{} {version} is older than the required {}",
+                tool.name, tool.min_version
+            ),
+            tool.remediation,
+        ))?;
+    }
+
+    Ok(format!("{} {version}", tool.name))
+}
+
+// Tools print their version in different ways (e.g.; `git version 2.43.0`, `0.20.2`, or
+// `0.35.1+git-3a22a7f.build-6099`) so the first X.Y.Z is taken.
+fn parse_tool_version(output: &str) -> Option<Version> {
+    lazy_static! {
+        static ref VERSION: Regex =
+            Regex::new(r"(\d+)\.(\d+)\.(\d+)").expect("Should be a valid regex");
+    }
+    let captures = VERSION.captures(output)?;
+    Some(Version::new(
+        captures[1].parse().ok()?,
+        captures[2].parse().ok()?,
+        captures[3].parse().ok()?,
+    ))
+}
+
+// Requesting a token for the repository goes through the same credential lookup (and, for push
+// access, the same scopes) that publishing does.
+fn check_registry_access(image_repository: &str, access: RegistryAccess) -> CheckResult {
+    let mut args = vec!["auth", "token"];
+    if access == RegistryAccess::Push {
+        args.push("--push");
+    }
+    args.push(image_repository);
+
+    let access_name = match access {
+        RegistryAccess::Pull => "pull",
+        RegistryAccess::Push => "push",
+    };
+
+    match Command::new("crane").args(&args).output() {
+        Ok(output) if output.status.success() => Ok(format!(
+            "Registry credentials allow {access_name} access to {image_repository}"
+        )),
+        _ => Err(PreflightFailure::new(
+            format!("Registry credentials don't allow {access_name} access to {image_repository}"),
+            "Log in to the registry with `docker login` (or the `docker/login-action` action) using credentials that can access this repository.",
+        )),
+    }
+}
+
+// Classic personal access tokens report their scopes in the `X-OAuth-Scopes` header. Tokens
+// without scopes (e.g.; the `GITHUB_TOKEN` of a workflow) are only checked for repository access.
+fn check_github_token(repository: Option<&str>) -> CheckResult {
+    let token_is_set = ["GH_TOKEN", "GITHUB_TOKEN"]
+        .iter()
+        .any(|name| std::env::var(name).is_ok_and(|token| !token.is_empty()));
+    if !token_is_set {
+        Err(PreflightFailure::new(
+            "No GitHub token is set",
+            "Set `GH_TOKEN` or `GITHUB_TOKEN` in the step's environment.",
+        ))?;
+    }
+
+    let Some(repository) = repository else {
+        return Ok("GitHub token is set".to_string());
+    };
+
+    let output = Command::new("gh")
+        .args(["api", "--include", &format!("repos/{repository}")])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .ok_or_else(|| {
+            PreflightFailure::new(
+                format!("GitHub token can't access {repository}"),
+                "Use a token that has access to the repository (check the workflow's `permissions`).",
+            )
+        })?;
+
+    match parse_oauth_scopes(&String::from_utf8_lossy(&output.stdout)) {
+        Some(scopes)
+            if !scopes
+                .iter()
+                .any(|scope| scope == "repo" || scope == "public_repo") =>
+        {
+            Err(PreflightFailure::new(
+                format!(
+                    "GitHub token is missing the `repo` scope (has: {})",
+                    scopes.join(", ")
+                ),
+                "Use a token with the `repo` scope (or `public_repo` for public repositories).",
+            ))
+        }
+        _ => Ok(format!("GitHub token can access {repository}")),
+    }
+}
+
+fn parse_oauth_scopes(response: &str) -> Option<Vec<String>> {
+    response
+        .lines()
+        .take_while(|line| !line.trim().is_empty())
+        .find_map(|line| {
+            let (name, value) = line.split_once(':')?;
+            name.trim().eq_ignore_ascii_case("x-oauth-scopes").then(|| {
+                value
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|scope| !scope.is_empty())
+                    .map(ToString::to_string)
+                    .collect()
+            })
+        })
+}
+
+fn check_free_disk_space(dir: &Path, min_free_gib: u64) -> CheckResult {
+    let available_kib = Command::new("df")
+        .arg("-Pk")
+        .arg(dir)
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .and_then(|output| parse_df_available(&String::from_utf8_lossy(&output.stdout)))
+        .ok_or_else(|| {
+            PreflightFailure::new(
+                format!(
+                    "Could not determine the free disk space of {}",
+                    dir.display()
+                ),
+                "Make sure `df` is available on the runner.",
+            )
+        })?;
+
+    let free_gib = available_kib / (1024 * 1024);
+    if free_gib < min_free_gib {
+        Err(PreflightFailure::new(
+            format!("Only {free_gib} GiB of disk space is free, {min_free_gib} GiB is needed"),
+            "Free up disk space on the runner (e.g.; remove unused tool caches and docker images) or use a larger runner.",
+        ))?;
+    }
+
+    Ok(format!("{free_gib} GiB of disk space is free"))
+}
+
+// The available space (in KiB) from the POSIX output format of `df -Pk`.
+fn parse_df_available(output: &str) -> Option<u64> {
+    output
+        .lines()
+        .nth(1)?
+        .split_whitespace()
+        .nth(3)?
+        .parse()
+        .ok()
+}
+
+#[cfg(test)]
+mod test {
+    use crate::commands::preflight::command::{
+        parse_df_available, parse_oauth_scopes, parse_tool_version,
+    };
+    use semver::Version;
+
+    #[test]
+    fn test_parse_tool_version() {
+        assert_eq!(
+            parse_tool_version("git version 2.43.0\n"),
+            Some(Version::new(2, 43, 0))
+        );
+        assert_eq!(
+            parse_tool_version(
+                "gh version 2.45.0 (2024-03-04)\nhttps://github.com/cli/cli/releases/tag/v2.45.0\n"
+            ),
+            Some(Version::new(2, 45, 0))
+        );
+        assert_eq!(
+            parse_tool_version("0.35.1+git-3a22a7f.build-6099\n"),
+            Some(Version::new(0, 35, 1))
+        );
+        assert_eq!(parse_tool_version("unknown"), None);
+    }
+
+    #[test]
+    fn test_parse_oauth_scopes() {
+        assert_eq!(
+            parse_oauth_scopes("HTTP/2.0 200 OK\nX-Oauth-Scopes: read:org, repo\nX-Github-Media-Type: github.v3\n\n{\"x-oauth-scopes: gist\": 1}"),
+            Some(vec!["read:org".to_string(), "repo".to_string()])
+        );
+        assert_eq!(parse_oauth_scopes("HTTP/2.0 200 OK\n\n{}"), None);
+    }
+
+    #[test]
+    fn test_parse_df_available() {
+        assert_eq!(
+            parse_df_available("Filesystem 1024-blocks Used Available Capacity Mounted on\n/dev/root 76026616 52174248 23835984 69% /\n"),
+            Some(23_835_984)
+        );
+        assert_eq!(parse_df_available(""), None);
+    }
+}
@@ -0,0 +1,37 @@
+use crate::buildpacks::{FindReleasableBuildpacksError, ReadBuildpackDescriptorError};
+
+#[derive(Debug, thiserror::Error)]
+pub(crate) enum Error {
+    #[error("Failed to get current directory\nError: {0}")]
+    GetCurrentDir(std::io::Error),
+    #[error(transparent)]
+    FindReleasableBuildpacks(FindReleasableBuildpacksError),
+    #[error(transparent)]
+    ReadBuildpackDescriptor(ReadBuildpackDescriptorError),
+    #[error("The following preflight checks failed:\n{}", list_failures(.0))]
+    PreflightFailed(Vec<PreflightFailure>),
+}
+
+// A failed check along with what to do about it.
+#[derive(Debug)]
+pub(crate) struct PreflightFailure {
+    pub(crate) check: String,
+    pub(crate) remediation: String,
+}
+
+impl PreflightFailure {
+    pub(crate) fn new(check: impl Into<String>, remediation: impl Into<String>) -> Self {
+        Self {
+            check: check.into(),
+            remediation: remediation.into(),
+        }
+    }
+}
+
+fn list_failures(failures: &[PreflightFailure]) -> String {
+    failures
+        .iter()
+        .map(|failure| format!("• {}\n  {}", failure.check, failure.remediation))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
@@ -1,13 +1,32 @@
-use crate::buildpacks::find_releasable_buildpacks;
-use crate::changelog::{generate_release_declarations, Changelog, ReleaseEntry};
+use crate::buildpacks::{
+    descriptor_path, find_releasable_buildpacks, is_registered_in_cnb_registry,
+};
+use crate::changelog::{
+    append_group_entries, changes_by_group, demote_headings, generate_release_declarations,
+    normalize_change_groups, validate_changelog, Changelog, LinkTemplates, ReleaseEntry,
+};
 use crate::commands::prepare_release::errors::Error;
+use crate::commands::resolve_path;
+use crate::concurrency::{try_map_concurrently, MAX_CONCURRENT_REQUESTS};
+use crate::git::{
+    checkout_branch, commit_files, current_branch, list_tags, normalize_path, push_branch,
+};
 use crate::github::actions;
-use chrono::{DateTime, Utc};
+use crate::github::pull_requests::create_pull_request;
+use crate::locking::{lock_files, FileLock};
+use chrono::{DateTime, NaiveDate, NaiveTime, TimeZone, Utc};
 use clap::{Parser, ValueEnum};
+use diffy::DiffOptions;
+use globset::Glob;
 use indexmap::IndexMap;
+use lazy_static::lazy_static;
 use libcnb_data::buildpack::{BuildpackId, BuildpackVersion};
-use semver::{BuildMetadata, Prerelease, Version};
-use std::collections::{HashMap, HashSet};
+use regex::Regex;
+use semver::Version;
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::env;
+use std::fmt::{Display, Formatter};
 use std::fs::write;
 use std::path::{Path, PathBuf};
 use std::str::FromStr;
@@ -17,21 +36,226 @@ use uriparse::URI;
 type Result<T> = std::result::Result<T, Error>;
 
 #[derive(Parser, Debug)]
-#[command(author, version, about = "Bumps the version of each detected buildpack and adds an entry for any unreleased changes from the changelog", long_about = None)]
+#[allow(clippy::struct_excessive_bools)]
+#[command(author, version, about = "Bumps the version of each detected buildpack and adds an entry for any unreleased changes from the changelog", long_about = None, disable_version_flag = true)]
 pub(crate) struct PrepareReleaseArgs {
-    #[arg(long, value_enum)]
-    pub(crate) bump: BumpCoordinate,
+    #[arg(long, value_enum, required_unless_present = "version")]
+    pub(crate) bump: Option<BumpCoordinate>,
+    /// Releases this exact version instead of bumping the current one.
+    #[arg(long, conflicts_with = "bump")]
+    pub(crate) version: Option<String>,
     #[arg(long)]
     pub(crate) repository_url: String,
     #[arg(long)]
     pub(crate) declarations_starting_version: Option<String>,
+    /// Overrides the template of a changelog link, written as `<link>=<template>` (e.g.;
+    /// `compare={repository}/-/compare/v{previous_version}...v{version}` for GitLab). The links
+    /// are `compare`, `tag` and `unreleased`. Can be repeated.
+    #[arg(long, value_parser = parse_link_template)]
+    pub(crate) link_template: Vec<LinkTemplate>,
+    #[arg(long, value_enum, default_value_t = PreviousVersionSource::Changelog)]
+    pub(crate) previous_version_from: PreviousVersionSource,
+    #[arg(long)]
+    pub(crate) coerce_versions: bool,
+    #[arg(long)]
+    pub(crate) dry_run: bool,
+    /// Commits the release changes to a branch and opens a pull request for them.
+    #[arg(long, conflicts_with = "dry_run")]
+    pub(crate) create_pull_request: bool,
+    /// Branch that the release changes are committed to with --create-pull-request.
+    #[arg(long, default_value = "prepare-release")]
+    pub(crate) pull_request_branch: String,
+    /// Also updates the crate version in the `Cargo.toml` of each buildpack (or the workspace
+    /// version it inherits) to the released version.
+    #[arg(long)]
+    pub(crate) sync_cargo_version: bool,
+    /// Leaves out buildpacks matching the given glob, by buildpack id or directory.
+    #[arg(long)]
+    pub(crate) exclude: Vec<Glob>,
+    /// Changelog entry added for each updated dependency, with `{id}` and `{version}` placeholders.
+    #[arg(long, default_value = DEFAULT_DEPENDENCY_CHANGE_TEMPLATE)]
+    pub(crate) dependency_change_template: String,
+    /// Changelog group that updated dependency entries are added to.
+    #[arg(long, value_enum, default_value_t = ChangeGroup::Changed)]
+    pub(crate) dependency_change_group: ChangeGroup,
+    /// Adds an unreleased changelog entry to every buildpack before it's released, written as
+    /// `<group>: <text>` (e.g.; `fixed: Corrected PATH handling`). Can be repeated.
+    #[arg(long, value_parser = parse_added_change)]
+    pub(crate) add_change: Vec<AddedChange>,
+    /// Only releases the buildpacks under this directory (e.g.; `buildpacks/nodejs` in a
+    /// repository with several independently versioned families of buildpacks).
+    #[arg(long)]
+    pub(crate) project_dir: Option<PathBuf>,
+    /// Also adds a release entry to the `CHANGELOG.md` at the root of the project, with the changes
+    /// of each released buildpack under a heading for its id.
+    #[arg(long)]
+    pub(crate) root_changelog: bool,
+    /// Also replaces the previous version with the released one in each buildpack's README.md,
+    /// between `<!-- release-version:start -->` and `<!-- release-version:end -->` markers.
+    #[arg(long)]
+    pub(crate) update_docs: bool,
+    /// Date of the release entries (e.g.; `2024-06-01`). Defaults to the date from
+    /// `SOURCE_DATE_EPOCH` when it's set, or else today.
+    #[arg(long)]
+    pub(crate) release_date: Option<NaiveDate>,
+    /// Adds a `<!-- released-by actions vX.Y.Z run <id> -->` comment under each new release
+    /// heading, with the version of this tool and the GitHub Actions run (from `GITHUB_RUN_ID`)
+    /// that prepared the release.
+    #[arg(long)]
+    pub(crate) released_by_comment: bool,
+    /// Fails before changing any files if the version being released is already in the CNB
+    /// registry for any of the buildpacks.
+    #[arg(long)]
+    pub(crate) check_registry: bool,
 }
 
-#[derive(ValueEnum, Debug, Clone)]
+const DEFAULT_DEPENDENCY_CHANGE_TEMPLATE: &str = "Updated `{id}` to `{version}`.";
+
+#[derive(ValueEnum, Debug, Clone, Eq, PartialEq)]
 pub(crate) enum BumpCoordinate {
     Major,
     Minor,
     Patch,
+    /// Picks the coordinate from the change groups in the unreleased changelog entries.
+    Auto,
+    /// Keeps the current version and only promotes the unreleased changes to a release entry
+    /// for it (e.g.; to republish or retag a release).
+    None,
+}
+
+// Where the version that each release's changelog link compares against comes from.
+#[derive(ValueEnum, Debug, Clone, Copy, Eq, PartialEq)]
+pub(crate) enum PreviousVersionSource {
+    /// The release before it in the changelog.
+    Changelog,
+    /// The closest older `vX.Y.Z` tag in the git repository.
+    GitTags,
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy, Eq, PartialEq)]
+pub(crate) enum ChangeGroup {
+    Added,
+    Changed,
+    Deprecated,
+    Removed,
+    Fixed,
+    Security,
+    Dependencies,
+}
+
+impl Display for ChangeGroup {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let heading = match self {
+            ChangeGroup::Added => "Added",
+            ChangeGroup::Changed => "Changed",
+            ChangeGroup::Deprecated => "Deprecated",
+            ChangeGroup::Removed => "Removed",
+            ChangeGroup::Fixed => "Fixed",
+            ChangeGroup::Security => "Security",
+            ChangeGroup::Dependencies => "Dependencies",
+        };
+        write!(f, "{heading}")
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub(crate) struct AddedChange {
+    group: ChangeGroup,
+    text: String,
+}
+
+fn parse_added_change(value: &str) -> std::result::Result<AddedChange, String> {
+    let (group, text) = value
+        .split_once(':')
+        .ok_or("expected `<group>: <text>`".to_string())?;
+    let group = ChangeGroup::from_str(group.trim(), true)?;
+    let text = text.trim();
+    if text.is_empty() {
+        Err("the change text is empty".to_string())?;
+    }
+    Ok(AddedChange {
+        group,
+        text: text.to_string(),
+    })
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy, Eq, PartialEq)]
+pub(crate) enum LinkKind {
+    Compare,
+    Tag,
+    Unreleased,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub(crate) struct LinkTemplate {
+    kind: LinkKind,
+    template: String,
+}
+
+fn parse_link_template(value: &str) -> std::result::Result<LinkTemplate, String> {
+    let (kind, template) = value
+        .split_once('=')
+        .ok_or("expected `<link>=<template>`".to_string())?;
+    let kind = LinkKind::from_str(kind.trim(), true)?;
+    let template = template.trim();
+    if template.is_empty() {
+        Err("the link template is empty".to_string())?;
+    }
+    Ok(LinkTemplate {
+        kind,
+        template: template.to_string(),
+    })
+}
+
+fn resolve_link_templates(overrides: &[LinkTemplate]) -> LinkTemplates {
+    let mut link_templates = LinkTemplates::default();
+    for LinkTemplate { kind, template } in overrides {
+        let link_template = match kind {
+            LinkKind::Compare => &mut link_templates.compare,
+            LinkKind::Tag => &mut link_templates.tag,
+            LinkKind::Unreleased => &mut link_templates.unreleased,
+        };
+        link_template.clone_from(template);
+    }
+    link_templates
+}
+
+// How the entries for buildpack dependencies released in the same run are recorded.
+struct DependencyChangeEntry {
+    template: String,
+    group: ChangeGroup,
+}
+
+impl Default for DependencyChangeEntry {
+    fn default() -> Self {
+        DependencyChangeEntry {
+            template: DEFAULT_DEPENDENCY_CHANGE_TEMPLATE.to_string(),
+            group: ChangeGroup::Changed,
+        }
+    }
+}
+
+impl DependencyChangeEntry {
+    fn render(&self, id: &BuildpackId, version: &Version) -> String {
+        self.template
+            .replace("{id}", id.as_str())
+            .replace("{version}", &version.to_string())
+    }
+}
+
+enum ReleaseVersion {
+    Bump(BumpCoordinate),
+    Explicit(Version),
+}
+
+// Describes each buildpack in the release for the `released_buildpacks` output, so later
+// workflow steps don't have to find and read the buildpacks again.
+#[derive(Debug, Eq, PartialEq, Serialize)]
+struct ReleasedBuildpack {
+    path: PathBuf,
+    from_version: String,
+    to_version: String,
+    changelog_path: PathBuf,
 }
 
 struct BuildpackFile {
@@ -44,97 +268,1002 @@ struct ChangelogFile {
     changelog: Changelog,
 }
 
+const ROOT_CHANGELOG_FILE: &str = "CHANGELOG.md";
+const README_FILE: &str = "README.md";
+const VERSION_MARKER_START: &str = "<!-- release-version:start -->";
+const VERSION_MARKER_END: &str = "<!-- release-version:end -->";
+
 pub(crate) fn execute(args: PrepareReleaseArgs) -> Result<()> {
-    let current_dir = std::env::current_dir().map_err(Error::GetCurrentDir)?;
+    let current_dir = env::current_dir().map_err(Error::GetCurrentDir)?;
 
-    let repository_url = URI::try_from(args.repository_url.as_str())
-        .map(URI::into_owned)
-        .map_err(|e| Error::InvalidRepositoryUrl(args.repository_url.clone(), e))?;
+    let repository_url = parse_repository_url(&args.repository_url)?;
 
-    let declarations_starting_version = args
-        .declarations_starting_version
-        .map(|value| {
-            value
-                .parse::<Version>()
-                .map_err(|e| Error::InvalidDeclarationsStartingVersion(value, e))
-        })
-        .transpose()?;
+    let declarations_starting_version =
+        parse_declarations_starting_version(args.declarations_starting_version)?;
 
-    let buildpack_dirs =
-        find_releasable_buildpacks(&current_dir).map_err(Error::FindReleasableBuildpacks)?;
+    let release_date = resolve_release_date(args.release_date, env::var("SOURCE_DATE_EPOCH").ok())?;
+    let released_by = released_by(args.released_by_comment, env::var("GITHUB_RUN_ID").ok());
 
-    if buildpack_dirs.is_empty() {
-        Err(Error::NoBuildpacksFound(current_dir))?;
-    }
+    let project_dir = resolve_project_dir(args.project_dir.as_deref(), &current_dir);
 
-    let buildpack_files = buildpack_dirs
-        .iter()
-        .map(|dir| read_buildpack_file(dir.join("buildpack.toml")))
-        .collect::<Result<Vec<_>>>()?;
+    let buildpack_dirs = find_sorted_buildpack_dirs(&project_dir, &args.exclude)?;
+
+    let root_changelog_path = args
+        .root_changelog
+        .then(|| project_dir.join(ROOT_CHANGELOG_FILE));
 
-    let changelog_files = buildpack_dirs
+    let (_locks, buildpack_files, mut changelog_files) = lock_and_read_release_files(
+        &buildpack_dirs,
+        root_changelog_path.as_deref(),
+        args.coerce_versions,
+    )?;
+
+    // added before the version is resolved so these entries count towards `--bump auto`
+    add_unreleased_changes(&mut changelog_files, &args.add_change);
+
+    let buildpack_ids = buildpack_files
         .iter()
-        .map(|dir| read_changelog_file(dir.join("CHANGELOG.md")))
+        .map(get_buildpack_id)
         .collect::<Result<Vec<_>>>()?;
 
-    let updated_buildpack_ids = buildpack_files
+    let released_repositories = buildpack_files
         .iter()
-        .map(get_buildpack_id)
-        .collect::<Result<HashSet<_>>>()?;
+        .filter_map(get_image_repository)
+        .collect::<HashSet<_>>();
 
-    let current_version = get_fixed_version(&buildpack_files)?;
+    let (current_version, next_version) = start_release(
+        &buildpack_files,
+        &changelog_files,
+        args.version.as_deref(),
+        args.bump.as_ref(),
+    )?;
 
-    let next_version = get_next_version(&current_version, &args.bump);
+    if args.check_registry {
+        check_unregistered_version(&buildpack_ids, &next_version)?;
+    }
 
-    for (mut buildpack_file, changelog_file) in buildpack_files.into_iter().zip(changelog_files) {
-        let updated_dependencies = get_buildpack_dependency_ids(&buildpack_file)?
-            .into_iter()
-            .filter(|buildpack_id| updated_buildpack_ids.contains(buildpack_id))
-            .collect::<HashSet<_>>();
+    let release_tags = read_release_tags(
+        args.previous_version_from,
+        &current_dir,
+        &changelog_files,
+        declarations_starting_version.as_ref(),
+    )?;
 
-        let new_buildpack_contents = update_buildpack_contents_with_new_version(
-            &mut buildpack_file,
+    let released_buildpacks = read_released_buildpacks(
+        &buildpack_ids,
+        &buildpack_files,
+        &changelog_files,
+        &current_version,
+        &next_version,
+    );
+
+    let security_fixes = read_security_fixes(&buildpack_ids, &changelog_files);
+
+    let updated_buildpack_ids = buildpack_ids.iter().cloned().collect::<HashSet<_>>();
+
+    let buildpacks = buildpack_ids
+        .into_iter()
+        .zip(buildpack_files.into_iter().zip(changelog_files))
+        .collect::<Vec<_>>();
+
+    let mut writer = ReleaseWriter::new(&current_dir, args.dry_run);
+
+    let release_changes = ReleaseChanges {
+        current_version: &current_version,
+        next_version: &next_version,
+        updated_buildpack_ids: &updated_buildpack_ids,
+        released_repositories: &released_repositories,
+        dependency_change_entry: &DependencyChangeEntry {
+            template: args.dependency_change_template,
+            group: args.dependency_change_group,
+        },
+        repository_url: &repository_url.to_string(),
+        link_templates: &resolve_link_templates(&args.link_template),
+        declarations_starting_version: declarations_starting_version.as_ref(),
+        release_tags: release_tags.as_deref(),
+        release_date: &release_date,
+        released_by: released_by.as_deref(),
+    };
+
+    release_buildpacks(
+        buildpacks,
+        root_changelog_path.as_deref(),
+        &release_changes,
+        &mut writer,
+    )?;
+
+    if args.sync_cargo_version {
+        sync_cargo_versions(&buildpack_dirs, &current_dir, &next_version, &mut writer)?;
+    }
+
+    if args.update_docs {
+        update_readme_versions(&buildpack_dirs, &release_changes, &mut writer)?;
+    }
+
+    finish_release(&mut writer)?;
+
+    if args.create_pull_request {
+        create_release_pull_request(
+            &current_dir,
+            &repository_url.to_string(),
+            &args.pull_request_branch,
+            &writer,
+            &released_buildpacks,
             &next_version,
-            &updated_dependencies,
         )?;
+    }
 
-        write(&buildpack_file.path, new_buildpack_contents)
-            .map_err(|e| Error::WritingBuildpack(buildpack_file.path.clone(), e))?;
+    set_release_outputs(&current_version, &next_version, &released_buildpacks)?;
+    set_security_fix_outputs(&security_fixes)?;
 
-        eprintln!(
-            "✅️ Updated version {current_version} → {next_version}: {}",
-            buildpack_file.path.display(),
-        );
+    Ok(())
+}
 
-        let new_changelog = promote_changelog_unreleased_to_version(
-            &changelog_file.changelog,
-            &next_version,
-            &Utc::now(),
-            &updated_dependencies,
-        );
+// Everything about the release that's shared by the buildpacks in it.
+struct ReleaseChanges<'a> {
+    current_version: &'a Version,
+    next_version: &'a Version,
+    updated_buildpack_ids: &'a HashSet<BuildpackId>,
+    released_repositories: &'a HashSet<String>,
+    dependency_change_entry: &'a DependencyChangeEntry,
+    repository_url: &'a str,
+    link_templates: &'a LinkTemplates,
+    declarations_starting_version: Option<&'a Version>,
+    release_tags: Option<&'a [Version]>,
+    release_date: &'a DateTime<Utc>,
+    released_by: Option<&'a str>,
+}
 
-        let release_declarations = generate_release_declarations(
-            &new_changelog,
-            repository_url.to_string(),
-            declarations_starting_version.as_ref(),
-        );
+fn release_buildpacks(
+    buildpacks: Vec<(BuildpackId, (BuildpackFile, ChangelogFile))>,
+    root_changelog_path: Option<&Path>,
+    release_changes: &ReleaseChanges,
+    writer: &mut ReleaseWriter,
+) -> Result<()> {
+    // checked for every buildpack up front so a release isn't left half-finished
+    if release_changes.current_version == release_changes.next_version {
+        for (_, (_, changelog_file)) in &buildpacks {
+            let next_version = release_changes.next_version.to_string();
+            if changelog_file
+                .changelog
+                .releases
+                .contains_key(&next_version)
+            {
+                Err(Error::ReleaseEntryAlreadyExists(
+                    changelog_file.path.clone(),
+                    release_changes.next_version.clone(),
+                ))?;
+            }
+        }
+    }
+
+    let mut release_notes = vec![];
+    for (buildpack_id, (mut buildpack_file, changelog_file)) in buildpacks {
+        let changes = release_buildpack(
+            &mut buildpack_file,
+            &changelog_file,
+            release_changes,
+            writer,
+        )?;
+        release_notes.push((buildpack_id, changes));
+    }
 
-        let changelog_contents = format!("{new_changelog}\n{release_declarations}\n");
+    if let Some(root_changelog_path) = root_changelog_path {
+        update_root_changelog(
+            &read_changelog_file(root_changelog_path.to_path_buf())?,
+            &release_notes,
+            release_changes,
+            writer,
+        )?;
+    }
 
-        write(&changelog_file.path, changelog_contents)
-            .map_err(|e| Error::WritingChangelog(changelog_file.path.clone(), e))?;
+    Ok(())
+}
 
+fn release_buildpack(
+    buildpack_file: &mut BuildpackFile,
+    changelog_file: &ChangelogFile,
+    release_changes: &ReleaseChanges,
+    writer: &mut ReleaseWriter,
+) -> Result<String> {
+    let ReleaseChanges {
+        current_version,
+        next_version,
+        ..
+    } = release_changes;
+
+    // with `--bump none` the buildpack (and the versions of its dependencies) are left as they are
+    let updated_dependencies = if current_version == next_version {
         eprintln!(
-            "✅️ Added release entry {next_version}: {}",
-            changelog_file.path.display()
+            "✅️ Kept version {current_version}: {}",
+            buildpack_file.path.display()
         );
+        HashSet::new()
+    } else {
+        let updated_dependencies =
+            get_updated_dependency_ids(buildpack_file, release_changes.updated_buildpack_ids)?;
+        update_buildpack_files(
+            buildpack_file,
+            &updated_dependencies,
+            release_changes,
+            writer,
+        )?;
+        updated_dependencies
+    };
+
+    let mut new_changelog = promote_changelog_unreleased_to_version(
+        &changelog_file.changelog,
+        next_version,
+        release_changes.release_date,
+        &updated_dependencies,
+        release_changes.dependency_change_entry,
+    );
+    record_released_by(&mut new_changelog, release_changes);
+
+    writer
+        .write(
+            &changelog_file.path,
+            &changelog_contents(&new_changelog, release_changes),
+        )
+        .map_err(|e| Error::WritingChangelog(changelog_file.path.clone(), e))?;
+
+    eprintln!(
+        "✅️ Added release entry {next_version}: {}",
+        changelog_file.path.display()
+    );
+
+    Ok(new_changelog
+        .releases
+        .get(&next_version.to_string())
+        .map(|release| release.body.clone())
+        .unwrap_or_default())
+}
+
+// The root changelog's release entry has the changes of each buildpack under a heading for its id
+// (with the buildpack's own headings moved down a level), after any changes from the root
+// changelog's own unreleased section.
+fn update_root_changelog(
+    root_changelog_file: &ChangelogFile,
+    release_notes: &[(BuildpackId, String)],
+    release_changes: &ReleaseChanges,
+    writer: &mut ReleaseWriter,
+) -> Result<()> {
+    let next_version = release_changes.next_version;
+    let ChangelogFile { path, changelog } = root_changelog_file;
+
+    if changelog.releases.contains_key(&next_version.to_string()) {
+        Err(Error::ReleaseEntryAlreadyExists(
+            path.clone(),
+            next_version.clone(),
+        ))?;
     }
 
+    let buildpack_sections = release_notes
+        .iter()
+        .map(|(buildpack_id, changes)| (buildpack_id.to_string(), changes))
+        .collect::<BTreeMap<_, _>>()
+        .into_iter()
+        .map(|(buildpack_id, changes)| {
+            format!("### {buildpack_id}\n\n{}", demote_headings(changes))
+        });
+    let unreleased = changelog
+        .unreleased
+        .clone()
+        .into_iter()
+        .chain(buildpack_sections)
+        .collect::<Vec<_>>()
+        .join("\n\n");
+
+    let mut new_changelog = promote_changelog_unreleased_to_version(
+        &Changelog {
+            protected_preamble: changelog.protected_preamble.clone(),
+            unreleased: Some(unreleased),
+            releases: changelog.releases.clone(),
+        },
+        next_version,
+        release_changes.release_date,
+        &HashSet::new(),
+        release_changes.dependency_change_entry,
+    );
+    record_released_by(&mut new_changelog, release_changes);
+
+    writer
+        .write(path, &changelog_contents(&new_changelog, release_changes))
+        .map_err(|e| Error::WritingChangelog(path.clone(), e))?;
+
+    eprintln!("✅️ Added release entry {next_version}: {}", path.display());
+
+    Ok(())
+}
+
+fn update_buildpack_files(
+    buildpack_file: &mut BuildpackFile,
+    updated_dependencies: &HashSet<BuildpackId>,
+    release_changes: &ReleaseChanges,
+    writer: &mut ReleaseWriter,
+) -> Result<()> {
+    let ReleaseChanges {
+        current_version,
+        next_version,
+        ..
+    } = release_changes;
+
+    let new_buildpack_contents = update_buildpack_contents_with_new_version(
+        buildpack_file,
+        next_version,
+        updated_dependencies,
+    )?;
+
+    writer
+        .write(&buildpack_file.path, &new_buildpack_contents)
+        .map_err(|e| Error::WritingBuildpack(buildpack_file.path.clone(), e))?;
+
+    eprintln!(
+        "✅️ Updated version {current_version} → {next_version}: {}",
+        buildpack_file.path.display(),
+    );
+
+    update_package_file(
+        &buildpack_file.path.with_file_name("package.toml"),
+        release_changes.released_repositories,
+        next_version,
+        writer,
+    )
+}
+
+fn add_unreleased_changes(changelog_files: &mut [ChangelogFile], added_changes: &[AddedChange]) {
+    for changelog_file in changelog_files {
+        for added_change in added_changes {
+            changelog_file
+                .changelog
+                .add_unreleased_entry(&added_change.group.to_string(), &added_change.text);
+        }
+    }
+}
+
+fn read_released_buildpacks(
+    buildpack_ids: &[BuildpackId],
+    buildpack_files: &[BuildpackFile],
+    changelog_files: &[ChangelogFile],
+    current_version: &Version,
+    next_version: &Version,
+) -> BTreeMap<String, ReleasedBuildpack> {
+    buildpack_ids
+        .iter()
+        .zip(buildpack_files.iter().zip(changelog_files))
+        .map(|(buildpack_id, (buildpack_file, changelog_file))| {
+            (
+                buildpack_id.to_string(),
+                ReleasedBuildpack {
+                    path: buildpack_file
+                        .path
+                        .parent()
+                        .unwrap_or(&buildpack_file.path)
+                        .to_path_buf(),
+                    from_version: current_version.to_string(),
+                    to_version: next_version.to_string(),
+                    changelog_path: changelog_file.path.clone(),
+                },
+            )
+        })
+        .collect()
+}
+
+fn set_release_outputs(
+    current_version: &Version,
+    next_version: &Version,
+    released_buildpacks: &BTreeMap<String, ReleasedBuildpack>,
+) -> Result<()> {
     actions::set_output("from_version", current_version.to_string())
         .map_err(Error::SetActionOutput)?;
     actions::set_output("to_version", next_version.to_string()).map_err(Error::SetActionOutput)?;
+    actions::set_output(
+        "released_buildpacks",
+        serde_json::to_string(released_buildpacks).map_err(Error::SerializingJson)?,
+    )
+    .map_err(Error::SetActionOutput)
+}
 
-    Ok(())
+// The `### Security` entries being released for each buildpack.
+fn read_security_fixes(
+    buildpack_ids: &[BuildpackId],
+    changelog_files: &[ChangelogFile],
+) -> BTreeMap<String, Vec<String>> {
+    buildpack_ids
+        .iter()
+        .zip(changelog_files)
+        .filter_map(|(buildpack_id, changelog_file)| {
+            let body = changelog_file.changelog.unreleased.as_deref()?;
+            changes_by_group(body)
+                .swap_remove("Security")
+                .filter(|entries| !entries.is_empty())
+                .map(|entries| (buildpack_id.to_string(), entries))
+        })
+        .collect()
+}
+
+// Lets the release workflow fast-track publishing (and notify about it) when the release
+// includes security fixes.
+fn set_security_fix_outputs(security_fixes: &BTreeMap<String, Vec<String>>) -> Result<()> {
+    actions::set_output(
+        "includes_security_fixes",
+        (!security_fixes.is_empty()).to_string(),
+    )
+    .map_err(Error::SetActionOutput)?;
+
+    if security_fixes.is_empty() {
+        return Ok(());
+    }
+
+    let entries = security_fixes
+        .iter()
+        .flat_map(|(buildpack_id, entries)| {
+            entries
+                .iter()
+                .map(move |entry| format!("> - `{buildpack_id}`: {}", entry.replace('\n', " ")))
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    actions::set_summary(format!(
+        "> [!CAUTION]\n> This release includes security fixes:\n>\n{entries}"
+    ))
+    .map_err(Error::SetActionOutput)
+}
+
+// Commits every file the release touched to the pull request branch, which starts from the
+// commit the release was prepared on.
+fn create_release_pull_request(
+    current_dir: &Path,
+    repository_url: &str,
+    branch: &str,
+    writer: &ReleaseWriter,
+    released_buildpacks: &BTreeMap<String, ReleasedBuildpack>,
+    next_version: &Version,
+) -> Result<()> {
+    let repository = github_repository(repository_url).ok_or(
+        Error::InvalidPullRequestRepository(repository_url.to_string()),
+    )?;
+
+    let paths = writer.written.iter().collect::<Vec<_>>();
+
+    let title = format!("Prepare release v{next_version}");
+    let base = current_branch(current_dir).map_err(Error::Git)?;
+    checkout_branch(current_dir, branch).map_err(Error::Git)?;
+
+    if !commit_files(current_dir, &paths, &title).map_err(Error::Git)? {
+        eprintln!("⚠️ No release changes to commit, skipping the pull request");
+        return Ok(());
+    }
+
+    let token = env::var("GH_TOKEN")
+        .or_else(|_| env::var("GITHUB_TOKEN"))
+        .ok();
+    push_branch(current_dir, repository_url, branch, token.as_deref()).map_err(Error::Git)?;
+
+    let url = create_pull_request(
+        &repository,
+        &base,
+        branch,
+        &title,
+        &pull_request_body(released_buildpacks, next_version),
+    )
+    .map_err(Error::CreatePullRequest)?;
+
+    eprintln!("✅️ Opened pull request: {url}");
+    actions::set_output("pull_request_url", url).map_err(Error::SetActionOutput)
+}
+
+// e.g.; `https://github.com/heroku/buildpacks-nodejs.git` → `heroku/buildpacks-nodejs`
+fn github_repository(repository_url: &str) -> Option<String> {
+    let path = repository_url
+        .split_once("://")
+        .map_or(repository_url, |(_, rest)| rest)
+        .trim_end_matches('/')
+        .trim_end_matches(".git");
+    match path.split('/').collect::<Vec<_>>().as_slice() {
+        [_host, owner, name] if !owner.is_empty() && !name.is_empty() => {
+            Some(format!("{owner}/{name}"))
+        }
+        _ => None,
+    }
+}
+
+fn pull_request_body(
+    released_buildpacks: &BTreeMap<String, ReleasedBuildpack>,
+    next_version: &Version,
+) -> String {
+    let rows = released_buildpacks
+        .iter()
+        .map(|(buildpack_id, released_buildpack)| {
+            format!(
+                "| `{buildpack_id}` | `{}` | `{}` |",
+                released_buildpack.from_version, released_buildpack.to_version
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    format!(
+        "Prepares the release of v{next_version}.\n\n| Buildpack | From | To |\n|-----------|------|----|\n{rows}\n"
+    )
+}
+
+// Dry runs report the changes they would have made instead of writing them.
+fn finish_release(writer: &mut ReleaseWriter) -> Result<()> {
+    if writer.dry_run {
+        writer.write_summary()
+    } else {
+        writer.commit()
+    }
+}
+
+// With `--previous-version-from git-tags` changelog links compare against the tags that actually
+// exist, and releases in the changelog without a tag (whose links would 404) are reported.
+fn read_release_tags(
+    source: PreviousVersionSource,
+    current_dir: &Path,
+    changelog_files: &[ChangelogFile],
+    declarations_starting_version: Option<&Version>,
+) -> Result<Option<Vec<Version>>> {
+    if source == PreviousVersionSource::Changelog {
+        return Ok(None);
+    }
+
+    let tags = list_tags(current_dir)
+        .map_err(Error::Git)?
+        .iter()
+        .filter_map(|tag| tag.strip_prefix('v'))
+        .filter_map(|version| Version::parse(version).ok())
+        .collect::<Vec<_>>();
+
+    if tags.is_empty() {
+        eprintln!("⚠️ No vX.Y.Z tags found, changelog links will compare against the previous release in the changelog");
+        return Ok(None);
+    }
+
+    let untagged_versions = changelog_files
+        .iter()
+        .flat_map(|changelog_file| changelog_file.changelog.releases.values())
+        .map(|release| &release.version)
+        .filter(|version| {
+            declarations_starting_version.map_or(true, |starting| starting.le(version))
+        })
+        .filter(|version| !tags.contains(version))
+        .collect::<BTreeSet<_>>();
+
+    for version in untagged_versions {
+        eprintln!("⚠️ No tag v{version} found for the changelog release {version}, its changelog link won't resolve");
+    }
+
+    Ok(Some(tags))
+}
+
+// A version that's already registered (e.g.; by an earlier release whose changes were never
+// merged) can't be published again, so the release is refused instead of half-finished.
+fn check_unregistered_version(buildpack_ids: &[BuildpackId], version: &Version) -> Result<()> {
+    let version_string = version.to_string();
+    let registered = try_map_concurrently(buildpack_ids, MAX_CONCURRENT_REQUESTS, |buildpack_id| {
+        is_registered_in_cnb_registry(buildpack_id.as_str(), &version_string)
+    })
+    .map_err(Error::CheckCnbRegistry)?;
+
+    let registered_buildpack_ids = buildpack_ids
+        .iter()
+        .zip(registered)
+        .filter(|(_, registered)| *registered)
+        .map(|(buildpack_id, _)| buildpack_id.to_string())
+        .collect::<Vec<_>>();
+
+    if registered_buildpack_ids.is_empty() {
+        Ok(())
+    } else {
+        Err(Error::VersionAlreadyRegistered(
+            version.clone(),
+            registered_buildpack_ids,
+        ))
+    }
+}
+
+fn resolve_project_dir(project_dir: Option<&Path>, current_dir: &Path) -> PathBuf {
+    project_dir.map_or_else(
+        || current_dir.to_path_buf(),
+        |dir| resolve_path(dir, current_dir),
+    )
+}
+
+// Replayed or backdated release runs can pin the date so they produce identical changelogs.
+// `SOURCE_DATE_EPOCH` is the reproducible builds convention for this (a Unix timestamp).
+fn resolve_release_date(
+    release_date: Option<NaiveDate>,
+    source_date_epoch: Option<String>,
+) -> Result<DateTime<Utc>> {
+    if let Some(release_date) = release_date {
+        return Ok(release_date.and_time(NaiveTime::MIN).and_utc());
+    }
+
+    match source_date_epoch {
+        Some(value) => value
+            .trim()
+            .parse::<i64>()
+            .ok()
+            .and_then(|seconds| Utc.timestamp_opt(seconds, 0).single())
+            .ok_or(Error::InvalidSourceDateEpoch(value)),
+        None => Ok(Utc::now()),
+    }
+}
+
+fn parse_repository_url(value: &str) -> Result<URI<'static>> {
+    URI::try_from(value)
+        .map(URI::into_owned)
+        .map_err(|e| Error::InvalidRepositoryUrl(value.to_string(), e))
+}
+
+fn parse_declarations_starting_version(value: Option<String>) -> Result<Option<Version>> {
+    value
+        .map(|value| {
+            value
+                .parse::<Version>()
+                .map_err(|e| Error::InvalidDeclarationsStartingVersion(value, e))
+        })
+        .transpose()
+}
+
+fn changelog_contents(new_changelog: &Changelog, release_changes: &ReleaseChanges) -> String {
+    let release_declarations = generate_release_declarations(
+        new_changelog,
+        release_changes.repository_url,
+        release_changes.link_templates,
+        release_changes.declarations_starting_version,
+        release_changes.release_tags,
+    );
+
+    format!("{new_changelog}\n{release_declarations}\n")
+}
+
+// With `--dry-run` files are left untouched and a unified diff of each change is printed and
+// collected for the job summary instead. Otherwise the new contents are staged and only written,
+// all at once, by `commit`. The written files are recorded so they can be committed with
+// `--create-pull-request`.
+struct ReleaseWriter {
+    base_dir: PathBuf,
+    dry_run: bool,
+    diffs: Vec<String>,
+    staged: BTreeMap<PathBuf, StagedFile>,
+    written: BTreeSet<PathBuf>,
+}
+
+// The contents of a file when it was first staged are kept to check that nothing else changed it
+// in the meantime and to restore it if the release can't be written.
+struct StagedFile {
+    original: String,
+    contents: String,
+}
+
+impl ReleaseWriter {
+    fn new(base_dir: &Path, dry_run: bool) -> Self {
+        ReleaseWriter {
+            base_dir: base_dir.to_path_buf(),
+            dry_run,
+            diffs: vec![],
+            staged: BTreeMap::new(),
+            written: BTreeSet::new(),
+        }
+    }
+
+    // Files are staged by their normalized path so a changelog shared by several buildpacks
+    // (e.g.; `../CHANGELOG.md`) is only written once.
+    fn write(&mut self, path: &Path, contents: &str) -> std::io::Result<()> {
+        let original = std::fs::read_to_string(path)?;
+
+        if !self.dry_run {
+            self.staged
+                .entry(normalize_path(path))
+                .and_modify(|staged_file| staged_file.contents = contents.to_string())
+                .or_insert_with(|| StagedFile {
+                    original,
+                    contents: contents.to_string(),
+                });
+            return Ok(());
+        }
+
+        let name = path.strip_prefix(&self.base_dir).unwrap_or(path).display();
+        let diff = DiffOptions::new()
+            .set_original_filename(format!("a/{name}"))
+            .set_modified_filename(format!("b/{name}"))
+            .create_patch(&original, contents)
+            .to_string();
+
+        eprint!("{diff}");
+        self.diffs.push(diff);
+        Ok(())
+    }
+
+    // Every staged file is verified and written to a temporary file next to it before any of
+    // them are renamed over the originals. If a rename fails, the files that were already
+    // replaced are restored so a failed release doesn't leave the repository half-released.
+    fn commit(&mut self) -> Result<()> {
+        for (path, staged_file) in &self.staged {
+            verify_staged_file(path, staged_file)?;
+        }
+
+        let mut temporary_paths = vec![];
+        for (path, staged_file) in &self.staged {
+            let temporary_path = temporary_file_path(path);
+            if let Err(e) = write(&temporary_path, &staged_file.contents) {
+                remove_temporary_files(&temporary_paths);
+                Err(Error::StagingReleaseFile(path.clone(), e))?;
+            }
+            temporary_paths.push(temporary_path);
+        }
+
+        let mut replaced = vec![];
+        for ((path, _), temporary_path) in self.staged.iter().zip(&temporary_paths) {
+            if let Err(e) = std::fs::rename(temporary_path, path) {
+                remove_temporary_files(&temporary_paths[replaced.len()..]);
+                let not_restored = self.restore(&replaced);
+                return Err(Error::ReplacingReleaseFile(
+                    path.clone(),
+                    e,
+                    replaced,
+                    not_restored,
+                ));
+            }
+            replaced.push(path.clone());
+        }
+
+        eprintln!("✅️ Wrote {} release files", replaced.len());
+        self.written.extend(replaced);
+        self.staged.clear();
+        Ok(())
+    }
+
+    // Returns the files that couldn't be restored.
+    fn restore(&self, paths: &[PathBuf]) -> Vec<PathBuf> {
+        paths
+            .iter()
+            .filter(|path| {
+                let temporary_path = temporary_file_path(path);
+                let restored = write(&temporary_path, &self.staged[*path].original)
+                    .and_then(|()| std::fs::rename(&temporary_path, path));
+                if restored.is_err() {
+                    let _ = std::fs::remove_file(&temporary_path);
+                }
+                restored.is_err()
+            })
+            .cloned()
+            .collect()
+    }
+
+    fn write_summary(&self) -> Result<()> {
+        actions::set_summary(format!(
+            "## Release preview\n\n```diff\n{}```",
+            self.diffs.concat()
+        ))
+        .map_err(Error::SetActionOutput)
+    }
+}
+
+fn verify_staged_file(path: &Path, staged_file: &StagedFile) -> Result<()> {
+    let current = std::fs::read_to_string(path)
+        .map_err(|e| Error::VerifyingReleaseFile(path.to_path_buf(), e))?;
+    if current != staged_file.original {
+        Err(Error::ReleaseFileModified(path.to_path_buf()))?;
+    }
+
+    let problem = if path
+        .extension()
+        .is_some_and(|extension| extension == "toml")
+    {
+        DocumentMut::from_str(&staged_file.contents)
+            .err()
+            .map(|e| e.to_string())
+    } else if path.file_name().is_some_and(|name| name == "CHANGELOG.md") {
+        Changelog::try_from(staged_file.contents.as_str())
+            .err()
+            .map(|e| e.to_string())
+    } else {
+        None
+    };
+    match problem {
+        Some(problem) => Err(Error::InvalidReleaseFile(path.to_path_buf(), problem)),
+        None => Ok(()),
+    }
+}
+
+fn temporary_file_path(path: &Path) -> PathBuf {
+    let name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+    path.with_file_name(format!(".{name}.prepare-release"))
+}
+
+fn remove_temporary_files(paths: &[PathBuf]) {
+    for path in paths {
+        let _ = std::fs::remove_file(path);
+    }
+}
+
+// The files are locked before they're read so another command can't modify them while the
+// release is being prepared.
+fn lock_and_read_release_files(
+    buildpack_dirs: &[PathBuf],
+    root_changelog_path: Option<&Path>,
+    coerce_versions: bool,
+) -> Result<(Vec<FileLock>, Vec<BuildpackFile>, Vec<ChangelogFile>)> {
+    let changelog_paths = read_changelog_paths(buildpack_dirs)?;
+
+    if let Some(root_changelog_path) = root_changelog_path {
+        let is_root_changelog =
+            |path: &PathBuf| path.canonicalize().ok() == root_changelog_path.canonicalize().ok();
+        if changelog_paths.iter().any(is_root_changelog) {
+            Err(Error::RootChangelogIsBuildpackChangelog(
+                root_changelog_path.to_path_buf(),
+            ))?;
+        }
+    }
+
+    let locks = lock_files(
+        buildpack_dirs
+            .iter()
+            .flat_map(|dir| [descriptor_path(dir), dir.join("package.toml")])
+            .chain(changelog_paths.iter().cloned())
+            .chain(root_changelog_path.map(Path::to_path_buf)),
+    )
+    .map_err(Error::LockFile)?;
+
+    let mut buildpack_files = buildpack_dirs
+        .iter()
+        .map(|dir| read_buildpack_file(descriptor_path(dir)))
+        .collect::<Result<Vec<_>>>()?;
+
+    check_buildpack_versions(&mut buildpack_files, coerce_versions)?;
+
+    validate_changelogs(&changelog_paths)?;
+
+    let changelog_files = changelog_paths
+        .into_iter()
+        .map(read_changelog_file)
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok((locks, buildpack_files, changelog_files))
+}
+
+// Unreleased entries like "will be removed in 4.0.0" that mention a version beyond the one being
+// released can be a sign that the wrong bump coordinate was picked. This is only a heuristic so
+// the release isn't stopped.
+fn warn_about_future_version_references(
+    changelog_files: &[ChangelogFile],
+    next_version: &Version,
+) -> Result<()> {
+    let references = changelog_files
+        .iter()
+        .flat_map(|changelog_file| {
+            changelog_file
+                .changelog
+                .unreleased
+                .as_deref()
+                .map(|unreleased| find_future_version_references(unreleased, next_version))
+                .unwrap_or_default()
+                .into_iter()
+                .map(|reference| format!("{}: {reference}", changelog_file.path.display()))
+        })
+        .collect::<BTreeSet<_>>();
+
+    if references.is_empty() {
+        return Ok(());
+    }
+
+    let references = references
+        .iter()
+        .map(|reference| format!("• {reference}"))
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    eprintln!("⚠️ Unreleased changes reference versions after {next_version}, check that the bump is correct:\n{references}");
+
+    actions::set_summary(format!(
+        "> [!WARNING]\n> Unreleased changes reference versions after {next_version}, check that the bump is correct:\n>\n{}",
+        references
+            .lines()
+            .map(|line| format!("> {line}"))
+            .collect::<Vec<_>>()
+            .join("\n")
+    ))
+    .map_err(Error::SetActionOutput)
+}
+
+fn find_future_version_references(value: &str, next_version: &Version) -> Vec<String> {
+    lazy_static! {
+        static ref FUTURE_VERSION: Regex = Regex::new(
+            r"(?i)\b(?:in|until|by|before|starting (?:with|in|from))\s+(?:version\s+)?v?(\d+)\.(\d+)\.(\d+)\b"
+        )
+        .expect("Should be a valid regex");
+    }
+
+    FUTURE_VERSION
+        .captures_iter(value)
+        .filter_map(|captures| {
+            let version = Version::new(
+                captures[1].parse().ok()?,
+                captures[2].parse().ok()?,
+                captures[3].parse().ok()?,
+            );
+            let is_future = (version.major, version.minor, version.patch)
+                > (next_version.major, next_version.minor, next_version.patch);
+            is_future.then(|| captures[0].to_string())
+        })
+        .collect()
+}
+
+fn find_sorted_buildpack_dirs(project_dir: &Path, exclude: &[Glob]) -> Result<Vec<PathBuf>> {
+    let mut buildpack_dirs = find_releasable_buildpacks(project_dir, exclude)
+        .map_err(Error::FindReleasableBuildpacks)?;
+
+    if buildpack_dirs.is_empty() {
+        Err(Error::NoBuildpacksFound(project_dir.to_path_buf()))?;
+    }
+
+    // sorted so the release notes and outputs list the buildpacks in a stable order
+    buildpack_dirs.sort();
+
+    Ok(buildpack_dirs)
+}
+
+// Read from the descriptor's TOML rather than with `read_buildpack_descriptor`, which rejects any
+// version that isn't `X.Y.Z` (e.g.; a pre-release like `2.0.0-rc.3` that's being finalized).
+fn read_changelog_paths(buildpack_dirs: &[PathBuf]) -> Result<Vec<PathBuf>> {
+    buildpack_dirs
+        .iter()
+        .map(|dir| {
+            let buildpack_file = read_buildpack_file(descriptor_path(dir))?;
+            let changelog = buildpack_file
+                .document
+                .get("metadata")
+                .and_then(|metadata| metadata.get("release"))
+                .and_then(|release| release.get("changelog"))
+                .and_then(toml_edit::Item::as_str)
+                .unwrap_or("CHANGELOG.md");
+            Ok(dir.join(changelog))
+        })
+        .collect()
+}
+
+// Returns the versions released from and to.
+fn start_release(
+    buildpack_files: &[BuildpackFile],
+    changelog_files: &[ChangelogFile],
+    version: Option<&str>,
+    bump: Option<&BumpCoordinate>,
+) -> Result<(Version, Version)> {
+    let current_version = get_fixed_version(buildpack_files)?;
+    let next_version = resolve_next_version(
+        &current_version,
+        &resolve_release_version(version, bump, changelog_files)?,
+    )?;
+
+    warn_about_future_version_references(changelog_files, &next_version)?;
+
+    Ok((current_version, next_version))
+}
+
+fn resolve_next_version(
+    current_version: &Version,
+    release_version: &ReleaseVersion,
+) -> Result<Version> {
+    match release_version {
+        ReleaseVersion::Bump(bump) => Ok(get_next_version(current_version, bump)),
+        ReleaseVersion::Explicit(version) => {
+            if version <= current_version {
+                Err(Error::VersionNotGreater(
+                    version.clone(),
+                    current_version.clone(),
+                ))?;
+            }
+            Ok(version.clone())
+        }
+    }
 }
 
 fn read_buildpack_file(path: PathBuf) -> Result<BuildpackFile> {
@@ -145,6 +1274,308 @@ fn read_buildpack_file(path: PathBuf) -> Result<BuildpackFile> {
     Ok(BuildpackFile { path, document })
 }
 
+fn get_image_repository(buildpack_file: &BuildpackFile) -> Option<String> {
+    buildpack_file
+        .document
+        .get("metadata")
+        .and_then(|metadata| metadata.get("release"))
+        .and_then(|release| release.get("image"))
+        .and_then(|image| image.get("repository"))
+        .and_then(toml_edit::Item::as_str)
+        .map(ToString::to_string)
+}
+
+fn update_package_file(
+    path: &Path,
+    released_repositories: &HashSet<String>,
+    next_version: &Version,
+    writer: &mut ReleaseWriter,
+) -> Result<()> {
+    if !path.exists() {
+        return Ok(());
+    }
+
+    let contents =
+        std::fs::read_to_string(path).map_err(|e| Error::ReadingPackage(path.to_path_buf(), e))?;
+    let mut document = DocumentMut::from_str(&contents)
+        .map_err(|e| Error::ParsingPackage(path.to_path_buf(), e))?;
+
+    if update_package_dependencies(&mut document, released_repositories, next_version) {
+        writer
+            .write(path, &document.to_string())
+            .map_err(|e| Error::WritingPackage(path.to_path_buf(), e))?;
+        eprintln!(
+            "✅️ Updated dependencies to {next_version}: {}",
+            path.display()
+        );
+    }
+
+    Ok(())
+}
+
+// libcnb.rs buildpacks are also crates, and their `Cargo.toml` version is kept in step with
+// `buildpack.toml`. Crates that inherit their version (`version.workspace = true`) are synced by
+// updating `workspace.package.version` in the workspace manifest instead, once per workspace.
+fn sync_cargo_versions(
+    buildpack_dirs: &[PathBuf],
+    current_dir: &Path,
+    next_version: &Version,
+    writer: &mut ReleaseWriter,
+) -> Result<()> {
+    let mut manifests = BTreeMap::new();
+
+    for buildpack_dir in buildpack_dirs {
+        let path = buildpack_dir.join("Cargo.toml");
+        if !path.exists() {
+            continue;
+        }
+        if inherits_workspace_version(&read_cargo_manifest(&path)?) {
+            let workspace_path = find_cargo_workspace_manifest(buildpack_dir, current_dir)?
+                .ok_or(Error::MissingCargoWorkspace(path.clone()))?;
+            manifests.insert(workspace_path, ["workspace", "package"].as_slice());
+        } else {
+            manifests.insert(path, ["package"].as_slice());
+        }
+    }
+
+    for (path, table_path) in manifests {
+        let mut document = read_cargo_manifest(&path)?;
+        if update_cargo_version(&mut document, table_path, next_version) {
+            writer
+                .write(&path, &document.to_string())
+                .map_err(|e| Error::WritingCargoManifest(path.clone(), e))?;
+            eprintln!(
+                "✅️ Updated crate version to {next_version}: {}",
+                path.display()
+            );
+        }
+    }
+
+    Ok(())
+}
+
+fn update_readme_versions(
+    buildpack_dirs: &[PathBuf],
+    release_changes: &ReleaseChanges,
+    writer: &mut ReleaseWriter,
+) -> Result<()> {
+    let ReleaseChanges {
+        current_version,
+        next_version,
+        ..
+    } = release_changes;
+
+    if current_version == next_version {
+        return Ok(());
+    }
+
+    for buildpack_dir in buildpack_dirs {
+        let path = buildpack_dir.join(README_FILE);
+        if !path.exists() {
+            continue;
+        }
+        let contents =
+            std::fs::read_to_string(&path).map_err(|e| Error::ReadingReadme(path.clone(), e))?;
+        let new_contents = replace_marked_versions(
+            &contents,
+            &current_version.to_string(),
+            &next_version.to_string(),
+        )
+        .ok_or(Error::UnclosedVersionMarker(path.clone()))?;
+        if new_contents != contents {
+            writer
+                .write(&path, &new_contents)
+                .map_err(|e| Error::WritingReadme(path.clone(), e))?;
+            eprintln!(
+                "✅️ Updated version references {current_version} → {next_version}: {}",
+                path.display()
+            );
+        }
+    }
+    Ok(())
+}
+
+// Replaces whole occurrences of the version (so `1.2.3` isn't matched in `11.2.3` or `1.2.30`)
+// on the lines between the version markers. Returns `None` if a marker isn't closed.
+fn replace_marked_versions(contents: &str, from: &str, to: &str) -> Option<String> {
+    let mut in_marked_lines = false;
+    let mut new_contents = String::with_capacity(contents.len());
+    for line in contents.split_inclusive('\n') {
+        if line.contains(VERSION_MARKER_START) {
+            in_marked_lines = true;
+        } else if line.contains(VERSION_MARKER_END) {
+            in_marked_lines = false;
+        } else if in_marked_lines {
+            new_contents.push_str(&replace_version(line, from, to));
+            continue;
+        }
+        new_contents.push_str(line);
+    }
+    (!in_marked_lines).then_some(new_contents)
+}
+
+fn replace_version(line: &str, from: &str, to: &str) -> String {
+    let mut new_line = String::with_capacity(line.len());
+    let mut last_end = 0;
+    for (start, _) in line.match_indices(from) {
+        let end = start + from.len();
+        let before = line[..start].chars().next_back();
+        let mut after = line[end..].chars();
+        // a `.` after the version ends a sentence unless more of a version follows it
+        let continues_version = match after.next() {
+            Some('.') => after.next().is_some_and(|c| c.is_ascii_digit()),
+            next => next.is_some_and(|c| c.is_ascii_alphanumeric() || c == '-' || c == '+'),
+        };
+        if before.is_some_and(|c| c.is_ascii_digit() || c == '.') || continues_version {
+            continue;
+        }
+        new_line.push_str(&line[last_end..start]);
+        new_line.push_str(to);
+        last_end = end;
+    }
+    new_line.push_str(&line[last_end..]);
+    new_line
+}
+
+fn read_cargo_manifest(path: &Path) -> Result<DocumentMut> {
+    let contents = std::fs::read_to_string(path)
+        .map_err(|e| Error::ReadingCargoManifest(path.to_path_buf(), e))?;
+    DocumentMut::from_str(&contents).map_err(|e| Error::ParsingCargoManifest(path.to_path_buf(), e))
+}
+
+fn inherits_workspace_version(document: &DocumentMut) -> bool {
+    document
+        .get("package")
+        .and_then(|package| package.get("version"))
+        .and_then(|version| version.get("workspace"))
+        .and_then(toml_edit::Item::as_bool)
+        .unwrap_or(false)
+}
+
+// The closest manifest with a `[workspace]` table, from the buildpack directory up to (and
+// including) the project directory.
+fn find_cargo_workspace_manifest(
+    buildpack_dir: &Path,
+    current_dir: &Path,
+) -> Result<Option<PathBuf>> {
+    for dir in buildpack_dir
+        .ancestors()
+        .take_while(|dir| dir.starts_with(current_dir))
+    {
+        let path = dir.join("Cargo.toml");
+        if path.exists() && read_cargo_manifest(&path)?.contains_key("workspace") {
+            return Ok(Some(path));
+        }
+    }
+    Ok(None)
+}
+
+// Only a literal version string is replaced (keeping its comments and spacing), so manifests
+// without a version at the given table are left as-is.
+fn update_cargo_version(
+    document: &mut DocumentMut,
+    table_path: &[&str],
+    next_version: &Version,
+) -> bool {
+    let table = table_path
+        .iter()
+        .try_fold(document.as_item_mut(), |item, key| item.get_mut(key));
+
+    let Some(version) = table
+        .and_then(|table| table.get_mut("version"))
+        .and_then(toml_edit::Item::as_value_mut)
+    else {
+        return false;
+    };
+
+    if version.as_str().is_none() || version.as_str() == Some(next_version.to_string().as_str()) {
+        return false;
+    }
+
+    let decor = version.decor().clone();
+    *version = toml_edit::Value::from(next_version.to_string());
+    *version.decor_mut() = decor;
+    true
+}
+
+// Dependencies that reference the image of a buildpack released in the same run (e.g.;
+// `docker://docker.io/heroku/buildpack-nodejs-engine:1.2.3`) are moved to the new version.
+// Local (e.g.; `libcnb:` or relative path) and digest-pinned references are left as-is.
+fn update_package_dependencies(
+    document: &mut DocumentMut,
+    released_repositories: &HashSet<String>,
+    next_version: &Version,
+) -> bool {
+    let mut updated = false;
+
+    let dependencies = document
+        .get_mut("dependencies")
+        .and_then(toml_edit::Item::as_array_of_tables_mut)
+        .into_iter()
+        .flat_map(ArrayOfTables::iter_mut);
+
+    for dependency in dependencies {
+        let Some(uri) = dependency
+            .get_mut("uri")
+            .and_then(toml_edit::Item::as_value_mut)
+        else {
+            continue;
+        };
+        let Some(new_uri) = uri
+            .as_str()
+            .and_then(|uri| update_image_uri_version(uri, released_repositories, next_version))
+        else {
+            continue;
+        };
+        let decor = uri.decor().clone();
+        *uri = toml_edit::Value::from(new_uri);
+        *uri.decor_mut() = decor;
+        updated = true;
+    }
+
+    updated
+}
+
+fn update_image_uri_version(
+    uri: &str,
+    released_repositories: &HashSet<String>,
+    next_version: &Version,
+) -> Option<String> {
+    let (scheme, image) = uri
+        .strip_prefix("docker://")
+        .map_or(("", uri), |image| ("docker://", image));
+    if image.contains('@') {
+        return None;
+    }
+    let (repository, tag) = image.rsplit_once(':')?;
+    if tag.contains('/') || !released_repositories.contains(repository) {
+        return None;
+    }
+    let new_uri = format!("{scheme}{repository}:{next_version}");
+    (new_uri != uri).then_some(new_uri)
+}
+
+// Every changelog is checked before anything is written so that all the problems are reported
+// together instead of the release stopping partway through at the first one.
+fn validate_changelogs(changelog_paths: &[PathBuf]) -> Result<()> {
+    let mut invalid_changelogs = vec![];
+
+    for path in changelog_paths.iter().collect::<BTreeSet<_>>() {
+        let contents =
+            std::fs::read_to_string(path).map_err(|e| Error::ReadingChangelog(path.clone(), e))?;
+        let problems = validate_changelog(&contents);
+        if !problems.is_empty() {
+            invalid_changelogs.push((path.clone(), problems));
+        }
+    }
+
+    if invalid_changelogs.is_empty() {
+        Ok(())
+    } else {
+        Err(Error::InvalidChangelogs(invalid_changelogs))
+    }
+}
+
 fn read_changelog_file(path: PathBuf) -> Result<ChangelogFile> {
     let contents =
         std::fs::read_to_string(&path).map_err(|e| Error::ReadingChangelog(path.clone(), e))?;
@@ -153,35 +1584,118 @@ fn read_changelog_file(path: PathBuf) -> Result<ChangelogFile> {
     Ok(ChangelogFile { path, changelog })
 }
 
+// Extensions keep the same fields under `[extension]` in `extension.toml`.
+fn descriptor_table_name(buildpack_file: &BuildpackFile) -> &'static str {
+    if buildpack_file.path.ends_with("extension.toml") {
+        "extension"
+    } else {
+        "buildpack"
+    }
+}
+
 fn get_buildpack_id(buildpack_file: &BuildpackFile) -> Result<BuildpackId> {
+    let table_name = descriptor_table_name(buildpack_file);
     let buildpack_id = buildpack_file
         .document
-        .get("buildpack")
+        .get(table_name)
         .and_then(toml_edit::Item::as_table_like)
         .and_then(|buildpack| buildpack.get("id"))
         .and_then(|id| id.as_str().map(std::string::ToString::to_string))
         .ok_or(Error::MissingRequiredField(
             buildpack_file.path.clone(),
-            "buildpack.id".to_string(),
+            format!("{table_name}.id"),
         ))?;
     buildpack_id
         .parse()
         .map_err(|_| Error::InvalidBuildpackId(buildpack_file.path.clone(), buildpack_id.clone()))
 }
 
-fn get_buildpack_version(buildpack_file: &BuildpackFile) -> Result<BuildpackVersion> {
-    let version = buildpack_file
-        .document
-        .get("buildpack")
-        .and_then(toml_edit::Item::as_table_like)
-        .and_then(|buildpack| buildpack.get("version"))
-        .and_then(|version| version.as_str().map(std::string::ToString::to_string))
-        .ok_or(Error::MissingRequiredField(
-            buildpack_file.path.clone(),
-            "buildpack.version".to_string(),
-        ))?;
-    BuildpackVersion::try_from(version.clone())
-        .map_err(|_| Error::InvalidBuildpackVersion(buildpack_file.path.clone(), version))
+fn get_buildpack_version(buildpack_file: &BuildpackFile) -> Result<Version> {
+    let table_name = descriptor_table_name(buildpack_file);
+    let version = buildpack_file
+        .document
+        .get(table_name)
+        .and_then(toml_edit::Item::as_table_like)
+        .and_then(|buildpack| buildpack.get("version"))
+        .and_then(|version| version.as_str().map(std::string::ToString::to_string))
+        .ok_or(Error::MissingRequiredField(
+            buildpack_file.path.clone(),
+            format!("{table_name}.version"),
+        ))?;
+    Version::parse(&version)
+        .map_err(|_| Error::InvalidBuildpackVersion(buildpack_file.path.clone(), version))
+}
+
+// Reports every buildpack whose version isn't in the `X.Y.Z` shape (optionally with a pre-release
+// like `-rc.1`) up front, before any files are touched. With `--coerce-versions` those versions are
+// instead normalized in place (e.g.; `1.2.3.4` → `1.2.3`, `1.2` → `1.2.0`) so the bump can
+// continue from them.
+fn check_buildpack_versions(buildpack_files: &mut [BuildpackFile], coerce: bool) -> Result<()> {
+    let mut unsupported_versions = vec![];
+
+    for buildpack_file in buildpack_files.iter_mut() {
+        let version = match get_buildpack_version(buildpack_file) {
+            Err(Error::InvalidBuildpackVersion(_, version)) => version,
+            result => {
+                result?;
+                continue;
+            }
+        };
+
+        match coerce_version(&version).filter(|_| coerce) {
+            Some(coerced_version) => {
+                let table_name = descriptor_table_name(buildpack_file);
+                buildpack_file
+                    .document
+                    .get_mut(table_name)
+                    .and_then(toml_edit::Item::as_table_like_mut)
+                    .ok_or(Error::MissingRequiredField(
+                        buildpack_file.path.clone(),
+                        table_name.to_string(),
+                    ))?
+                    .insert("version", value(coerced_version.to_string()));
+                eprintln!(
+                    "✅️ Coerced version {version} → {coerced_version}: {}",
+                    buildpack_file.path.display()
+                );
+            }
+            None => unsupported_versions.push((buildpack_file.path.clone(), version)),
+        }
+    }
+
+    if unsupported_versions.is_empty() {
+        Ok(())
+    } else {
+        Err(Error::UnsupportedBuildpackVersions(unsupported_versions))
+    }
+}
+
+fn coerce_version(version: &str) -> Option<Version> {
+    let segments = version
+        .trim()
+        .trim_start_matches('v')
+        .split('.')
+        .map(str::parse::<u64>)
+        .collect::<std::result::Result<Vec<_>, _>>()
+        .ok()?;
+
+    match segments.as_slice() {
+        [major] => Some(Version::new(*major, 0, 0)),
+        [major, minor] => Some(Version::new(*major, *minor, 0)),
+        [major, minor, patch, ..] => Some(Version::new(*major, *minor, *patch)),
+        [] => None,
+    }
+}
+
+// The dependencies of a buildpack that are released in the same run.
+fn get_updated_dependency_ids(
+    buildpack_file: &BuildpackFile,
+    updated_buildpack_ids: &HashSet<BuildpackId>,
+) -> Result<HashSet<BuildpackId>> {
+    Ok(get_buildpack_dependency_ids(buildpack_file)?
+        .into_iter()
+        .filter(|buildpack_id| updated_buildpack_ids.contains(buildpack_id))
+        .collect())
 }
 
 fn get_buildpack_dependency_ids(buildpack_file: &BuildpackFile) -> Result<HashSet<BuildpackId>> {
@@ -217,7 +1731,7 @@ fn get_group_buildpack_id(group: &Table, path: &Path) -> Result<BuildpackId> {
         })
 }
 
-fn get_fixed_version(buildpack_files: &[BuildpackFile]) -> Result<BuildpackVersion> {
+fn get_fixed_version(buildpack_files: &[BuildpackFile]) -> Result<Version> {
     let version_map = buildpack_files
         .iter()
         .map(|buildpack_file| {
@@ -242,44 +1756,108 @@ fn get_fixed_version(buildpack_files: &[BuildpackFile]) -> Result<BuildpackVersi
         .ok_or(Error::NoFixedVersion)
 }
 
-fn get_next_version(current_version: &BuildpackVersion, bump: &BumpCoordinate) -> BuildpackVersion {
-    let BuildpackVersion {
+fn resolve_release_version(
+    version: Option<&str>,
+    bump: Option<&BumpCoordinate>,
+    changelog_files: &[ChangelogFile],
+) -> Result<ReleaseVersion> {
+    match (version, bump) {
+        // releases are always final versions, even when bumping from a pre-release
+        (Some(version), _) => BuildpackVersion::try_from(version.to_string())
+            .map(|version| {
+                ReleaseVersion::Explicit(Version::new(version.major, version.minor, version.patch))
+            })
+            .map_err(|_| Error::InvalidVersion(version.to_string())),
+        (None, Some(bump)) => Ok(ReleaseVersion::Bump(resolve_bump_coordinate(
+            bump,
+            changelog_files,
+        ))),
+        (None, None) => Err(Error::MissingReleaseVersion),
+    }
+}
+
+// Removing something is a breaking change and adding or changing behavior is a new feature
+// while anything else (e.g.; `Fixed` or `Security`) only needs a patch release.
+fn resolve_bump_coordinate(
+    bump: &BumpCoordinate,
+    changelog_files: &[ChangelogFile],
+) -> BumpCoordinate {
+    if *bump != BumpCoordinate::Auto {
+        return bump.clone();
+    }
+
+    let groups = changelog_files
+        .iter()
+        .filter_map(|changelog_file| changelog_file.changelog.unreleased.as_deref())
+        .flat_map(|unreleased| {
+            changes_by_group(unreleased)
+                .into_iter()
+                .filter(|(_, entries)| !entries.is_empty())
+                .map(|(group, _)| group)
+        })
+        .collect::<HashSet<_>>();
+
+    let resolved = if groups.contains("Removed") {
+        BumpCoordinate::Major
+    } else if ["Added", "Changed", "Deprecated"]
+        .iter()
+        .any(|group| groups.contains(*group))
+    {
+        BumpCoordinate::Minor
+    } else {
+        BumpCoordinate::Patch
+    };
+
+    eprintln!(
+        "✅️ Resolved --bump auto to {} from the unreleased changes",
+        format!("{resolved:?}").to_lowercase()
+    );
+
+    resolved
+}
+
+// A pre-release is finalized rather than bumped when it's already a pre-release of the version
+// the bump leads to (e.g.; a major bump of `2.0.0-rc.3` releases `2.0.0` but a patch bump of
+// `2.1.0-rc.1` also releases `2.1.0`). Build metadata is always dropped.
+fn get_next_version(current_version: &Version, bump: &BumpCoordinate) -> Version {
+    let Version {
         major,
         minor,
         patch,
+        pre,
+        ..
     } = current_version;
+    let is_prerelease = !pre.is_empty();
 
     match bump {
-        BumpCoordinate::Major => BuildpackVersion {
-            major: major + 1,
-            minor: 0,
-            patch: 0,
-        },
-        BumpCoordinate::Minor => BuildpackVersion {
-            major: *major,
-            minor: minor + 1,
-            patch: 0,
-        },
-        BumpCoordinate::Patch => BuildpackVersion {
-            major: *major,
-            minor: *minor,
-            patch: patch + 1,
-        },
+        BumpCoordinate::Major if is_prerelease && *minor == 0 && *patch == 0 => {
+            Version::new(*major, 0, 0)
+        }
+        BumpCoordinate::Major => Version::new(major + 1, 0, 0),
+        BumpCoordinate::Minor if is_prerelease && *patch == 0 => Version::new(*major, *minor, 0),
+        BumpCoordinate::Minor => Version::new(*major, minor + 1, 0),
+        // `Auto` is resolved to one of the other coordinates before a version is calculated
+        BumpCoordinate::Patch | BumpCoordinate::Auto if is_prerelease => {
+            Version::new(*major, *minor, *patch)
+        }
+        BumpCoordinate::Patch | BumpCoordinate::Auto => Version::new(*major, *minor, patch + 1),
+        BumpCoordinate::None => current_version.clone(),
     }
 }
 
 fn update_buildpack_contents_with_new_version(
     buildpack_file: &mut BuildpackFile,
-    next_version: &BuildpackVersion,
+    next_version: &Version,
     updated_dependencies: &HashSet<BuildpackId>,
 ) -> Result<String> {
+    let table_name = descriptor_table_name(buildpack_file);
     let buildpack = buildpack_file
         .document
-        .get_mut("buildpack")
+        .get_mut(table_name)
         .and_then(toml_edit::Item::as_table_like_mut)
         .ok_or(Error::MissingRequiredField(
             buildpack_file.path.clone(),
-            "buildpack".to_string(),
+            table_name.to_string(),
         ))?;
 
     buildpack.insert("version", value(next_version.to_string()));
@@ -287,120 +1865,781 @@ fn update_buildpack_contents_with_new_version(
     let mut empty_orders = ArrayOfTables::default();
     let mut empty_groups = ArrayOfTables::default();
 
-    let orders = buildpack_file
-        .document
-        .get_mut("order")
-        .and_then(toml_edit::Item::as_array_of_tables_mut)
-        .unwrap_or(&mut empty_orders);
-    for order in orders.iter_mut() {
-        let groups = order
-            .get_mut("group")
-            .and_then(toml_edit::Item::as_array_of_tables_mut)
-            .unwrap_or(&mut empty_groups);
-        for group in groups.iter_mut() {
-            let buildpack_id = get_group_buildpack_id(group, &buildpack_file.path)?;
-            if updated_dependencies.contains(&buildpack_id) {
-                group.insert("version", value(next_version.to_string()));
+    let orders = buildpack_file
+        .document
+        .get_mut("order")
+        .and_then(toml_edit::Item::as_array_of_tables_mut)
+        .unwrap_or(&mut empty_orders);
+    for order in orders.iter_mut() {
+        let groups = order
+            .get_mut("group")
+            .and_then(toml_edit::Item::as_array_of_tables_mut)
+            .unwrap_or(&mut empty_groups);
+        for group in groups.iter_mut() {
+            let buildpack_id = get_group_buildpack_id(group, &buildpack_file.path)?;
+            if updated_dependencies.contains(&buildpack_id) {
+                group.insert("version", value(next_version.to_string()));
+            }
+        }
+    }
+
+    Ok(buildpack_file.document.to_string())
+}
+
+// Audits can trace a release entry back to the tool version and workflow run that produced it.
+fn released_by(released_by_comment: bool, run_id: Option<String>) -> Option<String> {
+    if !released_by_comment {
+        return None;
+    }
+    let tool = format!("actions v{}", env!("CARGO_PKG_VERSION"));
+    match run_id {
+        Some(run_id) => Some(format!("{tool} run {run_id}")),
+        None => Some(tool),
+    }
+}
+
+fn record_released_by(changelog: &mut Changelog, release_changes: &ReleaseChanges) {
+    if let Some(entry) = changelog
+        .releases
+        .get_mut(&release_changes.next_version.to_string())
+    {
+        entry.released_by = release_changes.released_by.map(ToString::to_string);
+    }
+}
+
+fn promote_changelog_unreleased_to_version(
+    changelog: &Changelog,
+    version: &Version,
+    date: &DateTime<Utc>,
+    updated_dependencies: &HashSet<BuildpackId>,
+    dependency_change_entry: &DependencyChangeEntry,
+) -> Changelog {
+    let updated_dependencies_text = if updated_dependencies.is_empty() {
+        None
+    } else {
+        let mut updated_dependencies_bullet_points = updated_dependencies
+            .iter()
+            .map(|id| format!("- {}", dependency_change_entry.render(id, version)))
+            .collect::<Vec<_>>();
+        updated_dependencies_bullet_points.sort();
+        Some(updated_dependencies_bullet_points.join("\n"))
+    };
+
+    let changes_with_dependencies = (&changelog.unreleased, &updated_dependencies_text);
+
+    let body = if let (Some(changes), Some(dependencies)) = changes_with_dependencies {
+        append_group_entries(
+            changes,
+            &dependency_change_entry.group.to_string(),
+            dependencies,
+        )
+    } else if let (Some(changes), None) = changes_with_dependencies {
+        changes.clone()
+    } else if let (None, Some(dependencies)) = changes_with_dependencies {
+        format!("### {}\n\n{dependencies}", dependency_change_entry.group)
+    } else {
+        String::new()
+    };
+
+    let body = normalize_change_groups(&body);
+    let body = if body.is_empty() {
+        "- No changes.".to_string()
+    } else {
+        body
+    };
+
+    let new_release_entry = ReleaseEntry {
+        version: version.clone(),
+        date: *date,
+        body,
+        released_by: None,
+    };
+
+    let mut releases = IndexMap::from([(version.to_string(), new_release_entry)]);
+    for (id, entry) in &changelog.releases {
+        releases.insert(id.clone(), entry.clone());
+    }
+    Changelog {
+        protected_preamble: changelog.protected_preamble.clone(),
+        unreleased: None,
+        releases,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::changelog::{Changelog, LinkTemplates, ReleaseEntry};
+    use crate::commands::prepare_release::command::{
+        check_buildpack_versions, coerce_version, find_future_version_references, get_buildpack_id,
+        get_fixed_version, get_next_version, github_repository, parse_added_change,
+        parse_link_template, promote_changelog_unreleased_to_version, read_changelog_file,
+        read_released_buildpacks, read_security_fixes, released_by, replace_marked_versions,
+        resolve_bump_coordinate, resolve_link_templates, resolve_next_version,
+        resolve_release_date, sync_cargo_versions, update_buildpack_contents_with_new_version,
+        update_package_dependencies, update_root_changelog, AddedChange, BuildpackFile,
+        BumpCoordinate, ChangeGroup, ChangelogFile, DependencyChangeEntry, ReleaseChanges,
+        ReleaseVersion, ReleaseWriter, ReleasedBuildpack,
+    };
+    use crate::commands::prepare_release::errors::Error;
+    use chrono::{NaiveDate, TimeZone, Utc};
+    use indexmap::IndexMap;
+    use libcnb_data::buildpack_id;
+    use semver::Version;
+    use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+    use std::path::PathBuf;
+    use std::str::FromStr;
+    use toml_edit::DocumentMut;
+
+    #[test]
+    fn test_resolve_bump_coordinate() {
+        let changelog_file = |unreleased: Option<&str>| ChangelogFile {
+            path: PathBuf::from("CHANGELOG.md"),
+            changelog: Changelog {
+                protected_preamble: None,
+                unreleased: unreleased.map(ToString::to_string),
+                releases: IndexMap::new(),
+            },
+        };
+
+        assert_eq!(
+            resolve_bump_coordinate(
+                &BumpCoordinate::Patch,
+                &[changelog_file(Some("### Removed\n\n- Foo."))]
+            ),
+            BumpCoordinate::Patch
+        );
+        assert_eq!(
+            resolve_bump_coordinate(
+                &BumpCoordinate::Auto,
+                &[
+                    changelog_file(Some("### Fixed\n\n- Foo.")),
+                    changelog_file(Some("### Removed\n\n- Bar."))
+                ]
+            ),
+            BumpCoordinate::Major
+        );
+        assert_eq!(
+            resolve_bump_coordinate(
+                &BumpCoordinate::Auto,
+                &[
+                    changelog_file(Some("### Added\n\n- Foo.\n\n### Removed\n")),
+                    changelog_file(None)
+                ]
+            ),
+            BumpCoordinate::Minor
+        );
+        assert_eq!(
+            resolve_bump_coordinate(
+                &BumpCoordinate::Auto,
+                &[changelog_file(Some("### Fixed\n\n- Foo."))]
+            ),
+            BumpCoordinate::Patch
+        );
+        assert_eq!(
+            resolve_bump_coordinate(&BumpCoordinate::Auto, &[changelog_file(None)]),
+            BumpCoordinate::Patch
+        );
+    }
+
+    #[test]
+    fn test_release_writer_dry_run() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("buildpack.toml");
+        std::fs::write(&path, "[buildpack]\nid = \"a\"\nversion = \"0.0.1\"\n").unwrap();
+
+        let mut writer = ReleaseWriter::new(dir.path(), true);
+        writer
+            .write(&path, "[buildpack]\nid = \"a\"\nversion = \"0.0.2\"\n")
+            .unwrap();
+
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            "[buildpack]\nid = \"a\"\nversion = \"0.0.1\"\n"
+        );
+        assert_eq!(
+            writer.diffs,
+            vec!["--- a/buildpack.toml\n+++ b/buildpack.toml\n@@ -1,3 +1,3 @@\n [buildpack]\n id = \"a\"\n-version = \"0.0.1\"\n+version = \"0.0.2\"\n"]
+        );
+    }
+
+    #[test]
+    fn test_update_root_changelog() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("CHANGELOG.md");
+        std::fs::write(
+            &path,
+            "# Changelog\n\n## [Unreleased]\n\n- Updated the README.\n\n## [1.0.0] - 2024-05-01\n\n- Initial release.\n",
+        )
+        .unwrap();
+        let root_changelog_file = read_changelog_file(path.clone()).unwrap();
+
+        let current_version = Version::new(1, 0, 0);
+        let next_version = Version::new(1, 1, 0);
+        let release_date = Utc.with_ymd_and_hms(2024, 6, 1, 0, 0, 0).unwrap();
+        let release_changes = ReleaseChanges {
+            current_version: &current_version,
+            next_version: &next_version,
+            updated_buildpack_ids: &HashSet::new(),
+            released_repositories: &HashSet::new(),
+            dependency_change_entry: &DependencyChangeEntry::default(),
+            repository_url: "https://github.com/heroku/buildpacks-nodejs",
+            link_templates: &LinkTemplates::default(),
+            declarations_starting_version: None,
+            release_tags: None,
+            release_date: &release_date,
+            released_by: None,
+        };
+
+        let mut writer = ReleaseWriter::new(dir.path(), false);
+        update_root_changelog(
+            &root_changelog_file,
+            &[
+                (
+                    buildpack_id!("heroku/nodejs-npm"),
+                    "- No changes.".to_string(),
+                ),
+                (
+                    buildpack_id!("heroku/nodejs-engine"),
+                    "### Added\n\n- Added Node.js 22.".to_string(),
+                ),
+            ],
+            &release_changes,
+            &mut writer,
+        )
+        .unwrap();
+        writer.commit().unwrap();
+
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            r"# Changelog
+
+All notable changes to this project will be documented in this file.
+
+The format is based on [Keep a Changelog](https://keepachangelog.com/en/1.1.0/),
+and this project adheres to [Semantic Versioning](https://semver.org/spec/v2.0.0.html).
+
+## [Unreleased]
+
+## [1.1.0] - 2024-06-01
+
+- Updated the README.
+
+### heroku/nodejs-engine
+
+#### Added
+
+- Added Node.js 22.
+
+### heroku/nodejs-npm
+
+- No changes.
+
+## [1.0.0] - 2024-05-01
+
+- Initial release.
+
+[unreleased]: https://github.com/heroku/buildpacks-nodejs/compare/v1.1.0...HEAD
+[1.1.0]: https://github.com/heroku/buildpacks-nodejs/compare/v1.0.0...v1.1.0
+[1.0.0]: https://github.com/heroku/buildpacks-nodejs/releases/tag/v1.0.0
+"
+        );
+
+        match update_root_changelog(
+            &read_changelog_file(path.clone()).unwrap(),
+            &[],
+            &release_changes,
+            &mut writer,
+        )
+        .unwrap_err()
+        {
+            Error::ReleaseEntryAlreadyExists(error_path, _) => assert_eq!(error_path, path),
+            e => panic!("Unexpected error: {e}"),
+        }
+    }
+
+    #[test]
+    fn test_release_writer_commit() {
+        let dir = tempfile::tempdir().unwrap();
+        let buildpack_path = dir.path().join("buildpack.toml");
+        let changelog_path = dir.path().join("CHANGELOG.md");
+        std::fs::write(&buildpack_path, "[buildpack]\nversion = \"0.0.1\"\n").unwrap();
+        std::fs::write(&changelog_path, "## [Unreleased]\n").unwrap();
+
+        let mut writer = ReleaseWriter::new(dir.path(), false);
+        writer
+            .write(&buildpack_path, "[buildpack]\nversion = \"0.0.2\"\n")
+            .unwrap();
+        writer
+            .write(
+                &changelog_path,
+                "## [Unreleased]\n\n## [0.0.2] - 2024-06-01\n",
+            )
+            .unwrap();
+
+        // nothing is written until the release is committed
+        assert_eq!(
+            std::fs::read_to_string(&buildpack_path).unwrap(),
+            "[buildpack]\nversion = \"0.0.1\"\n"
+        );
+
+        writer.commit().unwrap();
+        assert_eq!(
+            std::fs::read_to_string(&buildpack_path).unwrap(),
+            "[buildpack]\nversion = \"0.0.2\"\n"
+        );
+        assert_eq!(
+            std::fs::read_to_string(&changelog_path).unwrap(),
+            "## [Unreleased]\n\n## [0.0.2] - 2024-06-01\n"
+        );
+        assert_eq!(
+            writer.written,
+            BTreeSet::from([buildpack_path.clone(), changelog_path.clone()])
+        );
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 2);
+    }
+
+    #[test]
+    fn test_release_writer_commit_with_a_shared_changelog() {
+        let dir = tempfile::tempdir().unwrap();
+        let changelog_path = dir.path().join("CHANGELOG.md");
+        std::fs::create_dir_all(dir.path().join("buildpacks/a")).unwrap();
+        std::fs::create_dir_all(dir.path().join("buildpacks/b")).unwrap();
+        std::fs::write(&changelog_path, "## [Unreleased]\n").unwrap();
+
+        let mut writer = ReleaseWriter::new(dir.path(), false);
+        for buildpack in ["a", "b"] {
+            writer
+                .write(
+                    &dir.path()
+                        .join(format!("buildpacks/{buildpack}/../../CHANGELOG.md")),
+                    "## [Unreleased]\n\n## [0.0.2] - 2024-06-01\n",
+                )
+                .unwrap();
+        }
+        assert_eq!(writer.staged.len(), 1);
+
+        writer.commit().unwrap();
+        assert_eq!(
+            std::fs::read_to_string(&changelog_path).unwrap(),
+            "## [Unreleased]\n\n## [0.0.2] - 2024-06-01\n"
+        );
+        assert_eq!(writer.written, BTreeSet::from([changelog_path]));
+    }
+
+    #[test]
+    fn test_release_writer_commit_writes_nothing_when_verification_fails() {
+        let dir = tempfile::tempdir().unwrap();
+        let buildpack_path = dir.path().join("buildpack.toml");
+        let package_path = dir.path().join("package.toml");
+        std::fs::write(&buildpack_path, "[buildpack]\nversion = \"0.0.1\"\n").unwrap();
+        std::fs::write(&package_path, "[buildpack]\nuri = \".\"\n").unwrap();
+
+        let mut writer = ReleaseWriter::new(dir.path(), false);
+        writer
+            .write(&buildpack_path, "[buildpack]\nversion = \"0.0.2\"\n")
+            .unwrap();
+        writer.write(&package_path, "[buildpack\n").unwrap();
+        match writer.commit().unwrap_err() {
+            Error::InvalidReleaseFile(path, _) => assert_eq!(path, package_path),
+            e => panic!("Unexpected error: {e}"),
+        }
+
+        let mut writer = ReleaseWriter::new(dir.path(), false);
+        writer
+            .write(&buildpack_path, "[buildpack]\nversion = \"0.0.2\"\n")
+            .unwrap();
+        std::fs::write(&buildpack_path, "[buildpack]\nversion = \"0.0.3\"\n").unwrap();
+        match writer.commit().unwrap_err() {
+            Error::ReleaseFileModified(path) => assert_eq!(path, buildpack_path),
+            e => panic!("Unexpected error: {e}"),
+        }
+
+        assert_eq!(
+            std::fs::read_to_string(&package_path).unwrap(),
+            "[buildpack]\nuri = \".\"\n"
+        );
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 2);
+    }
+
+    #[test]
+    fn test_sync_cargo_versions() {
+        let dir = tempfile::tempdir().unwrap();
+        let inherited_dir = dir.path().join("buildpacks/inherited");
+        let pinned_dir = dir.path().join("buildpacks/pinned");
+        std::fs::create_dir_all(&inherited_dir).unwrap();
+        std::fs::create_dir_all(&pinned_dir).unwrap();
+        std::fs::write(
+            dir.path().join("Cargo.toml"),
+            "[workspace]\nmembers = [\"buildpacks/*\"]\n\n[workspace.package]\nversion = \"1.0.0\" # release\n",
+        )
+        .unwrap();
+        std::fs::write(
+            inherited_dir.join("Cargo.toml"),
+            "[package]\nname = \"inherited\"\nversion.workspace = true\n",
+        )
+        .unwrap();
+        std::fs::write(
+            pinned_dir.join("Cargo.toml"),
+            "[package]\nname = \"pinned\"\nversion = \"0.0.0\"\n",
+        )
+        .unwrap();
+
+        let mut writer = ReleaseWriter::new(dir.path(), false);
+        sync_cargo_versions(
+            &[inherited_dir.clone(), pinned_dir.clone()],
+            dir.path(),
+            &Version::new(1, 1, 0),
+            &mut writer,
+        )
+        .unwrap();
+        writer.commit().unwrap();
+
+        assert_eq!(
+            std::fs::read_to_string(dir.path().join("Cargo.toml")).unwrap(),
+            "[workspace]\nmembers = [\"buildpacks/*\"]\n\n[workspace.package]\nversion = \"1.1.0\" # release\n"
+        );
+        assert_eq!(
+            std::fs::read_to_string(inherited_dir.join("Cargo.toml")).unwrap(),
+            "[package]\nname = \"inherited\"\nversion.workspace = true\n"
+        );
+        assert_eq!(
+            std::fs::read_to_string(pinned_dir.join("Cargo.toml")).unwrap(),
+            "[package]\nname = \"pinned\"\nversion = \"1.1.0\"\n"
+        );
+    }
+
+    #[test]
+    fn test_sync_cargo_versions_without_workspace() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("Cargo.toml"),
+            "[package]\nname = \"inherited\"\nversion.workspace = true\n",
+        )
+        .unwrap();
+
+        let mut writer = ReleaseWriter::new(dir.path(), false);
+        match sync_cargo_versions(
+            &[dir.path().to_path_buf()],
+            dir.path(),
+            &Version::new(1, 1, 0),
+            &mut writer,
+        )
+        .unwrap_err()
+        {
+            Error::MissingCargoWorkspace(path) => {
+                assert_eq!(path, dir.path().join("Cargo.toml"));
+            }
+            e => panic!("Unexpected error: {e}"),
+        }
+    }
+
+    #[test]
+    fn test_update_package_dependencies() {
+        let mut document = DocumentMut::from_str(
+            r#"
+[buildpack]
+uri = "."
+
+[[dependencies]]
+uri = "docker://docker.io/heroku/buildpack-nodejs-engine:1.2.3" # engine
+
+[[dependencies]]
+uri = "docker.io/heroku/buildpack-nodejs-npm:1.2.3"
+
+[[dependencies]]
+uri = "docker://docker.io/heroku/buildpack-procfile:3.0.0"
+
+[[dependencies]]
+uri = "docker://docker.io/heroku/buildpack-nodejs-yarn@sha256:abc"
+
+[[dependencies]]
+uri = "libcnb:heroku/nodejs-corepack"
+"#,
+        )
+        .unwrap();
+
+        let released_repositories = HashSet::from([
+            "docker.io/heroku/buildpack-nodejs-engine".to_string(),
+            "docker.io/heroku/buildpack-nodejs-npm".to_string(),
+            "docker.io/heroku/buildpack-nodejs-yarn".to_string(),
+        ]);
+
+        assert!(update_package_dependencies(
+            &mut document,
+            &released_repositories,
+            &Version::new(1, 3, 0)
+        ));
+        assert_eq!(
+            document.to_string(),
+            r#"
+[buildpack]
+uri = "."
+
+[[dependencies]]
+uri = "docker://docker.io/heroku/buildpack-nodejs-engine:1.3.0" # engine
+
+[[dependencies]]
+uri = "docker.io/heroku/buildpack-nodejs-npm:1.3.0"
+
+[[dependencies]]
+uri = "docker://docker.io/heroku/buildpack-procfile:3.0.0"
+
+[[dependencies]]
+uri = "docker://docker.io/heroku/buildpack-nodejs-yarn@sha256:abc"
+
+[[dependencies]]
+uri = "libcnb:heroku/nodejs-corepack"
+"#
+        );
+        assert!(!update_package_dependencies(
+            &mut document,
+            &released_repositories,
+            &Version::new(1, 3, 0)
+        ));
+    }
+
+    #[test]
+    fn test_find_future_version_references() {
+        assert_eq!(
+            find_future_version_references(
+                "- Deprecated `foo`, it will be removed in 4.0.0.\n- Updated Node.js to 22.1.0.\n- Supported until version 3.0.1.\n- Added in v3.1.0.",
+                &Version::new(3, 1, 0)
+            ),
+            vec!["in 4.0.0"]
+        );
+    }
+
+    #[test]
+    fn test_resolve_next_version_with_explicit_version() {
+        let current_version = Version::new(3, 0, 0);
+
+        assert_eq!(
+            resolve_next_version(
+                &current_version,
+                &ReleaseVersion::Explicit(Version::new(3, 1, 0))
+            )
+            .unwrap(),
+            Version::new(3, 1, 0)
+        );
+
+        match resolve_next_version(
+            &current_version,
+            &ReleaseVersion::Explicit(Version::new(3, 0, 0)),
+        )
+        .unwrap_err()
+        {
+            Error::VersionNotGreater(version, current_version) => {
+                assert_eq!(version.to_string(), "3.0.0");
+                assert_eq!(current_version.to_string(), "3.0.0");
             }
+            e => panic!("Unexpected error: {e}"),
         }
     }
 
-    Ok(buildpack_file.document.to_string())
-}
+    #[test]
+    fn test_get_next_version() {
+        let current_version = Version::new(1, 2, 3);
+        assert_eq!(
+            get_next_version(&current_version, &BumpCoordinate::Minor),
+            Version::new(1, 3, 0)
+        );
+        assert_eq!(
+            get_next_version(&current_version, &BumpCoordinate::None),
+            current_version
+        );
+    }
 
-fn promote_changelog_unreleased_to_version(
-    changelog: &Changelog,
-    version: &BuildpackVersion,
-    date: &DateTime<Utc>,
-    updated_dependencies: &HashSet<BuildpackId>,
-) -> Changelog {
-    let updated_dependencies_text = if updated_dependencies.is_empty() {
-        None
-    } else {
-        let mut updated_dependencies_bullet_points = updated_dependencies
-            .iter()
-            .map(|id| format!("- Updated `{id}` to `{version}`."))
-            .collect::<Vec<_>>();
-        updated_dependencies_bullet_points.sort();
-        Some(updated_dependencies_bullet_points.join("\n"))
-    };
+    #[test]
+    fn test_get_next_version_from_prerelease() {
+        for (current_version, bump, next_version) in [
+            ("2.0.0-rc.3", BumpCoordinate::Major, "2.0.0"),
+            ("2.0.0-rc.3", BumpCoordinate::Minor, "2.0.0"),
+            ("2.0.0-rc.3", BumpCoordinate::Patch, "2.0.0"),
+            ("2.1.0-beta.1", BumpCoordinate::Major, "3.0.0"),
+            ("2.1.0-beta.1", BumpCoordinate::Minor, "2.1.0"),
+            ("2.1.1-alpha", BumpCoordinate::Minor, "2.2.0"),
+            ("2.1.1-alpha", BumpCoordinate::Patch, "2.1.1"),
+            ("2.1.1+build.5", BumpCoordinate::Patch, "2.1.2"),
+            ("2.0.0-rc.3", BumpCoordinate::None, "2.0.0-rc.3"),
+        ] {
+            assert_eq!(
+                get_next_version(&Version::parse(current_version).unwrap(), &bump).to_string(),
+                next_version,
+                "{current_version} with {bump:?}"
+            );
+        }
+    }
 
-    let changes_with_dependencies = (&changelog.unreleased, &updated_dependencies_text);
+    #[test]
+    fn test_parse_added_change() {
+        assert_eq!(
+            parse_added_change("fixed: Corrected PATH handling").unwrap(),
+            AddedChange {
+                group: ChangeGroup::Fixed,
+                text: "Corrected PATH handling".to_string()
+            }
+        );
+        assert_eq!(
+            parse_added_change("Security:Updated base image: heroku-24").unwrap(),
+            AddedChange {
+                group: ChangeGroup::Security,
+                text: "Updated base image: heroku-24".to_string()
+            }
+        );
+        assert!(parse_added_change("Corrected PATH handling").is_err());
+        assert!(parse_added_change("improved: Faster builds").is_err());
+        assert!(parse_added_change("fixed: ").is_err());
+    }
 
-    let body = if let (Some(changes), Some(dependencies)) = changes_with_dependencies {
-        merge_existing_changelog_entries_with_dependency_changes(changes, dependencies)
-    } else if let (Some(changes), None) = changes_with_dependencies {
-        changes.clone()
-    } else if let (None, Some(dependencies)) = changes_with_dependencies {
-        format!("### Changed\n\n{dependencies}")
-    } else {
-        "- No changes.".to_string()
-    };
+    #[test]
+    fn test_resolve_link_templates() {
+        let link_templates = resolve_link_templates(&[
+            parse_link_template("compare={repository}/-/compare/v{previous_version}...v{version}")
+                .unwrap(),
+            parse_link_template("Tag = {repository}/-/tags/v{version}").unwrap(),
+        ]);
+        assert_eq!(
+            link_templates,
+            LinkTemplates {
+                compare: "{repository}/-/compare/v{previous_version}...v{version}".to_string(),
+                tag: "{repository}/-/tags/v{version}".to_string(),
+                ..LinkTemplates::default()
+            }
+        );
+        assert!(parse_link_template("{repository}/-/tags/v{version}").is_err());
+        assert!(parse_link_template("release={repository}/-/releases/v{version}").is_err());
+        assert!(parse_link_template("tag=").is_err());
+    }
 
-    let new_release_entry = ReleaseEntry {
-        version: Version {
-            major: version.major,
-            minor: version.minor,
-            patch: version.patch,
-            pre: Prerelease::default(),
-            build: BuildMetadata::default(),
-        },
-        date: *date,
-        body,
-    };
+    #[test]
+    fn test_resolve_release_date() {
+        let release_date = NaiveDate::from_ymd_opt(2024, 6, 1).unwrap();
+        assert_eq!(
+            resolve_release_date(Some(release_date), Some("0".to_string())).unwrap(),
+            Utc.with_ymd_and_hms(2024, 6, 1, 0, 0, 0).unwrap()
+        );
+        assert_eq!(
+            resolve_release_date(None, Some("1717200000".to_string())).unwrap(),
+            Utc.with_ymd_and_hms(2024, 6, 1, 0, 0, 0).unwrap()
+        );
+        match resolve_release_date(None, Some("yesterday".to_string())).unwrap_err() {
+            Error::InvalidSourceDateEpoch(value) => assert_eq!(value, "yesterday"),
+            e => panic!("Unexpected error: {e}"),
+        }
+    }
 
-    let mut releases = IndexMap::from([(version.to_string(), new_release_entry)]);
-    for (id, entry) in &changelog.releases {
-        releases.insert(id.clone(), entry.clone());
+    #[test]
+    fn test_released_by() {
+        let version = env!("CARGO_PKG_VERSION");
+        assert_eq!(
+            released_by(true, Some("123456".to_string())),
+            Some(format!("actions v{version} run 123456"))
+        );
+        assert_eq!(released_by(true, None), Some(format!("actions v{version}")));
+        assert_eq!(released_by(false, Some("123456".to_string())), None);
     }
-    Changelog {
-        unreleased: None,
-        releases,
+
+    #[test]
+    fn test_github_repository() {
+        assert_eq!(
+            github_repository("https://github.com/heroku/buildpacks-nodejs"),
+            Some("heroku/buildpacks-nodejs".to_string())
+        );
+        assert_eq!(
+            github_repository("https://github.com/heroku/buildpacks-nodejs.git/"),
+            Some("heroku/buildpacks-nodejs".to_string())
+        );
+        assert_eq!(github_repository("https://github.com/heroku"), None);
     }
-}
 
-fn merge_existing_changelog_entries_with_dependency_changes(
-    changelog_entries: &str,
-    updated_dependencies: &str,
-) -> String {
-    if changelog_entries.contains("### Changed") {
-        changelog_entries
-            .split("### ")
-            .map(|entry| {
-                if entry.starts_with("Changed") {
-                    format!("{}\n{}\n\n", entry.trim_end(), updated_dependencies)
-                } else {
-                    entry.to_string()
+    #[test]
+    fn test_read_released_buildpacks() {
+        let buildpack_files = vec![create_buildpack_file_with_name(
+            "/buildpacks/a/buildpack.toml",
+            "[buildpack]\nid = \"a\"\nversion = \"1.0.0\"\n",
+        )];
+        let changelog_files = vec![ChangelogFile {
+            path: PathBuf::from("/CHANGELOG.md"),
+            changelog: Changelog {
+                protected_preamble: None,
+                unreleased: None,
+                releases: IndexMap::new(),
+            },
+        }];
+
+        let released_buildpacks = read_released_buildpacks(
+            &[buildpack_id!("a")],
+            &buildpack_files,
+            &changelog_files,
+            &Version::new(1, 0, 0),
+            &Version::new(1, 1, 0),
+        );
+
+        assert_eq!(
+            released_buildpacks,
+            BTreeMap::from([(
+                "a".to_string(),
+                ReleasedBuildpack {
+                    path: PathBuf::from("/buildpacks/a"),
+                    from_version: "1.0.0".to_string(),
+                    to_version: "1.1.0".to_string(),
+                    changelog_path: PathBuf::from("/CHANGELOG.md"),
                 }
-            })
-            .collect::<Vec<_>>()
-            .join("### ")
-    } else {
-        format!(
-            "{}\n\n### Changed\n\n{}",
-            changelog_entries.trim_end(),
-            updated_dependencies
-        )
+            )])
+        );
+        assert_eq!(
+            serde_json::to_string(&released_buildpacks).unwrap(),
+            r#"{"a":{"path":"/buildpacks/a","from_version":"1.0.0","to_version":"1.1.0","changelog_path":"/CHANGELOG.md"}}"#
+        );
     }
-}
 
-#[cfg(test)]
-mod test {
-    use crate::changelog::{Changelog, ReleaseEntry};
-    use crate::commands::prepare_release::command::{
-        get_fixed_version, promote_changelog_unreleased_to_version,
-        update_buildpack_contents_with_new_version, BuildpackFile,
-    };
-    use crate::commands::prepare_release::errors::Error;
-    use chrono::{TimeZone, Utc};
-    use indexmap::IndexMap;
-    use libcnb_data::buildpack::BuildpackVersion;
-    use libcnb_data::buildpack_id;
-    use semver::Version;
-    use std::collections::{HashMap, HashSet};
-    use std::path::PathBuf;
-    use std::str::FromStr;
-    use toml_edit::DocumentMut;
+    #[test]
+    fn test_read_security_fixes() {
+        let changelog_file = |unreleased: Option<&str>, releases: &[(&str, &str)]| ChangelogFile {
+            path: PathBuf::from("/CHANGELOG.md"),
+            changelog: Changelog {
+                protected_preamble: None,
+                unreleased: unreleased.map(ToString::to_string),
+                releases: releases
+                    .iter()
+                    .map(|(version, body)| {
+                        (
+                            (*version).to_string(),
+                            ReleaseEntry {
+                                version: Version::parse(version).unwrap(),
+                                date: Utc.with_ymd_and_hms(2023, 1, 1, 0, 0, 0).unwrap(),
+                                body: (*body).to_string(),
+                                released_by: None,
+                            },
+                        )
+                    })
+                    .collect(),
+            },
+        };
+
+        let security_fixes = read_security_fixes(
+            &[buildpack_id!("a"), buildpack_id!("b"), buildpack_id!("c")],
+            &[
+                changelog_file(
+                    Some("### Security\n\n- Updated OpenSSL.\n\n### Fixed\n\n- Foo."),
+                    &[],
+                ),
+                changelog_file(Some("### Fixed\n\n- Bar."), &[]),
+                changelog_file(
+                    Some("### Security\n\n- Patched CVE-2024-1234."),
+                    &[("1.0.0", "### Security\n\n- Patched CVE-2023-1234.")],
+                ),
+            ],
+        );
+
+        assert_eq!(
+            security_fixes,
+            BTreeMap::from([
+                ("a".to_string(), vec!["Updated OpenSSL.".to_string()]),
+                ("c".to_string(), vec!["Patched CVE-2024-1234.".to_string()]),
+            ])
+        );
+    }
 
     #[test]
     fn test_get_fixed_version() {
@@ -420,11 +2659,7 @@ version = "0.0.0"
         );
         assert_eq!(
             get_fixed_version(&vec![buildpack_a, buildpack_b]).unwrap(),
-            BuildpackVersion {
-                major: 0,
-                minor: 0,
-                patch: 0
-            }
+            Version::new(0, 0, 0)
         );
     }
 
@@ -448,22 +2683,8 @@ version = "0.0.1"
             Error::NotAllVersionsMatch(version_map) => {
                 assert_eq!(
                     HashMap::from([
-                        (
-                            PathBuf::from("/a/buildpack.toml"),
-                            BuildpackVersion {
-                                major: 0,
-                                minor: 0,
-                                patch: 0
-                            }
-                        ),
-                        (
-                            PathBuf::from("/b/buildpack.toml"),
-                            BuildpackVersion {
-                                major: 0,
-                                minor: 0,
-                                patch: 1
-                            }
-                        )
+                        (PathBuf::from("/a/buildpack.toml"), Version::new(0, 0, 0)),
+                        (PathBuf::from("/b/buildpack.toml"), Version::new(0, 0, 1))
                     ]),
                     version_map
                 );
@@ -472,6 +2693,61 @@ version = "0.0.1"
         };
     }
 
+    #[test]
+    fn test_check_buildpack_versions_reports_unsupported_versions() {
+        let mut buildpack_files = vec![
+            create_buildpack_file_with_name(
+                "/a/buildpack.toml",
+                r#"[buildpack]
+id = "a"
+version = "1.2.3.4"
+"#,
+            ),
+            create_buildpack_file_with_name(
+                "/b/buildpack.toml",
+                r#"[buildpack]
+id = "b"
+version = "1.2.3"
+"#,
+            ),
+        ];
+        match check_buildpack_versions(&mut buildpack_files, false).unwrap_err() {
+            Error::UnsupportedBuildpackVersions(unsupported_versions) => {
+                assert_eq!(
+                    unsupported_versions,
+                    vec![(PathBuf::from("/a/buildpack.toml"), "1.2.3.4".to_string())]
+                );
+            }
+            _ => panic!("Expected error UnsupportedBuildpackVersions"),
+        }
+    }
+
+    #[test]
+    fn test_check_buildpack_versions_with_coercion() {
+        let mut buildpack_files = vec![create_buildpack_file(
+            r#"[buildpack]
+id = "a"
+version = "1.2.3.4"
+"#,
+        )];
+        check_buildpack_versions(&mut buildpack_files, true).unwrap();
+        assert_eq!(
+            buildpack_files[0].document.to_string(),
+            r#"[buildpack]
+id = "a"
+version = "1.2.3"
+"#
+        );
+    }
+
+    #[test]
+    fn test_coerce_version() {
+        assert_eq!(coerce_version("1.2.3.4"), Some(Version::new(1, 2, 3)));
+        assert_eq!(coerce_version("1.2"), Some(Version::new(1, 2, 0)));
+        assert_eq!(coerce_version("01.02.03"), Some(Version::new(1, 2, 3)));
+        assert_eq!(coerce_version("1.2.x"), None);
+    }
+
     #[test]
     fn test_update_buildpack_contents_with_new_version() {
         let toml = r#"[buildpack]
@@ -480,11 +2756,7 @@ version = "0.0.0"
             "#;
 
         let mut buildpack_file = create_buildpack_file(toml);
-        let next_version = BuildpackVersion {
-            major: 1,
-            minor: 0,
-            patch: 0,
-        };
+        let next_version = Version::new(1, 0, 0);
         let updated_dependencies = HashSet::new();
         assert_eq!(
             update_buildpack_contents_with_new_version(
@@ -500,6 +2772,33 @@ version = "1.0.0"
         );
     }
 
+    #[test]
+    fn test_update_extension_contents_with_new_version() {
+        let mut extension_file = create_buildpack_file_with_name(
+            "/path/to/test/extension.toml",
+            r#"[extension]
+id = "heroku/apt"
+version = "0.0.0"
+"#,
+        );
+        assert_eq!(
+            get_buildpack_id(&extension_file).unwrap(),
+            buildpack_id!("heroku/apt")
+        );
+        assert_eq!(
+            update_buildpack_contents_with_new_version(
+                &mut extension_file,
+                &Version::new(1, 0, 0),
+                &HashSet::new()
+            )
+            .unwrap(),
+            r#"[extension]
+id = "heroku/apt"
+version = "1.0.0"
+"#
+        );
+    }
+
     #[test]
     fn test_update_buildpack_contents_with_new_version_and_order_groups_are_present() {
         let toml = r#"[buildpack]
@@ -522,11 +2821,7 @@ optional = true
             "#;
 
         let mut buildpack_file = create_buildpack_file(toml);
-        let next_version = BuildpackVersion {
-            major: 0,
-            minor: 0,
-            patch: 10,
-        };
+        let next_version = Version::new(0, 0, 10);
         let updated_dependencies = HashSet::from([buildpack_id!("dep-a"), buildpack_id!("dep-b")]);
         assert_eq!(
             update_buildpack_contents_with_new_version(
@@ -561,16 +2856,19 @@ optional = true
         let release_entry_0_8_16 = ReleaseEntry {
             version: "0.8.16".parse::<Version>().unwrap(),
             date: Utc.with_ymd_and_hms(2023, 2, 27, 0, 0, 0).unwrap(),
-            body: "- Added node version 19.7.0, 19.6.1, 14.21.3, 16.19.1, 18.14.1, 18.14.2.\n- Added node version 18.14.0, 19.6.0.".to_string()
+            body: "- Added node version 19.7.0, 19.6.1, 14.21.3, 16.19.1, 18.14.1, 18.14.2.\n- Added node version 18.14.0, 19.6.0.".to_string(),
+            released_by: None,
         };
 
         let release_entry_0_8_15 = ReleaseEntry {
             version: "0.8.15".parse::<Version>().unwrap(),
             date: Utc.with_ymd_and_hms(2023, 2, 27, 0, 0, 0).unwrap(),
-            body: "- `name` is no longer a required field in package.json. ([#447](https://github.com/heroku/buildpacks-nodejs/pull/447))\n- Added node version 19.5.0.".to_string()
+            body: "- `name` is no longer a required field in package.json. ([#447](https://github.com/heroku/buildpacks-nodejs/pull/447))\n- Added node version 19.5.0.".to_string(),
+            released_by: None,
         };
 
         let changelog = Changelog {
+            protected_preamble: None,
             unreleased: Some(
                 "- Added node version 18.15.0.\n- Added yarn version 4.0.0-rc.2".to_string(),
             ),
@@ -594,11 +2892,7 @@ optional = true
             Some(&release_entry_0_8_15)
         );
 
-        let next_version = BuildpackVersion {
-            major: 0,
-            minor: 8,
-            patch: 17,
-        };
+        let next_version = Version::new(0, 8, 17);
         let date = Utc.with_ymd_and_hms(2023, 6, 16, 0, 0, 0).unwrap();
         let updated_dependencies = HashSet::new();
         let changelog = promote_changelog_unreleased_to_version(
@@ -606,6 +2900,7 @@ optional = true
             &next_version,
             &date,
             &updated_dependencies,
+            &DependencyChangeEntry::default(),
         );
 
         assert_eq!(changelog.unreleased, None);
@@ -614,7 +2909,8 @@ optional = true
             Some(&ReleaseEntry {
                 version: "0.8.17".parse::<Version>().unwrap(),
                 date,
-                body: "- Added node version 18.15.0.\n- Added yarn version 4.0.0-rc.2".to_string()
+                body: "- Added node version 18.15.0.\n- Added yarn version 4.0.0-rc.2".to_string(),
+                released_by: None,
             })
         );
         assert_eq!(
@@ -630,6 +2926,7 @@ optional = true
     #[test]
     fn test_promote_changelog_unreleased_to_version_with_no_entries() {
         let changelog = Changelog {
+            protected_preamble: None,
             unreleased: None,
             releases: IndexMap::new(),
         };
@@ -637,11 +2934,7 @@ optional = true
         assert_eq!(changelog.unreleased, None);
         assert_eq!(changelog.releases.get("0.8.17"), None);
 
-        let next_version = BuildpackVersion {
-            major: 0,
-            minor: 8,
-            patch: 17,
-        };
+        let next_version = Version::new(0, 8, 17);
         let date = Utc.with_ymd_and_hms(2023, 6, 16, 0, 0, 0).unwrap();
         let updated_dependencies = HashSet::new();
         let changelog = promote_changelog_unreleased_to_version(
@@ -649,6 +2942,7 @@ optional = true
             &next_version,
             &date,
             &updated_dependencies,
+            &DependencyChangeEntry::default(),
         );
 
         assert_eq!(changelog.unreleased, None);
@@ -657,27 +2951,104 @@ optional = true
             Some(&ReleaseEntry {
                 version: "0.8.17".parse::<Version>().unwrap(),
                 date,
-                body: "- No changes.".to_string()
+                body: "- No changes.".to_string(),
+                released_by: None,
             })
         );
     }
 
+    #[test]
+    fn test_promote_changelog_unreleased_to_version_prunes_and_orders_change_groups() {
+        let changelog = Changelog {
+            protected_preamble: None,
+            unreleased: Some(
+                "### Fixed\n\n- Fixed PATH handling.\n\n### Removed\n\n### Added\n\n- Added Node.js 22."
+                    .to_string(),
+            ),
+            releases: IndexMap::new(),
+        };
+
+        let next_version = Version::new(0, 8, 17);
+        let date = Utc.with_ymd_and_hms(2023, 6, 16, 0, 0, 0).unwrap();
+        let changelog = promote_changelog_unreleased_to_version(
+            &changelog,
+            &next_version,
+            &date,
+            &HashSet::new(),
+            &DependencyChangeEntry::default(),
+        );
+        assert_eq!(
+            changelog.releases.get("0.8.17").unwrap().body,
+            "### Added\n\n- Added Node.js 22.\n\n### Fixed\n\n- Fixed PATH handling."
+        );
+
+        let changelog = Changelog {
+            protected_preamble: None,
+            unreleased: Some("### Fixed\n\n### Security\n".to_string()),
+            releases: IndexMap::new(),
+        };
+        let changelog = promote_changelog_unreleased_to_version(
+            &changelog,
+            &next_version,
+            &date,
+            &HashSet::new(),
+            &DependencyChangeEntry::default(),
+        );
+        assert_eq!(
+            changelog.releases.get("0.8.17").unwrap().body,
+            "- No changes."
+        );
+    }
+
+    #[test]
+    fn test_promote_changelog_unreleased_to_version_keeps_protected_regions() {
+        let migration_note =
+            "<!-- actions:protect-start -->\n## Migrating to 1.0.0\n<!-- actions:protect-end -->";
+        let upgrade_notes = "<!-- actions:protect-start -->\n### Upgrading\n\nRun `heroku config:unset FOO_VERSION`   \nbefore deploying.\n\n- this isn't an entry\n<!-- actions:protect-end -->";
+        let changelog = Changelog::try_from(
+            format!(
+                "# Changelog\n\n{migration_note}\n\n## [Unreleased]\n\n### Removed\n\n- Dropped `FOO_VERSION`.\n\n{upgrade_notes}\n\n### Added\n\n- Added Node.js 22.\n"
+            )
+            .as_str(),
+        )
+        .unwrap();
+
+        let changelog = promote_changelog_unreleased_to_version(
+            &changelog,
+            &Version::new(1, 0, 0),
+            &Utc.with_ymd_and_hms(2023, 6, 16, 0, 0, 0).unwrap(),
+            &HashSet::from([buildpack_id!("heroku/jvm")]),
+            &DependencyChangeEntry::default(),
+        );
+        assert_eq!(
+            changelog.releases.get("1.0.0").unwrap().body,
+            format!("### Added\n\n- Added Node.js 22.\n\n### Changed\n\n- Updated `heroku/jvm` to `1.0.0`.\n\n### Removed\n\n- Dropped `FOO_VERSION`.\n\n{upgrade_notes}")
+        );
+        assert_eq!(
+            changelog.protected_preamble.as_deref(),
+            Some(migration_note)
+        );
+    }
+
     #[test]
     fn test_promote_changelog_unreleased_to_version_with_existing_entries_and_updated_dependencies()
     {
         let release_entry_0_8_16 = ReleaseEntry {
             version: "0.8.16".parse::<Version>().unwrap(),
             date: Utc.with_ymd_and_hms(2023, 2, 27, 0, 0, 0).unwrap(),
-            body: "### Added\n\n- Added node version 19.7.0, 19.6.1, 14.21.3, 16.19.1, 18.14.1, 18.14.2.\n- Added node version 18.14.0, 19.6.0.".to_string()
+            body: "### Added\n\n- Added node version 19.7.0, 19.6.1, 14.21.3, 16.19.1, 18.14.1, 18.14.2.\n- Added node version 18.14.0, 19.6.0.".to_string(),
+            released_by: None,
         };
 
         let release_entry_0_8_15 = ReleaseEntry {
             version: "0.8.15".parse::<Version>().unwrap(),
             date: Utc.with_ymd_and_hms(2023, 2, 27, 0, 0, 0).unwrap(),
-            body: "### Changed\n\n- `name` is no longer a required field in package.json. ([#447](https://github.com/heroku/buildpacks-nodejs/pull/447))\n\n### Added\n\n- Added node version 19.5.0.".to_string()
+            body: "### Changed\n\n- `name` is no longer a required field in package.json. ([#447](https://github.com/heroku/buildpacks-nodejs/pull/447))\n\n### Added\n\n- Added node version 19.5.0.".to_string(),
+            released_by: None,
         };
 
         let changelog = Changelog {
+            protected_preamble: None,
             unreleased: Some(
                 "### Added\n\n- Added node version 18.15.0.\n- Added yarn version 4.0.0-rc.2"
                     .to_string(),
@@ -705,11 +3076,7 @@ optional = true
             Some(&release_entry_0_8_15)
         );
 
-        let next_version = BuildpackVersion {
-            major: 0,
-            minor: 8,
-            patch: 17,
-        };
+        let next_version = Version::new(0, 8, 17);
         let date = Utc.with_ymd_and_hms(2023, 6, 16, 0, 0, 0).unwrap();
         let updated_dependencies = HashSet::from([buildpack_id!("b"), buildpack_id!("a")]);
         let changelog = promote_changelog_unreleased_to_version(
@@ -717,6 +3084,7 @@ optional = true
             &next_version,
             &date,
             &updated_dependencies,
+            &DependencyChangeEntry::default(),
         );
 
         assert_eq!(changelog.unreleased, None);
@@ -725,7 +3093,8 @@ optional = true
             Some(&ReleaseEntry {
                 version: "0.8.17".parse::<Version>().unwrap(),
                 date,
-                body: "### Added\n\n- Added node version 18.15.0.\n- Added yarn version 4.0.0-rc.2\n\n### Changed\n\n- Updated `a` to `0.8.17`.\n- Updated `b` to `0.8.17`.".to_string()
+                body: "### Added\n\n- Added node version 18.15.0.\n- Added yarn version 4.0.0-rc.2\n\n### Changed\n\n- Updated `a` to `0.8.17`.\n- Updated `b` to `0.8.17`.".to_string(),
+                released_by: None,
             })
         );
         assert_eq!(
@@ -741,6 +3110,7 @@ optional = true
     #[test]
     fn test_promote_changelog_unreleased_to_version_with_no_entries_and_updated_dependencies() {
         let changelog = Changelog {
+            protected_preamble: None,
             unreleased: None,
             releases: IndexMap::new(),
         };
@@ -748,11 +3118,7 @@ optional = true
         assert_eq!(changelog.unreleased, None);
         assert_eq!(changelog.releases.get("0.8.17"), None);
 
-        let next_version = BuildpackVersion {
-            major: 0,
-            minor: 8,
-            patch: 17,
-        };
+        let next_version = Version::new(0, 8, 17);
         let date = Utc.with_ymd_and_hms(2023, 6, 16, 0, 0, 0).unwrap();
         let updated_dependencies = HashSet::from([buildpack_id!("a"), buildpack_id!("b")]);
         let changelog = promote_changelog_unreleased_to_version(
@@ -760,6 +3126,7 @@ optional = true
             &next_version,
             &date,
             &updated_dependencies,
+            &DependencyChangeEntry::default(),
         );
 
         assert_eq!(changelog.unreleased, None);
@@ -769,7 +3136,8 @@ optional = true
                 version: "0.8.17".parse::<Version>().unwrap(),
                 date,
                 body: "### Changed\n\n- Updated `a` to `0.8.17`.\n- Updated `b` to `0.8.17`."
-                    .to_string()
+                    .to_string(),
+                released_by: None,
             })
         );
     }
@@ -778,6 +3146,7 @@ optional = true
     fn test_promote_changelog_unreleased_to_version_with_changed_entries_is_merged_with_updated_dependencies(
     ) {
         let changelog = Changelog {
+            protected_preamble: None,
             unreleased: Some(
                 r"
 - Entry not under a header
@@ -801,11 +3170,7 @@ optional = true
             releases: IndexMap::new(),
         };
 
-        let next_version = BuildpackVersion {
-            major: 0,
-            minor: 8,
-            patch: 17,
-        };
+        let next_version = Version::new(0, 8, 17);
         let date = Utc.with_ymd_and_hms(2023, 6, 16, 0, 0, 0).unwrap();
         let updated_dependencies = HashSet::from([buildpack_id!("b"), buildpack_id!("a")]);
         let changelog = promote_changelog_unreleased_to_version(
@@ -813,6 +3178,7 @@ optional = true
             &next_version,
             &date,
             &updated_dependencies,
+            &DependencyChangeEntry::default(),
         );
 
         assert_eq!(changelog.unreleased, None);
@@ -841,6 +3207,74 @@ optional = true
         );
     }
 
+    #[test]
+    fn test_promote_changelog_unreleased_to_version_with_dependency_change_template_and_group() {
+        let changelog = Changelog {
+            protected_preamble: None,
+            unreleased: Some("### Changed\n\n- Lowed limits".to_string()),
+            releases: IndexMap::new(),
+        };
+
+        let next_version = Version::new(0, 8, 17);
+        let date = Utc.with_ymd_and_hms(2023, 6, 16, 0, 0, 0).unwrap();
+        let updated_dependencies = HashSet::from([buildpack_id!("a")]);
+        let changelog = promote_changelog_unreleased_to_version(
+            &changelog,
+            &next_version,
+            &date,
+            &updated_dependencies,
+            &DependencyChangeEntry {
+                template: "Bumped {id} to v{version}".to_string(),
+                group: ChangeGroup::Dependencies,
+            },
+        );
+
+        assert_eq!(
+            changelog.releases.get("0.8.17").unwrap().body,
+            "### Changed\n\n- Lowed limits\n\n### Dependencies\n\n- Bumped a to v0.8.17"
+        );
+    }
+
+    #[test]
+    fn test_replace_marked_versions() {
+        let readme = r"# Node.js
+
+Pin the buildpack with `--buildpack heroku/nodejs@1.2.3`.
+
+<!-- release-version:start -->
+```
+pack build my-app --buildpack heroku/nodejs@1.2.3 --env NODE_VERSION=11.2.3
+```
+
+Released as v1.2.3. Not to be confused with 1.2.30, 1.2.3-rc.1 or 1.2.3.4.
+<!-- release-version:end -->
+";
+        assert_eq!(
+            replace_marked_versions(readme, "1.2.3", "1.3.0").unwrap(),
+            r"# Node.js
+
+Pin the buildpack with `--buildpack heroku/nodejs@1.2.3`.
+
+<!-- release-version:start -->
+```
+pack build my-app --buildpack heroku/nodejs@1.3.0 --env NODE_VERSION=11.2.3
+```
+
+Released as v1.3.0. Not to be confused with 1.2.30, 1.2.3-rc.1 or 1.2.3.4.
+<!-- release-version:end -->
+"
+        );
+
+        assert_eq!(
+            replace_marked_versions(
+                "<!-- release-version:start -->\nheroku/nodejs@1.2.3\n",
+                "1.2.3",
+                "1.3.0"
+            ),
+            None
+        );
+    }
+
     fn create_buildpack_file(contents: &str) -> BuildpackFile {
         create_buildpack_file_with_name("/path/to/test/buildpack.toml", contents)
     }
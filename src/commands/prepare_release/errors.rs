@@ -1,7 +1,10 @@
-use crate::buildpacks::FindReleasableBuildpacksError;
+use crate::buildpacks::{CheckCnbRegistryError, FindReleasableBuildpacksError};
 use crate::changelog::ChangelogError;
+use crate::git::GitError;
 use crate::github::actions::WriteActionDataError;
-use libcnb_data::buildpack::BuildpackVersion;
+use crate::github::pull_requests::CreatePullRequestError;
+use crate::locking::LockFileError;
+use semver::Version;
 use std::collections::HashMap;
 use std::io;
 use std::path::PathBuf;
@@ -13,7 +16,29 @@ pub(crate) enum Error {
     #[error(transparent)]
     FindReleasableBuildpacks(FindReleasableBuildpacksError),
     #[error(transparent)]
+    LockFile(LockFileError),
+    #[error(transparent)]
     SetActionOutput(WriteActionDataError),
+    #[error("Could not determine the GitHub repository (e.g.; heroku/buildpacks-nodejs) to open a pull request against from --repository-url {0}")]
+    InvalidPullRequestRepository(String),
+    #[error(transparent)]
+    Git(GitError),
+    #[error(transparent)]
+    CreatePullRequest(CreatePullRequestError),
+    #[error("Could not serialize released buildpacks into json\nError: {0}")]
+    SerializingJson(#[source] serde_json::Error),
+    #[error("One of --bump or --version is required")]
+    MissingReleaseVersion,
+    #[error("Invalid version `{0}` for argument --version, versions must be in the form X.Y.Z")]
+    InvalidVersion(String),
+    #[error("Version {0} for argument --version must be greater than the current version {1}")]
+    VersionNotGreater(Version, Version),
+    #[error("Changelog already has a release entry for the current version {1}, --bump none can only promote unreleased changes to a version that hasn't been released yet\nPath: {0}")]
+    ReleaseEntryAlreadyExists(PathBuf, Version),
+    #[error("Invalid value `{0}` for SOURCE_DATE_EPOCH, expected a Unix timestamp")]
+    InvalidSourceDateEpoch(String),
+    #[error("The root changelog is also the changelog of a buildpack, --root-changelog is for projects where each buildpack has its own changelog\nPath: {0}")]
+    RootChangelogIsBuildpackChangelog(PathBuf),
     #[error("Invalid URL `{0}` for argument --repository-url\nError: {1}")]
     InvalidRepositoryUrl(String, #[source] uriparse::URIError),
     #[error("Invalid Version `{0}` for argument --declarations-starting-version\nError: {1}")]
@@ -21,7 +46,7 @@ pub(crate) enum Error {
     #[error("No buildpacks found under {}", .0.display())]
     NoBuildpacksFound(PathBuf),
     #[error("Not all versions match:\n{}", list_versions_with_path(.0))]
-    NotAllVersionsMatch(HashMap<PathBuf, BuildpackVersion>),
+    NotAllVersionsMatch(HashMap<PathBuf, Version>),
     #[error("No fixed version could be determined")]
     NoFixedVersion,
     #[error("Could not read changelog\nPath: {0}\nError: {1}")]
@@ -36,18 +61,121 @@ pub(crate) enum Error {
     InvalidBuildpackId(PathBuf, String),
     #[error("Invalid buildpack version `{1}` in buildpack.toml\nPath: {0}")]
     InvalidBuildpackVersion(PathBuf, String),
+    #[error("Unsupported buildpack versions, versions must be in the form X.Y.Z (use --coerce-versions to normalize them):\n{}", list_unsupported_versions(.0))]
+    UnsupportedBuildpackVersions(Vec<(PathBuf, String)>),
+    #[error("Found problems in the following changelogs:\n{}", list_changelog_problems(.0))]
+    InvalidChangelogs(Vec<(PathBuf, Vec<String>)>),
     #[error("Could not read buildpack\nPath: {0}\nError: {1}")]
     ReadingBuildpack(PathBuf, #[source] io::Error),
     #[error("Could not parse buildpack\nPath: {0}\nError: {1}")]
     ParsingBuildpack(PathBuf, #[source] toml_edit::TomlError),
     #[error("Could not write buildpack\nPath: {0}\nError: {1}")]
     WritingBuildpack(PathBuf, #[source] io::Error),
+    #[error("Could not read package\nPath: {0}\nError: {1}")]
+    ReadingPackage(PathBuf, #[source] io::Error),
+    #[error("Could not parse package\nPath: {0}\nError: {1}")]
+    ParsingPackage(PathBuf, #[source] toml_edit::TomlError),
+    #[error("Could not write package\nPath: {0}\nError: {1}")]
+    WritingPackage(PathBuf, #[source] io::Error),
+    #[error("Could not read Cargo manifest\nPath: {0}\nError: {1}")]
+    ReadingCargoManifest(PathBuf, #[source] io::Error),
+    #[error("Could not parse Cargo manifest\nPath: {0}\nError: {1}")]
+    ParsingCargoManifest(PathBuf, #[source] toml_edit::TomlError),
+    #[error("Could not write Cargo manifest\nPath: {0}\nError: {1}")]
+    WritingCargoManifest(PathBuf, #[source] io::Error),
+    #[error("Could not read README\nPath: {0}\nError: {1}")]
+    ReadingReadme(PathBuf, #[source] io::Error),
+    #[error("Could not write README\nPath: {0}\nError: {1}")]
+    WritingReadme(PathBuf, #[source] io::Error),
+    #[error("README has a `<!-- release-version:start -->` marker without a matching `<!-- release-version:end -->`\nPath: {0}")]
+    UnclosedVersionMarker(PathBuf),
+    #[error("Could not find the Cargo workspace that the crate version is inherited from\nPath: {}", .0.display())]
+    MissingCargoWorkspace(PathBuf),
+    #[error("Could not read file to verify the release changes, no files were written\nPath: {0}\nError: {1}")]
+    VerifyingReleaseFile(PathBuf, #[source] io::Error),
+    #[error(
+        "File was changed while the release was being prepared, no files were written\nPath: {0}"
+    )]
+    ReleaseFileModified(PathBuf),
+    #[error("Release changes would leave the file invalid, no files were written\nPath: {0}\nError: {1}")]
+    InvalidReleaseFile(PathBuf, String),
+    #[error("Could not write temporary file for the release changes, no files were written\nPath: {0}\nError: {1}")]
+    StagingReleaseFile(PathBuf, #[source] io::Error),
+    #[error("Could not replace file with the release changes\nPath: {}\nError: {}\n{}", .0.display(), .1, describe_rollback(.2, .3))]
+    ReplacingReleaseFile(PathBuf, #[source] io::Error, Vec<PathBuf>, Vec<PathBuf>),
+    #[error(transparent)]
+    CheckCnbRegistry(CheckCnbRegistryError),
+    #[error("Version {} is already in the CNB registry for these buildpacks, no files were changed:\n{}", .0, list_buildpack_ids(.1))]
+    VersionAlreadyRegistered(Version, Vec<String>),
+}
+
+fn describe_rollback(replaced: &[PathBuf], not_restored: &[PathBuf]) -> String {
+    if replaced.is_empty() {
+        return "No other files were written".to_string();
+    }
+    let restored = replaced
+        .iter()
+        .filter(|path| !not_restored.contains(path))
+        .collect::<Vec<_>>();
+    let mut description = vec![];
+    if !restored.is_empty() {
+        description.push(format!(
+            "Restored the files that were already replaced:\n{}",
+            list_paths(restored)
+        ));
+    }
+    if !not_restored.is_empty() {
+        description.push(format!(
+            "Could not restore these files, they still have the release changes:\n{}",
+            list_paths(not_restored)
+        ));
+    }
+    description.join("\n")
 }
 
-fn list_versions_with_path(version_map: &HashMap<PathBuf, BuildpackVersion>) -> String {
+fn list_paths<'a>(paths: impl IntoIterator<Item = &'a PathBuf>) -> String {
+    paths
+        .into_iter()
+        .map(|path| format!("• {}", path.display()))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn list_buildpack_ids(buildpack_ids: &[String]) -> String {
+    buildpack_ids
+        .iter()
+        .map(|buildpack_id| format!("• {buildpack_id}"))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn list_versions_with_path(version_map: &HashMap<PathBuf, Version>) -> String {
     version_map
         .iter()
         .map(|(path, version)| format!("• {version} ({})", path.display()))
         .collect::<Vec<_>>()
         .join("\n")
 }
+
+fn list_unsupported_versions(unsupported_versions: &[(PathBuf, String)]) -> String {
+    unsupported_versions
+        .iter()
+        .map(|(path, version)| format!("• {version} ({})", path.display()))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn list_changelog_problems(invalid_changelogs: &[(PathBuf, Vec<String>)]) -> String {
+    invalid_changelogs
+        .iter()
+        .map(|(path, problems)| {
+            let problems = problems
+                .iter()
+                .map(|problem| format!("  - {problem}"))
+                .collect::<Vec<_>>()
+                .join("\n");
+            format!("• {}\n{problems}", path.display())
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
@@ -0,0 +1,435 @@
+use crate::changelog::Changelog;
+use crate::commands::selftest::errors::Error;
+use clap::Parser;
+use std::collections::HashMap;
+use std::ffi::OsString;
+use std::path::Path;
+use std::process::Command;
+
+type Result<T> = std::result::Result<T, Error>;
+
+#[derive(Parser, Debug)]
+#[command(author, version, about = "Runs the commands against a fixture workspace and checks the outputs they set", long_about = None, disable_version_flag = true)]
+pub(crate) struct SelftestArgs {
+    /// Leaves the fixture workspace in place for debugging instead of removing it.
+    #[arg(long)]
+    pub(crate) keep_workspace: bool,
+}
+
+#[derive(Debug)]
+enum ExpectedOutput {
+    Value(&'static str),
+    JsonArray,
+    NotEmpty,
+}
+
+struct Step {
+    // Git commands run in the workspace before the step (e.g.; to tag a release).
+    git: &'static [&'static [&'static str]],
+    args: &'static [&'static str],
+    outputs: &'static [(&'static str, ExpectedOutput)],
+    // Files in the workspace and text they must contain once the step has run.
+    files: &'static [(&'static str, &'static str)],
+}
+
+const FIXTURE_BUILDPACKS: [&str; 2] = ["buildpacks/selftest-bash", "buildpacks/selftest-libcnb"];
+
+// The files `init-buildpack` scaffolds for each kind of fixture buildpack, and text they must
+// contain.
+const SCAFFOLDED_FILES: [(&str, &str); 6] = [
+    (
+        "buildpacks/selftest-bash/buildpack.toml",
+        "id = \"heroku/selftest-bash\"",
+    ),
+    ("buildpacks/selftest-bash/bin/detect", "#!/usr/bin/env bash"),
+    ("buildpacks/selftest-bash/bin/build", "#!/usr/bin/env bash"),
+    (
+        "buildpacks/selftest-libcnb/buildpack.toml",
+        "id = \"heroku/selftest-libcnb\"",
+    ),
+    ("buildpacks/selftest-libcnb/Cargo.toml", "libcnb"),
+    ("buildpacks/selftest-libcnb/src/main.rs", "buildpack_main!"),
+];
+
+const FIXTURE_BUILDER: &str = "builders/builder-24/builder.toml";
+const FIXTURE_BUILDER_CONTENTS: &str = r#"[[buildpacks]]
+id = "heroku/selftest-bash"
+uri = "docker://docker.io/heroku/buildpack-selftest-bash@sha256:0000000000000000000000000000000000000000000000000000000000000000"
+
+[[order]]
+[[order.group]]
+id = "heroku/selftest-bash"
+version = "0.1.0"
+"#;
+
+const FIXTURE_REVERT_MAPPING: &str = "revert-to.toml";
+const FIXTURE_REVERT_MAPPING_CONTENTS: &str = r#"["heroku/selftest-bash"]
+version = "0.0.9"
+uri = "docker://docker.io/heroku/buildpack-selftest-bash@sha256:1111111111111111111111111111111111111111111111111111111111111111"
+"#;
+
+// The fixture buildpacks have no published images, so the commands run with a `crane` that
+// reports every image as missing instead of querying a registry. Commands that need published
+// images (e.g.; `update-builder` without `--revert-to`) aren't included.
+const FIXTURE_CRANE: &str = "#!/bin/sh\necho \"MANIFEST_UNKNOWN: $*\" >&2\nexit 1\n";
+
+const STEPS: &[Step] = &[
+    Step {
+        git: &[],
+        args: &["generate-buildpack-matrix", "--temporary-id", "selftest"],
+        outputs: &[
+            ("buildpacks", ExpectedOutput::JsonArray),
+            ("skipped_buildpacks", ExpectedOutput::JsonArray),
+            ("skipped_due_to_errors", ExpectedOutput::JsonArray),
+            ("dependency_graph", ExpectedOutput::JsonArray),
+            ("version", ExpectedOutput::Value("0.1.0")),
+            ("rust_triples", ExpectedOutput::JsonArray),
+        ],
+        files: &[],
+    },
+    Step {
+        git: &[],
+        args: &["generate-changelog", "--unreleased"],
+        outputs: &[("changelog", ExpectedOutput::NotEmpty)],
+        files: &[],
+    },
+    Step {
+        git: &[],
+        args: &[
+            "prepare-release",
+            "--bump",
+            "minor",
+            "--repository-url",
+            "https://github.com/heroku/buildpacks-selftest",
+        ],
+        outputs: &[
+            ("from_version", ExpectedOutput::Value("0.1.0")),
+            ("to_version", ExpectedOutput::Value("0.2.0")),
+        ],
+        files: &[(
+            "buildpacks/selftest-bash/buildpack.toml",
+            "version = \"0.2.0\"",
+        )],
+    },
+    Step {
+        git: &[],
+        args: &["generate-changelog", "--version", "0.2.0"],
+        outputs: &[("changelog", ExpectedOutput::NotEmpty)],
+        files: &[],
+    },
+    Step {
+        git: &[
+            &["add", "--all"],
+            &["commit", "--quiet", "--message", "Release v0.2.0"],
+            &["tag", "v0.2.0"],
+        ],
+        args: &["diff-release", "--from", "v0.1.0", "--to", "v0.2.0"],
+        outputs: &[("report", ExpectedOutput::NotEmpty)],
+        files: &[],
+    },
+    Step {
+        git: &[],
+        args: &["export-catalog"],
+        outputs: &[("catalog", ExpectedOutput::JsonArray)],
+        files: &[],
+    },
+    Step {
+        git: &[],
+        args: &[
+            "update-builder",
+            "--repository-path",
+            ".",
+            "--builder-repository-path",
+            "builders",
+            "--builders",
+            "builder-24",
+            "--revert-to",
+            FIXTURE_REVERT_MAPPING,
+        ],
+        outputs: &[],
+        files: &[(FIXTURE_BUILDER, "version = \"0.0.9\"")],
+    },
+];
+
+pub(crate) fn execute(args: &SelftestArgs) -> Result<()> {
+    let executable = std::env::current_exe().map_err(Error::CurrentExe)?;
+    let selftest_dir =
+        std::env::temp_dir().join(format!("actions-selftest-{}", std::process::id()));
+    let workspace_dir = selftest_dir.join("workspace");
+
+    std::fs::create_dir_all(&workspace_dir)
+        .map_err(|e| Error::CreatingWorkspace(workspace_dir.clone(), e))?;
+
+    let result = run_selftest(&executable, &selftest_dir, &workspace_dir);
+
+    if args.keep_workspace {
+        eprintln!("✅️ Kept selftest workspace: {}", workspace_dir.display());
+    } else {
+        let _ = std::fs::remove_dir_all(&selftest_dir);
+    }
+
+    result
+}
+
+fn run_selftest(executable: &Path, selftest_dir: &Path, workspace_dir: &Path) -> Result<()> {
+    create_fixture_workspace(executable, selftest_dir, workspace_dir)?;
+
+    for step in STEPS {
+        for git_args in step.git {
+            run_git(workspace_dir, git_args)?;
+        }
+        let outputs = run_step(executable, selftest_dir, workspace_dir, step.args)?;
+        let command = step.args.join(" ");
+        for (name, expected) in step.outputs {
+            let value = outputs
+                .get(*name)
+                .ok_or(Error::MissingOutput(command.clone(), (*name).to_string()))?;
+            check_output(value, expected).map_err(|expected| {
+                Error::UnexpectedOutput(
+                    command.clone(),
+                    (*name).to_string(),
+                    expected,
+                    value.clone(),
+                )
+            })?;
+        }
+        check_files(workspace_dir, &command, step.files)?;
+        eprintln!("✅️ Verified outputs: actions {command}");
+    }
+
+    Ok(())
+}
+
+// The fixture buildpacks are created with `init-buildpack` so the selftest also covers the
+// layout that command generates. The workspace is a git repository with the fixture tagged as
+// `v0.1.0`, which `diff-release` compares the release against.
+fn create_fixture_workspace(
+    executable: &Path,
+    selftest_dir: &Path,
+    workspace_dir: &Path,
+) -> Result<()> {
+    for buildpack_dir in FIXTURE_BUILDPACKS {
+        let name = buildpack_dir.trim_start_matches("buildpacks/");
+        let kind = name.trim_start_matches("selftest-");
+        let id = format!("heroku/{name}");
+        run_step(
+            executable,
+            selftest_dir,
+            workspace_dir,
+            &["init-buildpack", "--id", &id, "--type", kind],
+        )?;
+
+        let changelog_path = workspace_dir.join(buildpack_dir).join("CHANGELOG.md");
+        let contents = std::fs::read_to_string(&changelog_path)
+            .map_err(|e| Error::ReadingFile(changelog_path.clone(), e))?;
+        let mut changelog = Changelog::try_from(contents.as_str())
+            .map_err(|e| Error::ParsingChangelog(changelog_path.clone(), e))?;
+        changelog.add_unreleased_entry("Added", &format!("Initial release of `{id}`."));
+        std::fs::write(&changelog_path, changelog.to_string())
+            .map_err(|e| Error::WritingFile(changelog_path.clone(), e))?;
+    }
+    check_files(workspace_dir, "init-buildpack", &SCAFFOLDED_FILES)?;
+
+    for (path, contents) in [
+        (FIXTURE_BUILDER, FIXTURE_BUILDER_CONTENTS),
+        (FIXTURE_REVERT_MAPPING, FIXTURE_REVERT_MAPPING_CONTENTS),
+    ] {
+        write_file(&workspace_dir.join(path), contents)?;
+    }
+    write_executable(&selftest_dir.join("bin").join("crane"), FIXTURE_CRANE)?;
+
+    for git_args in [
+        &["init", "--quiet"][..],
+        &["add", "--all"],
+        &["commit", "--quiet", "--message", "Initial release"],
+        &["tag", "v0.1.0"],
+    ] {
+        run_git(workspace_dir, git_args)?;
+    }
+
+    eprintln!("✅️ Created fixture workspace: {}", workspace_dir.display());
+
+    Ok(())
+}
+
+// Each command runs with its own empty `GITHUB_OUTPUT` and `GITHUB_STEP_SUMMARY` files, the same
+// way it would in a workflow step.
+fn run_step(
+    executable: &Path,
+    selftest_dir: &Path,
+    workspace_dir: &Path,
+    args: &[&str],
+) -> Result<HashMap<String, String>> {
+    let command = args.join(" ");
+    let output_path = selftest_dir.join("github-output");
+    let summary_path = selftest_dir.join("github-step-summary");
+    for path in [&output_path, &summary_path] {
+        std::fs::write(path, "").map_err(|e| Error::WritingFile(path.clone(), e))?;
+    }
+
+    let output = Command::new(executable)
+        .args(args)
+        .current_dir(workspace_dir)
+        .env("PATH", fixture_path(selftest_dir))
+        .env("GITHUB_OUTPUT", &output_path)
+        .env("GITHUB_STEP_SUMMARY", &summary_path)
+        .env_remove("GITHUB_REPOSITORY")
+        .output()
+        .map_err(|e| Error::CommandFailure(command.clone(), e))?;
+
+    if !output.status.success() {
+        Err(Error::CommandExitStatus(
+            command,
+            output.status,
+            String::from_utf8_lossy(&output.stderr).to_string(),
+        ))?;
+    }
+
+    std::fs::read_to_string(&output_path)
+        .map(|contents| parse_outputs(&contents))
+        .map_err(|e| Error::ReadingFile(output_path.clone(), e))
+}
+
+// The fixture's `crane` comes first on the `PATH`, ahead of any installed one.
+fn fixture_path(selftest_dir: &Path) -> OsString {
+    let paths = std::iter::once(selftest_dir.join("bin"))
+        .chain(
+            std::env::var_os("PATH")
+                .iter()
+                .flat_map(std::env::split_paths),
+        )
+        .collect::<Vec<_>>();
+    std::env::join_paths(paths).unwrap_or_default()
+}
+
+// Commits are made as a fixed author so the selftest doesn't depend on the git configuration.
+fn run_git(workspace_dir: &Path, args: &[&str]) -> Result<()> {
+    let command = args.join(" ");
+    let output = Command::new("git")
+        .args(args)
+        .current_dir(workspace_dir)
+        .env("GIT_AUTHOR_NAME", "selftest")
+        .env("GIT_AUTHOR_EMAIL", "selftest@localhost")
+        .env("GIT_COMMITTER_NAME", "selftest")
+        .env("GIT_COMMITTER_EMAIL", "selftest@localhost")
+        .output()
+        .map_err(|e| Error::GitCommandFailure(command.clone(), e))?;
+
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(Error::GitExitStatus(
+            command,
+            output.status,
+            String::from_utf8_lossy(&output.stderr).to_string(),
+        ))
+    }
+}
+
+fn check_files(workspace_dir: &Path, command: &str, files: &[(&str, &str)]) -> Result<()> {
+    for (path, expected) in files {
+        let path = workspace_dir.join(path);
+        let contents =
+            std::fs::read_to_string(&path).map_err(|e| Error::ReadingFile(path.clone(), e))?;
+        if !contents.contains(expected) {
+            Err(Error::UnexpectedFile(
+                command.to_string(),
+                path,
+                (*expected).to_string(),
+            ))?;
+        }
+    }
+    Ok(())
+}
+
+fn write_file(path: &Path, contents: &str) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .map_err(|e| Error::CreatingWorkspace(parent.to_path_buf(), e))?;
+    }
+    std::fs::write(path, contents).map_err(|e| Error::WritingFile(path.to_path_buf(), e))
+}
+
+fn write_executable(path: &Path, contents: &str) -> Result<()> {
+    write_file(path, contents)?;
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o755))
+            .map_err(|e| Error::WritingFile(path.to_path_buf(), e))?;
+    }
+
+    Ok(())
+}
+
+// Reads outputs in the `name=value` and `name<<DELIMITER` formats written by `actions::set_output`.
+fn parse_outputs(contents: &str) -> HashMap<String, String> {
+    let mut outputs = HashMap::new();
+    let mut lines = contents.lines();
+
+    while let Some(line) = lines.next() {
+        if let Some((name, value)) = line.split_once('=') {
+            outputs.insert(name.to_string(), value.to_string());
+        } else if let Some((name, delimiter)) = line.split_once("<<") {
+            let value = lines
+                .by_ref()
+                .take_while(|line| *line != delimiter)
+                .collect::<Vec<_>>()
+                .join("\n");
+            outputs.insert(name.to_string(), value);
+        }
+    }
+
+    outputs
+}
+
+fn check_output(value: &str, expected: &ExpectedOutput) -> std::result::Result<(), String> {
+    let matches = match expected {
+        ExpectedOutput::Value(expected_value) => value == *expected_value,
+        ExpectedOutput::JsonArray => serde_json::from_str::<Vec<serde_json::Value>>(value).is_ok(),
+        ExpectedOutput::NotEmpty => !value.trim().is_empty(),
+    };
+
+    if matches {
+        Ok(())
+    } else {
+        Err(match expected {
+            ExpectedOutput::Value(expected_value) => format!("`{expected_value}`"),
+            ExpectedOutput::JsonArray => "a JSON array".to_string(),
+            ExpectedOutput::NotEmpty => "a non-empty value".to_string(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::commands::selftest::command::{check_output, parse_outputs, ExpectedOutput};
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_parse_outputs() {
+        assert_eq!(
+            parse_outputs(
+                "version=1.0.0\nchangelog<<abc123\n## heroku/java\n\n- Foo.\nabc123\nempty=\n"
+            ),
+            HashMap::from([
+                ("version".to_string(), "1.0.0".to_string()),
+                (
+                    "changelog".to_string(),
+                    "## heroku/java\n\n- Foo.".to_string()
+                ),
+                ("empty".to_string(), String::new()),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_check_output() {
+        assert!(check_output("1.0.0", &ExpectedOutput::Value("1.0.0")).is_ok());
+        assert!(check_output("1.0.1", &ExpectedOutput::Value("1.0.0")).is_err());
+        assert!(check_output("[]", &ExpectedOutput::JsonArray).is_ok());
+        assert!(check_output("{}", &ExpectedOutput::JsonArray).is_err());
+        assert!(check_output(" ", &ExpectedOutput::NotEmpty).is_err());
+    }
+}
@@ -0,0 +1,31 @@
+use crate::changelog::ChangelogError;
+use std::path::PathBuf;
+use std::process::ExitStatus;
+
+#[derive(Debug, thiserror::Error)]
+pub(crate) enum Error {
+    #[error("Failed to locate the current executable\nError: {0}")]
+    CurrentExe(#[source] std::io::Error),
+    #[error("Could not create selftest workspace\nPath: {0}\nError: {1}")]
+    CreatingWorkspace(PathBuf, #[source] std::io::Error),
+    #[error("Could not write file\nPath: {0}\nError: {1}")]
+    WritingFile(PathBuf, #[source] std::io::Error),
+    #[error("Could not read file\nPath: {0}\nError: {1}")]
+    ReadingFile(PathBuf, #[source] std::io::Error),
+    #[error("Could not parse changelog\nPath: {0}\nError: {1}")]
+    ParsingChangelog(PathBuf, #[source] ChangelogError),
+    #[error("Failed to execute `actions {0}`\nError: {1}")]
+    CommandFailure(String, #[source] std::io::Error),
+    #[error("Command `actions {0}` exited with a non-zero status\nStatus: {1}\nOutput:\n{2}")]
+    CommandExitStatus(String, ExitStatus, String),
+    #[error("Command `actions {0}` did not set the output `{1}`")]
+    MissingOutput(String, String),
+    #[error("Command `actions {0}` set the output `{1}` to an unexpected value, expected {2}\nValue: {3}")]
+    UnexpectedOutput(String, String, String, String),
+    #[error("Command `actions {0}` left a file without the expected contents, expected `{2}`\nPath: {1}")]
+    UnexpectedFile(String, PathBuf, String),
+    #[error("Failed to execute `git {0}`\nError: {1}")]
+    GitCommandFailure(String, #[source] std::io::Error),
+    #[error("Command `git {0}` exited with a non-zero status\nStatus: {1}\nOutput:\n{2}")]
+    GitExitStatus(String, ExitStatus, String),
+}
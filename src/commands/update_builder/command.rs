@@ -1,13 +1,27 @@
+use crate::builders::{
+    builder_file_path, detect_builder_schema, format_buildpack_uri, migrate_builder_schema,
+    read_builder_file, read_builder_targets, resolve_builders, BuilderFile,
+};
 use crate::buildpacks::{
     calculate_digest, find_releasable_buildpacks, read_buildpack_descriptor,
-    read_image_repository_metadata,
+    read_buildpack_targets, read_image_repository_metadata, validate_image_repository_metadata,
+    verify_manifest_exists,
 };
+use crate::changelog::{read_trailing_link_definitions, Changelog, ReleaseEntry};
 use crate::commands::resolve_path;
-use crate::update_builder::errors::Error;
-use clap::Parser;
-use libcnb_data::buildpack::{BuildpackId, BuildpackVersion};
+use crate::concurrency::{try_map_concurrently, MAX_CONCURRENT_REQUESTS};
+use crate::git::{read_file_at_revision, resolve_revision};
+use crate::locking::lock_files;
+use crate::update_builder::errors::{Error, MalformedSection};
+use chrono::Utc;
+use clap::{Parser, ValueEnum};
+use globset::Glob;
+use indexmap::IndexMap;
+use libcnb_data::buildpack::{BuildpackDescriptor, BuildpackId, BuildpackTarget, BuildpackVersion};
+use semver::Version;
+use serde::Deserialize;
 use std::collections::BTreeMap;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
 use toml_edit::{value, ArrayOfTables, DocumentMut, Item};
 use uriparse::URI;
@@ -15,31 +29,181 @@ use uriparse::URI;
 type Result<T> = std::result::Result<T, Error>;
 
 #[derive(Parser, Debug)]
+#[allow(clippy::struct_excessive_bools)]
 #[command(author, version, about = "Updates all references to a buildpack in heroku/cnb-builder-images for the given list of builders", long_about = None)]
 pub(crate) struct UpdateBuilderArgs {
     #[arg(long)]
     pub(crate) repository_path: PathBuf,
     #[arg(long)]
     pub(crate) builder_repository_path: PathBuf,
-    #[arg(long, required = true, value_delimiter = ',', num_args = 1..)]
+    #[arg(long, required_unless_present = "builders_file", value_delimiter = ',', num_args = 1..)]
     pub(crate) builders: Vec<String>,
+    /// Reads the builders from a TOML file with a `[[builders]]` entry (with a `name`) for each
+    /// one, instead of `--builders`.
+    #[arg(long, conflicts_with = "builders")]
+    pub(crate) builders_file: Option<PathBuf>,
+    #[arg(long)]
+    pub(crate) add_changelog_entries: bool,
+    #[arg(long)]
+    pub(crate) lenient: bool,
+    #[arg(long)]
+    pub(crate) strict_image_repository: bool,
+    #[arg(long)]
+    pub(crate) verify_digests: bool,
+    #[arg(long, value_enum)]
+    pub(crate) bump_builder_version: Option<BuilderVersionBump>,
+    /// Leaves out buildpacks matching the given glob, by buildpack id or directory.
+    #[arg(long)]
+    pub(crate) exclude: Vec<Glob>,
+    /// Reverts the buildpack references to a previous known-good set instead of the current
+    /// releases, either as of a revision (commit, tag, or branch) of the builder repository or
+    /// from a TOML file mapping buildpack ids to a `version` and `uri`.
+    #[arg(long, conflicts_with = "verify_digests")]
+    pub(crate) revert_to: Option<String>,
+    /// Moves builders that still declare a `[stack]` to the `[[targets]]` layout before updating
+    /// them. Builders of either layout are updated the same way without it.
+    #[arg(long)]
+    pub(crate) migrate_schema: bool,
 }
 
-struct BuilderFile {
-    path: PathBuf,
-    document: DocumentMut,
+// A buildpack reference as written to a builder: the id, the version its `[[order.group]]`
+// entries pin, and the image URI of its `[[buildpacks]]` entry.
+type BuildpackReference = (BuildpackId, BuildpackVersion, String);
+
+// An entry of the `--revert-to` mapping file, keyed by buildpack id.
+#[derive(Debug, Deserialize)]
+struct RevertMappingEntry {
+    version: BuildpackVersion,
+    uri: String,
 }
 
-pub(crate) fn execute(args: UpdateBuilderArgs) -> Result<()> {
+#[derive(ValueEnum, Debug, Clone, Copy, Eq, PartialEq)]
+pub(crate) enum BuilderVersionBump {
+    Patch,
+    Minor,
+}
+
+// Builders keep their version in `[metadata] version` of builder.toml or, when that's not
+// present, in a `VERSION` file next to it.
+#[derive(Debug, Eq, PartialEq)]
+enum BuilderVersionLocation {
+    Metadata,
+    File(PathBuf),
+}
+
+pub(crate) fn execute(args: &UpdateBuilderArgs) -> Result<()> {
     let repository_path = std::env::current_dir()
         .map(|base| resolve_path(&args.repository_path, &base))
-        .map_err(|e| Error::ResolvePath(args.repository_path, e))?;
+        .map_err(|e| Error::ResolvePath(args.repository_path.clone(), e))?;
 
     let builder_repository_path = std::env::current_dir()
         .map(|base| resolve_path(&args.builder_repository_path, &base))
-        .map_err(|e| Error::ResolvePath(args.builder_repository_path, e))?;
+        .map_err(|e| Error::ResolvePath(args.builder_repository_path.clone(), e))?;
+
+    let buildpacks = read_buildpacks(&repository_path, &args.exclude)?;
+
+    if args.strict_image_repository {
+        for (buildpack_dir, buildpack_descriptor) in &buildpacks {
+            validate_image_repository_metadata(buildpack_dir, buildpack_descriptor)
+                .map_err(Error::InvalidImageRepository)?;
+        }
+    }
+
+    let builders = resolve_builders(&args.builders, args.builders_file.as_deref())
+        .map_err(Error::ReadBuildersFile)?;
+    let builder_paths = builders
+        .iter()
+        .map(|builder| builder_file_path(&builder_repository_path, builder))
+        .collect::<Vec<_>>();
+
+    let _locks = lock_files(builder_paths.iter().flat_map(|builder_path| {
+        ["builder.toml", "CHANGELOG.md", "VERSION"].map(|name| builder_path.with_file_name(name))
+    }))
+    .map_err(Error::LockFile)?;
+
+    let mut builder_files = builder_paths
+        .into_iter()
+        .map(|builder_path| read_builder_file(builder_path).map_err(Error::ReadBuilderFile))
+        .collect::<Result<Vec<_>>>()?;
+
+    if builder_files.is_empty() {
+        Err(Error::NoBuilderFiles(builders))?;
+    }
+
+    // everything that can fail, apart from writing the files, is resolved before any builder
+    // is modified
+    let prepared_builders = prepare_builder_files(&mut builder_files, args)?;
+
+    let builder_references = resolve_builder_references(
+        args.revert_to.as_deref(),
+        args.verify_digests,
+        &buildpacks,
+        &builder_repository_path,
+        &builder_files,
+    )?;
+    let change_verb = if args.revert_to.is_some() {
+        "Reverted"
+    } else {
+        "Updated"
+    };
+
+    for ((mut builder_file, prepared_builder), buildpack_references) in builder_files
+        .into_iter()
+        .zip(prepared_builders)
+        .zip(builder_references)
+    {
+        let mut changelog_entries = vec![];
+
+        for (buildpack_id, buildpack_version, buildpack_uri) in
+            filter_compatible_references(&builder_file, &buildpack_references, &buildpacks)
+        {
+            let updated = update_builder_with_buildpack_info(
+                &mut builder_file.document,
+                buildpack_id,
+                buildpack_version,
+                buildpack_uri,
+            );
+
+            if updated {
+                changelog_entries.push(format!(
+                    "{change_verb} `{buildpack_id}` to `{buildpack_version}`."
+                ));
+            }
+        }
+
+        let release_version = match (args.bump_builder_version, prepared_builder.version) {
+            (Some(bump), Some((location, version))) if !changelog_entries.is_empty() => Some(
+                write_builder_version(&mut builder_file, &location, &bump_version(&version, bump))?,
+            ),
+            _ => None,
+        };
+
+        std::fs::write(&builder_file.path, builder_file.document.to_string())
+            .map_err(|e| Error::WritingBuilder(builder_file.path.clone(), e))?;
+
+        eprintln!("✅️ Updated builder: {}", builder_file.path.display());
+
+        if let Some(builder_changelog) = prepared_builder.changelog {
+            if (args.add_changelog_entries || release_version.is_some())
+                && !changelog_entries.is_empty()
+            {
+                add_builder_changelog_entries(
+                    builder_changelog,
+                    &changelog_entries,
+                    release_version.as_ref(),
+                )?;
+            }
+        }
+    }
+
+    Ok(())
+}
 
-    let buildpacks = find_releasable_buildpacks(&repository_path)
+fn read_buildpacks(
+    repository_path: &Path,
+    exclude: &[Glob],
+) -> Result<BTreeMap<PathBuf, BuildpackDescriptor>> {
+    let buildpacks = find_releasable_buildpacks(repository_path, exclude)
         .map_err(Error::FindReleasableBuildpacks)?
         .into_iter()
         .map(|dir| {
@@ -50,73 +214,468 @@ pub(crate) fn execute(args: UpdateBuilderArgs) -> Result<()> {
         .collect::<Result<BTreeMap<_, _>>>()?;
 
     if buildpacks.is_empty() {
-        Err(Error::NoBuildpacks(repository_path))?;
+        Err(Error::NoBuildpacks(repository_path.to_path_buf()))?;
     }
 
-    let builder_files = args
-        .builders
+    Ok(buildpacks)
+}
+
+// The buildpack references to write to each builder, in the same order as the builder files.
+fn resolve_builder_references(
+    revert_to: Option<&str>,
+    verify_digests: bool,
+    buildpacks: &BTreeMap<PathBuf, BuildpackDescriptor>,
+    builder_repository_path: &Path,
+    builder_files: &[BuilderFile],
+) -> Result<Vec<Vec<BuildpackReference>>> {
+    match revert_to {
+        None => {
+            let buildpack_references = resolve_buildpack_uris(buildpacks, verify_digests)?;
+            Ok(vec![buildpack_references; builder_files.len()])
+        }
+        Some(revert_to) if Path::new(revert_to).is_file() => {
+            let buildpack_references = read_revert_mapping(Path::new(revert_to))?;
+            Ok(vec![buildpack_references; builder_files.len()])
+        }
+        Some(revision) => {
+            resolve_revision(builder_repository_path, revision)
+                .map_err(|e| Error::ResolvingRevertRevision(revision.to_string(), e))?;
+            let buildpack_ids = buildpacks
+                .values()
+                .map(|buildpack_descriptor| buildpack_descriptor.buildpack().id.clone())
+                .collect::<Vec<_>>();
+            builder_files
+                .iter()
+                .map(|builder_file| {
+                    read_references_at_revision(
+                        builder_repository_path,
+                        revision,
+                        &builder_file.path,
+                        &buildpack_ids,
+                    )
+                })
+                .collect()
+        }
+    }
+}
+
+fn read_revert_mapping(path: &Path) -> Result<Vec<BuildpackReference>> {
+    let contents = std::fs::read_to_string(path)
+        .map_err(|e| Error::ReadingRevertMapping(path.to_path_buf(), e))?;
+    let mapping = toml::from_str::<IndexMap<BuildpackId, RevertMappingEntry>>(&contents)
+        .map_err(|e| Error::ParsingRevertMapping(path.to_path_buf(), e))?;
+    Ok(mapping
+        .into_iter()
+        .map(|(buildpack_id, entry)| (buildpack_id, entry.version, entry.uri))
+        .collect())
+}
+
+// Reads the references a builder had to each of the given buildpacks as of a revision of the
+// builder repository. Buildpacks the builder didn't reference at that revision are left as they
+// are.
+fn read_references_at_revision(
+    builder_repository_path: &Path,
+    revision: &str,
+    builder_path: &Path,
+    buildpack_ids: &[BuildpackId],
+) -> Result<Vec<BuildpackReference>> {
+    let relative_path = builder_path
+        .strip_prefix(builder_repository_path)
+        .unwrap_or(builder_path);
+    let object_name = format!("{revision}:{}", relative_path.display());
+
+    let contents = read_file_at_revision(builder_repository_path, revision, relative_path)
+        .map_err(|e| Error::ReadingBuilderAtRevision(object_name.clone(), e))?
+        .ok_or_else(|| Error::MissingBuilderAtRevision(object_name.clone()))?;
+    let document = DocumentMut::from_str(&contents)
+        .map_err(|e| Error::ParsingBuilderAtRevision(object_name.clone(), e))?;
+
+    buildpack_ids
         .iter()
-        .map(|builder| {
-            read_builder_file(builder_repository_path.join(builder).join("builder.toml"))
+        .filter_map(|buildpack_id| {
+            let version = find_order_group_version(&document, buildpack_id)?;
+            let uri = find_buildpack_uri(&document, buildpack_id)?;
+            Some(
+                BuildpackVersion::try_from(version.to_string())
+                    .map(|version| (buildpack_id.clone(), version, uri.to_string()))
+                    .map_err(|_| {
+                        Error::InvalidBuildpackVersionAtRevision(
+                            object_name.clone(),
+                            version.to_string(),
+                        )
+                    }),
+            )
         })
-        .collect::<Result<Vec<_>>>()?;
+        .collect()
+}
 
-    if builder_files.is_empty() {
-        Err(Error::NoBuilderFiles(args.builders))?;
-    }
+fn find_order_group_version<'a>(
+    document: &'a DocumentMut,
+    buildpack_id: &BuildpackId,
+) -> Option<&'a str> {
+    document
+        .get("order")
+        .and_then(Item::as_array_of_tables)
+        .into_iter()
+        .flat_map(ArrayOfTables::iter)
+        .filter_map(|order| order.get("group").and_then(Item::as_array_of_tables))
+        .flat_map(ArrayOfTables::iter)
+        .find(|group| group.get("id").and_then(Item::as_str) == Some(buildpack_id.as_str()))
+        .and_then(|group| group.get("version"))
+        .and_then(Item::as_str)
+}
 
-    for mut builder_file in builder_files {
-        for (buildpack_dir, buildpack_descriptor) in &buildpacks {
-            let buildpack_path = buildpack_dir.join("buildpack.toml");
+fn find_buildpack_uri<'a>(
+    document: &'a DocumentMut,
+    buildpack_id: &BuildpackId,
+) -> Option<&'a str> {
+    document
+        .get("buildpacks")
+        .and_then(Item::as_array_of_tables)
+        .into_iter()
+        .flat_map(ArrayOfTables::iter)
+        .find(|buildpack| buildpack.get("id").and_then(Item::as_str) == Some(buildpack_id.as_str()))
+        .and_then(|buildpack| buildpack.get("uri"))
+        .and_then(Item::as_str)
+}
 
-            let buildpack_id = &buildpack_descriptor.buildpack().id;
+// Resolves the digest-pinned image URI of each buildpack's release. With `verify_digests`
+// the manifest behind each URI must also exist in the registry.
+fn resolve_buildpack_uris(
+    buildpacks: &BTreeMap<PathBuf, BuildpackDescriptor>,
+    verify_digests: bool,
+) -> Result<Vec<BuildpackReference>> {
+    let buildpacks = buildpacks.iter().collect::<Vec<_>>();
 
+    try_map_concurrently(
+        &buildpacks,
+        MAX_CONCURRENT_REQUESTS,
+        |(buildpack_dir, buildpack_descriptor)| {
+            let buildpack_path = buildpack_dir.join("buildpack.toml");
+            let buildpack_id = &buildpack_descriptor.buildpack().id;
             let buildpack_version = &buildpack_descriptor.buildpack().version;
 
             let docker_repository = read_image_repository_metadata(buildpack_descriptor).ok_or(
                 Error::MissingImageRepositoryMetadata(buildpack_path.clone()),
             )?;
 
-            let buildpack_uri =
-                calculate_digest(&format!("{docker_repository}:{buildpack_version}"))
-                    .map_err(|e| Error::CalculatingDigest(buildpack_path.clone(), e))
-                    .map(|digest| format!("docker://{docker_repository}@{digest}"))?;
+            let digest = calculate_digest(&format!("{docker_repository}:{buildpack_version}"))
+                .map_err(|e| Error::CalculatingDigest(buildpack_path.clone(), e))?;
 
-            update_builder_with_buildpack_info(
-                &mut builder_file.document,
-                buildpack_id,
-                buildpack_version,
-                &buildpack_uri,
-            )?;
-        }
+            if verify_digests {
+                verify_manifest_exists(&format!("{docker_repository}@{digest}"))
+                    .map_err(|e| Error::VerifyingDigest(buildpack_path.clone(), e))?;
+                eprintln!("✅️ Verified image: {docker_repository}@{digest}");
+            }
 
-        std::fs::write(&builder_file.path, builder_file.document.to_string())
-            .map_err(|e| Error::WritingBuilder(builder_file.path.clone(), e))?;
+            Ok((
+                buildpack_id.clone(),
+                buildpack_version.clone(),
+                format!("docker://{docker_repository}@{digest}"),
+            ))
+        },
+    )
+}
 
-        eprintln!("✅️ Updated builder: {}", builder_file.path.display());
+// Leaves out (with a warning) buildpacks that don't build for every target of the builder, since
+// the builder would then fail to build for the missing targets. Builders without targets or a
+// stack, and buildpacks that aren't in the repository (e.g.; from a revert mapping), aren't
+// checked.
+fn filter_compatible_references<'a>(
+    builder_file: &BuilderFile,
+    buildpack_references: &'a [BuildpackReference],
+    buildpacks: &BTreeMap<PathBuf, BuildpackDescriptor>,
+) -> Vec<&'a BuildpackReference> {
+    let Some(builder_targets) = read_builder_targets(&builder_file.document) else {
+        return buildpack_references.iter().collect();
+    };
+
+    buildpack_references
+        .iter()
+        .filter(|(buildpack_id, _, _)| {
+            let unsupported_targets = buildpacks
+                .values()
+                .find(|buildpack_descriptor| buildpack_descriptor.buildpack().id == *buildpack_id)
+                .map(|buildpack_descriptor| {
+                    find_unsupported_targets(
+                        &builder_targets,
+                        &read_buildpack_targets(buildpack_descriptor),
+                    )
+                })
+                .unwrap_or_default();
+            if !unsupported_targets.is_empty() {
+                eprintln!(
+                    "⚠️ Skipped `{buildpack_id}` since it doesn't build for the builder's {} target: {}",
+                    unsupported_targets.join(", "),
+                    builder_file.path.display()
+                );
+            }
+            unsupported_targets.is_empty()
+        })
+        .collect()
+}
+
+// Targets that aren't declared on both sides (e.g.; a target without a `variant`) match any value.
+fn find_unsupported_targets(
+    builder_targets: &[BuildpackTarget],
+    buildpack_targets: &[BuildpackTarget],
+) -> Vec<String> {
+    let matches = |a: &Option<String>, b: &Option<String>| a.is_none() || b.is_none() || a == b;
+
+    builder_targets
+        .iter()
+        .filter(|builder_target| {
+            !buildpack_targets.iter().any(|buildpack_target| {
+                matches(&builder_target.os, &buildpack_target.os)
+                    && matches(&builder_target.arch, &buildpack_target.arch)
+                    && matches(&builder_target.variant, &buildpack_target.variant)
+            })
+        })
+        .map(|target| {
+            [&target.os, &target.arch, &target.variant]
+                .into_iter()
+                .flatten()
+                .cloned()
+                .collect::<Vec<_>>()
+                .join("/")
+        })
+        .collect()
+}
+
+// Checks (and with `--migrate-schema` migrates) each builder file, and reads the version and
+// changelog of each builder that the arguments ask to update.
+fn prepare_builder_files(
+    builder_files: &mut [BuilderFile],
+    args: &UpdateBuilderArgs,
+) -> Result<Vec<PreparedBuilder>> {
+    let mut prepared_builders = vec![];
+    for builder_file in builder_files {
+        check_builder_structure(builder_file, args.lenient)?;
+        if args.migrate_schema && migrate_builder_schema(&mut builder_file.document) {
+            eprintln!(
+                "✅️ Migrated builder schema: {}",
+                builder_file.path.display()
+            );
+        }
+        prepared_builders.push(PreparedBuilder {
+            version: args
+                .bump_builder_version
+                .map(|_| read_builder_version(builder_file))
+                .transpose()?,
+            changelog: (args.add_changelog_entries || args.bump_builder_version.is_some())
+                .then(|| read_builder_changelog(&builder_file.path.with_file_name("CHANGELOG.md")))
+                .transpose()?,
+        });
     }
+    Ok(prepared_builders)
+}
+
+struct PreparedBuilder {
+    version: Option<(BuilderVersionLocation, Version)>,
+    changelog: Option<BuilderChangelog>,
+}
 
+// Sections that don't have the structure `update_builder_with_buildpack_info` expects
+// are an error unless `--lenient` is set, in which case they're skipped and reported.
+fn check_builder_structure(builder_file: &BuilderFile, lenient: bool) -> Result<()> {
+    let malformed_sections = find_malformed_sections(&builder_file.document);
+    if malformed_sections.is_empty() {
+        return Ok(());
+    }
+    if !lenient {
+        return Err(Error::MalformedBuilder(
+            builder_file.path.clone(),
+            malformed_sections,
+        ));
+    }
+    for malformed_section in malformed_sections {
+        eprintln!(
+            "⚠️ Skipped malformed section `{}` ({}): {}",
+            malformed_section.key,
+            malformed_section.found,
+            builder_file.path.display()
+        );
+    }
     Ok(())
 }
 
-fn read_builder_file(path: PathBuf) -> Result<BuilderFile> {
-    let contents =
-        std::fs::read_to_string(&path).map_err(|e| Error::ReadingBuilder(path.clone(), e))?;
-    let document =
-        DocumentMut::from_str(&contents).map_err(|e| Error::ParsingBuilder(path.clone(), e))?;
-    Ok(BuilderFile { path, document })
+fn find_malformed_sections(document: &DocumentMut) -> Vec<MalformedSection> {
+    let Some(order_list) = document.get("order").and_then(Item::as_array_of_tables) else {
+        return vec![MalformedSection {
+            key: "order".to_string(),
+            found: describe_item(document.get("order")),
+            suggestion: "[[order]]\n  [[order.group]]\n    id = \"<buildpack id>\"\n    version = \"<buildpack version>\"".to_string(),
+        }];
+    };
+
+    order_list
+        .iter()
+        .enumerate()
+        .filter(|(_, order)| {
+            order
+                .get("group")
+                .and_then(Item::as_array_of_tables)
+                .is_none()
+        })
+        .map(|(index, order)| MalformedSection {
+            key: format!("order[{index}].group"),
+            found: describe_item(order.get("group")),
+            suggestion:
+                "[[order.group]]\n  id = \"<buildpack id>\"\n  version = \"<buildpack version>\""
+                    .to_string(),
+        })
+        .collect()
+}
+
+fn describe_item(item: Option<&Item>) -> String {
+    match item {
+        None | Some(Item::None) => "key is missing".to_string(),
+        Some(item) => format!("expected an array of tables but found {}", item.type_name()),
+    }
+}
+
+// The builder's version is kept in `[metadata] version` of builder.toml, or else in a `VERSION`
+// file next to it.
+fn read_builder_version(builder_file: &BuilderFile) -> Result<(BuilderVersionLocation, Version)> {
+    if let Some(version) = builder_file
+        .document
+        .get("metadata")
+        .and_then(|metadata| metadata.get("version"))
+    {
+        let version = version.as_str().unwrap_or_default();
+        return Version::parse(version)
+            .map(|version| (BuilderVersionLocation::Metadata, version))
+            .map_err(|_| {
+                Error::InvalidBuilderVersion(builder_file.path.clone(), version.to_string())
+            });
+    }
+
+    let version_path = builder_file.path.with_file_name("VERSION");
+    if !version_path.exists() {
+        Err(Error::MissingBuilderVersion(builder_file.path.clone()))?;
+    }
+    let contents = std::fs::read_to_string(&version_path)
+        .map_err(|e| Error::ReadingBuilderVersion(version_path.clone(), e))?;
+    Version::parse(contents.trim())
+        .map(|version| (BuilderVersionLocation::File(version_path.clone()), version))
+        .map_err(|_| Error::InvalidBuilderVersion(version_path, contents.trim().to_string()))
 }
 
+fn bump_version(version: &Version, bump: BuilderVersionBump) -> Version {
+    match bump {
+        BuilderVersionBump::Patch => Version::new(version.major, version.minor, version.patch + 1),
+        BuilderVersionBump::Minor => Version::new(version.major, version.minor + 1, 0),
+    }
+}
+
+fn write_builder_version(
+    builder_file: &mut BuilderFile,
+    location: &BuilderVersionLocation,
+    version: &Version,
+) -> Result<Version> {
+    match location {
+        BuilderVersionLocation::Metadata => {
+            if let Some(version_value) = builder_file.document["metadata"]["version"].as_value_mut()
+            {
+                let decor = version_value.decor().clone();
+                *version_value = toml_edit::Value::from(version.to_string());
+                *version_value.decor_mut() = decor;
+            }
+        }
+        BuilderVersionLocation::File(path) => {
+            std::fs::write(path, format!("{version}\n"))
+                .map_err(|e| Error::WritingBuilderVersion(path.clone(), e))?;
+        }
+    }
+    eprintln!(
+        "✅️ Bumped builder version to {version}: {}",
+        builder_file.path.display()
+    );
+    Ok(version.clone())
+}
+
+// A builder changelog along with the link definitions at its end, which displaying the parsed
+// changelog leaves out.
+struct BuilderChangelog {
+    path: PathBuf,
+    changelog: Changelog,
+    link_definitions: String,
+}
+
+fn read_builder_changelog(changelog_path: &Path) -> Result<BuilderChangelog> {
+    let contents = std::fs::read_to_string(changelog_path)
+        .map_err(|e| Error::ReadingChangelog(changelog_path.to_path_buf(), e))?;
+    let changelog = Changelog::try_from(contents.as_str())
+        .map_err(|e| Error::ParsingChangelog(changelog_path.to_path_buf(), e))?;
+    let link_definitions = read_trailing_link_definitions(&contents)
+        .map_err(|e| Error::ParsingChangelog(changelog_path.to_path_buf(), e))?;
+    Ok(BuilderChangelog {
+        path: changelog_path.to_path_buf(),
+        changelog,
+        link_definitions,
+    })
+}
+
+// Adds the given entries to the `Unreleased` section of a builder changelog, under the
+// `Changed` group.
+//
+// When a release version is given, the unreleased entries (including the added ones) are
+// moved under a heading for that version so the builder is ready to be released.
+fn add_builder_changelog_entries(
+    builder_changelog: BuilderChangelog,
+    entries: &[String],
+    release_version: Option<&Version>,
+) -> Result<()> {
+    let BuilderChangelog {
+        path: changelog_path,
+        mut changelog,
+        link_definitions,
+    } = builder_changelog;
+
+    for entry in entries {
+        changelog.add_unreleased_entry("Changed", entry);
+    }
+
+    if let Some(version) = release_version {
+        let release_entry = ReleaseEntry {
+            version: version.clone(),
+            date: Utc::now(),
+            body: changelog.unreleased.take().unwrap_or_default(),
+            released_by: None,
+        };
+        changelog
+            .releases
+            .shift_insert(0, version.to_string(), release_entry);
+    }
+
+    let new_contents = if link_definitions.is_empty() {
+        changelog.to_string()
+    } else {
+        format!("{changelog}\n{link_definitions}\n")
+    };
+
+    std::fs::write(&changelog_path, new_contents)
+        .map_err(|e| Error::WritingChangelog(changelog_path.clone(), e))?;
+    eprintln!("✅️ Updated builder changelog: {}", changelog_path.display());
+    Ok(())
+}
+
+// Returns `true` if the builder referenced the buildpack at a different version than the
+// one given.
 fn update_builder_with_buildpack_info(
     document: &mut DocumentMut,
     buildpack_id: &BuildpackId,
     buildpack_version: &BuildpackVersion,
     buildpack_uri_with_sha: &str,
-) -> Result<()> {
+) -> bool {
     if is_buildpack_using_cnb_shim(document, buildpack_id) {
-        return Ok(());
+        return false;
     }
 
+    let mut updated = false;
+    let buildpack_uri =
+        format_buildpack_uri(buildpack_uri_with_sha, detect_builder_schema(document));
+
     document
         .get_mut("buildpacks")
         .and_then(Item::as_array_of_tables_mut)
@@ -129,34 +688,39 @@ fn update_builder_with_buildpack_info(
                 .filter(|value| value == &buildpack_id.as_str())
                 .is_some();
             if matches_id {
-                buildpack["uri"] = value(buildpack_uri_with_sha.to_string());
+                buildpack["uri"] = value(buildpack_uri.clone());
             }
         });
 
+    // malformed sections are reported by `check_builder_structure` so they're skipped here
     let order_list = document
         .get_mut("order")
         .and_then(Item::as_array_of_tables_mut)
-        .ok_or(Error::BuilderMissingRequiredKey("order".to_string()))?;
+        .into_iter()
+        .flat_map(ArrayOfTables::iter_mut);
 
-    for order in order_list.iter_mut() {
+    for order in order_list {
         let group_list = order
             .get_mut("group")
             .and_then(Item::as_array_of_tables_mut)
-            .ok_or(Error::BuilderMissingRequiredKey("group".to_string()))?;
+            .into_iter()
+            .flat_map(ArrayOfTables::iter_mut);
 
-        for group in group_list.iter_mut() {
+        for group in group_list {
             let matches_id = group
                 .get("id")
                 .and_then(Item::as_str)
                 .filter(|value| value == &buildpack_id.as_str())
                 .is_some();
             if matches_id {
-                group["version"] = value(buildpack_version.to_string());
+                let new_version = buildpack_version.to_string();
+                updated |= group.get("version").and_then(Item::as_str) != Some(&new_version);
+                group["version"] = value(new_version);
             }
         }
     }
 
-    Ok(())
+    updated
 }
 
 fn is_buildpack_using_cnb_shim(document: &DocumentMut, buildpack_id: &BuildpackId) -> bool {
@@ -190,10 +754,18 @@ fn is_buildpack_using_cnb_shim(document: &DocumentMut, buildpack_id: &BuildpackI
 
 #[cfg(test)]
 mod test {
-    use crate::commands::update_builder::command::update_builder_with_buildpack_info;
-    use libcnb_data::buildpack::BuildpackVersion;
+    use crate::builders::BuilderFile;
+    use crate::commands::update_builder::command::{
+        add_builder_changelog_entries, bump_version, find_buildpack_uri, find_malformed_sections,
+        find_order_group_version, find_unsupported_targets, read_builder_changelog,
+        read_builder_version, read_revert_mapping, update_builder_with_buildpack_info,
+        write_builder_version, BuilderVersionBump, BuilderVersionLocation,
+    };
+    use libcnb_data::buildpack::{BuildpackTarget, BuildpackVersion};
     use libcnb_data::buildpack_id;
+    use semver::Version;
     use std::str::FromStr;
+    use tempfile::tempdir;
     use toml_edit::DocumentMut;
 
     #[test]
@@ -229,16 +801,14 @@ mod test {
             &buildpack_id!("heroku/java"),
             &BuildpackVersion::try_from("0.6.10".to_string()).unwrap(),
             "docker://docker.io/heroku/buildpack-java@sha256:some-java-test-sha",
-        )
-        .unwrap();
+        );
 
         update_builder_with_buildpack_info(
             &mut document,
             &buildpack_id!("heroku/nodejs"),
             &BuildpackVersion::try_from("0.6.6".to_string()).unwrap(),
             "docker://docker.io/heroku/buildpack-nodejs@sha256:some-nodejs-test-sha",
-        )
-        .unwrap();
+        );
 
         assert_eq!(
             document.to_string(),
@@ -269,6 +839,50 @@ mod test {
         );
     }
 
+    #[test]
+    fn test_update_builder_contents_with_targets_layout() {
+        let toml = r#"
+[[targets]]
+  os = "linux"
+  arch = "amd64"
+
+[[buildpacks]]
+  id = "heroku/java"
+  uri = "docker.io/heroku/buildpack-java@sha256:21990393c93927b16f76c303ae007ea7e95502d52b0317ca773d4cd51e7a5682"
+
+[[order]]
+  [[order.group]]
+    id = "heroku/java"
+    version = "0.6.9"
+"#;
+        let mut document = DocumentMut::from_str(toml).unwrap();
+
+        update_builder_with_buildpack_info(
+            &mut document,
+            &buildpack_id!("heroku/java"),
+            &BuildpackVersion::try_from("0.6.10".to_string()).unwrap(),
+            "docker://docker.io/heroku/buildpack-java@sha256:some-java-test-sha",
+        );
+
+        assert_eq!(
+            document.to_string(),
+            r#"
+[[targets]]
+  os = "linux"
+  arch = "amd64"
+
+[[buildpacks]]
+  id = "heroku/java"
+  uri = "docker.io/heroku/buildpack-java@sha256:some-java-test-sha"
+
+[[order]]
+  [[order.group]]
+    id = "heroku/java"
+    version = "0.6.10"
+"#
+        );
+    }
+
     #[test]
     fn test_update_builder_contents_does_not_touch_cnb_shimmed_buildpacks() {
         let toml = r#"
@@ -288,8 +902,7 @@ mod test {
             &buildpack_id!("heroku/scala"),
             &BuildpackVersion::try_from("1.1.1".to_string()).unwrap(),
             "docker://docker.io/heroku/buildpack-scala@sha256:dd41aacd9ce11a11fdc3f0ba0bf4cd8a816fc56c634d30c2806998b5fce9534d",
-        )
-        .unwrap();
+        );
 
         assert_eq!(
             document.to_string(),
@@ -305,4 +918,314 @@ mod test {
 "#
         );
     }
+
+    #[test]
+    fn test_update_builder_contents_reports_if_buildpack_version_changed() {
+        let toml = r#"
+[[buildpacks]]
+  id = "heroku/java"
+  uri = "docker://docker.io/heroku/buildpack-java@sha256:21990393c93927b16f76c303ae007ea7e95502d52b0317ca773d4cd51e7a5682"
+
+[[order]]
+  [[order.group]]
+    id = "heroku/java"
+    version = "0.6.9"
+"#;
+        let mut document = DocumentMut::from_str(toml).unwrap();
+
+        assert!(update_builder_with_buildpack_info(
+            &mut document,
+            &buildpack_id!("heroku/java"),
+            &BuildpackVersion::try_from("0.6.10".to_string()).unwrap(),
+            "docker://docker.io/heroku/buildpack-java@sha256:some-java-test-sha",
+        ));
+
+        assert!(!update_builder_with_buildpack_info(
+            &mut document,
+            &buildpack_id!("heroku/java"),
+            &BuildpackVersion::try_from("0.6.10".to_string()).unwrap(),
+            "docker://docker.io/heroku/buildpack-java@sha256:some-java-test-sha",
+        ));
+
+        assert!(!update_builder_with_buildpack_info(
+            &mut document,
+            &buildpack_id!("heroku/nodejs"),
+            &BuildpackVersion::try_from("1.0.0".to_string()).unwrap(),
+            "docker://docker.io/heroku/buildpack-nodejs@sha256:some-nodejs-test-sha",
+        ));
+    }
+
+    #[test]
+    fn test_add_builder_changelog_entries() {
+        let builder_dir = tempdir().unwrap();
+        let changelog_path = builder_dir.path().join("CHANGELOG.md");
+        std::fs::write(
+            &changelog_path,
+            r"# Changelog
+
+## [Unreleased]
+
+### Added
+
+- Added a new run image.
+
+## [0.1.0] - 2024-01-01
+
+- Initial release.
+
+[unreleased]: https://github.com/heroku/cnb-builder-images/compare/v0.1.0...HEAD
+[0.1.0]: https://github.com/heroku/cnb-builder-images/releases/tag/v0.1.0
+",
+        )
+        .unwrap();
+
+        add_builder_changelog_entries(
+            read_builder_changelog(&changelog_path).unwrap(),
+            &["Updated `heroku/java` to `0.6.10`.".to_string()],
+            None,
+        )
+        .unwrap();
+
+        assert_eq!(
+            std::fs::read_to_string(&changelog_path).unwrap(),
+            r"# Changelog
+
+All notable changes to this project will be documented in this file.
+
+The format is based on [Keep a Changelog](https://keepachangelog.com/en/1.1.0/),
+and this project adheres to [Semantic Versioning](https://semver.org/spec/v2.0.0.html).
+
+## [Unreleased]
+
+### Added
+
+- Added a new run image.
+
+### Changed
+
+- Updated `heroku/java` to `0.6.10`.
+
+## [0.1.0] - 2024-01-01
+
+- Initial release.
+
+[unreleased]: https://github.com/heroku/cnb-builder-images/compare/v0.1.0...HEAD
+[0.1.0]: https://github.com/heroku/cnb-builder-images/releases/tag/v0.1.0
+"
+        );
+    }
+
+    #[test]
+    fn test_add_builder_changelog_entries_with_release_version() {
+        let builder_dir = tempdir().unwrap();
+        let changelog_path = builder_dir.path().join("CHANGELOG.md");
+        std::fs::write(
+            &changelog_path,
+            "# Changelog\n\n## [Unreleased]\n\n## [0.1.0] - 2024-01-01\n\n- Initial release.\n",
+        )
+        .unwrap();
+
+        add_builder_changelog_entries(
+            read_builder_changelog(&changelog_path).unwrap(),
+            &["Updated `heroku/java` to `0.6.10`.".to_string()],
+            Some(&Version::new(0, 2, 0)),
+        )
+        .unwrap();
+
+        let contents = std::fs::read_to_string(&changelog_path).unwrap();
+        let release = contents
+            .split_once("## [Unreleased]\n\n## [0.2.0] - ")
+            .map(|(_, release)| release)
+            .unwrap();
+        assert!(release
+            .contains("\n\n### Changed\n\n- Updated `heroku/java` to `0.6.10`.\n\n## [0.1.0]"));
+    }
+
+    #[test]
+    fn test_bump_builder_version_in_metadata() {
+        let mut builder_file = BuilderFile {
+            path: "builder.toml".into(),
+            document: DocumentMut::from_str("[metadata]\nversion = \"1.2.3\" # comment\n").unwrap(),
+        };
+
+        let (location, version) = read_builder_version(&builder_file).unwrap();
+        assert_eq!(location, BuilderVersionLocation::Metadata);
+        assert_eq!(version, Version::new(1, 2, 3));
+        assert_eq!(
+            bump_version(&version, BuilderVersionBump::Minor),
+            Version::new(1, 3, 0)
+        );
+
+        write_builder_version(
+            &mut builder_file,
+            &location,
+            &bump_version(&version, BuilderVersionBump::Patch),
+        )
+        .unwrap();
+        assert_eq!(
+            builder_file.document.to_string(),
+            "[metadata]\nversion = \"1.2.4\" # comment\n"
+        );
+    }
+
+    #[test]
+    fn test_bump_builder_version_in_version_file() {
+        let builder_dir = tempdir().unwrap();
+        let mut builder_file = BuilderFile {
+            path: builder_dir.path().join("builder.toml"),
+            document: DocumentMut::new(),
+        };
+
+        assert!(read_builder_version(&builder_file).is_err());
+
+        std::fs::write(builder_dir.path().join("VERSION"), "0.9.1\n").unwrap();
+        let (location, version) = read_builder_version(&builder_file).unwrap();
+        write_builder_version(
+            &mut builder_file,
+            &location,
+            &bump_version(&version, BuilderVersionBump::Patch),
+        )
+        .unwrap();
+        assert_eq!(
+            std::fs::read_to_string(builder_dir.path().join("VERSION")).unwrap(),
+            "0.9.2\n"
+        );
+    }
+
+    #[test]
+    fn test_find_malformed_sections() {
+        let toml = r#"
+[[order]]
+  [[order.group]]
+    id = "heroku/java"
+    version = "0.6.9"
+
+[[order]]
+  group = "heroku/nodejs"
+"#;
+        let mut document = DocumentMut::from_str(toml).unwrap();
+
+        let malformed_sections = find_malformed_sections(&document);
+        assert_eq!(malformed_sections.len(), 1);
+        assert_eq!(malformed_sections[0].key, "order[1].group");
+        assert_eq!(
+            malformed_sections[0].found,
+            "expected an array of tables but found string"
+        );
+
+        assert!(update_builder_with_buildpack_info(
+            &mut document,
+            &buildpack_id!("heroku/java"),
+            &BuildpackVersion::try_from("0.6.10".to_string()).unwrap(),
+            "docker://docker.io/heroku/buildpack-java@sha256:some-java-test-sha",
+        ));
+        assert_eq!(
+            document.to_string(),
+            r#"
+[[order]]
+  [[order.group]]
+    id = "heroku/java"
+    version = "0.6.10"
+
+[[order]]
+  group = "heroku/nodejs"
+"#
+        );
+    }
+
+    #[test]
+    fn test_find_malformed_sections_when_order_is_missing() {
+        let document = DocumentMut::from_str("[buildpacks]\n").unwrap();
+        let malformed_sections = find_malformed_sections(&document);
+        assert_eq!(malformed_sections.len(), 1);
+        assert_eq!(malformed_sections[0].key, "order");
+        assert_eq!(malformed_sections[0].found, "key is missing");
+    }
+
+    #[test]
+    fn test_find_buildpack_references() {
+        let document = DocumentMut::from_str(
+            r#"
+[[buildpacks]]
+  id = "heroku/java"
+  uri = "docker://docker.io/heroku/buildpack-java@sha256:some-java-test-sha"
+
+[[order]]
+  [[order.group]]
+    id = "heroku/java"
+    version = "0.6.9"
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            find_order_group_version(&document, &buildpack_id!("heroku/java")),
+            Some("0.6.9")
+        );
+        assert_eq!(
+            find_buildpack_uri(&document, &buildpack_id!("heroku/java")),
+            Some("docker://docker.io/heroku/buildpack-java@sha256:some-java-test-sha")
+        );
+        assert_eq!(
+            find_order_group_version(&document, &buildpack_id!("heroku/nodejs")),
+            None
+        );
+    }
+
+    #[test]
+    fn test_read_revert_mapping() {
+        let dir = tempdir().unwrap();
+        let mapping_path = dir.path().join("known-good.toml");
+        std::fs::write(
+            &mapping_path,
+            r#"
+["heroku/java"]
+version = "0.6.9"
+uri = "docker://docker.io/heroku/buildpack-java@sha256:some-java-test-sha"
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            read_revert_mapping(&mapping_path).unwrap(),
+            vec![(
+                buildpack_id!("heroku/java"),
+                BuildpackVersion::try_from("0.6.9".to_string()).unwrap(),
+                "docker://docker.io/heroku/buildpack-java@sha256:some-java-test-sha".to_string()
+            )]
+        );
+
+        std::fs::write(&mapping_path, "[\"heroku/java\"]\nversion = \"0.6.9\"\n").unwrap();
+        assert!(read_revert_mapping(&mapping_path).is_err());
+    }
+
+    #[test]
+    fn test_find_unsupported_targets() {
+        let target = |os: &str, arch: &str| BuildpackTarget {
+            os: Some(os.to_string()),
+            arch: Some(arch.to_string()),
+            variant: None,
+            distros: vec![],
+        };
+        let multi_arch = [target("linux", "amd64"), target("linux", "arm64")];
+
+        assert!(find_unsupported_targets(&[target("linux", "amd64")], &multi_arch).is_empty());
+        assert!(find_unsupported_targets(&multi_arch, &multi_arch).is_empty());
+        assert_eq!(
+            find_unsupported_targets(&multi_arch, &[target("linux", "arm64")]),
+            vec!["linux/amd64".to_string()]
+        );
+        assert_eq!(
+            find_unsupported_targets(
+                &[target("linux", "amd64")],
+                &[BuildpackTarget {
+                    os: Some("linux".to_string()),
+                    arch: None,
+                    variant: None,
+                    distros: vec![],
+                }]
+            ),
+            Vec::<String>::new()
+        );
+    }
 }
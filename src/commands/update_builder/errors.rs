@@ -1,6 +1,11 @@
+use crate::builders::{ReadBuilderFileError, ReadBuildersFileError};
 use crate::buildpacks::{
-    CalculateDigestError, FindReleasableBuildpacksError, ReadBuildpackDescriptorError,
+    CalculateDigestError, FindReleasableBuildpacksError, InvalidImageRepositoryError,
+    ReadBuildpackDescriptorError, VerifyManifestError,
 };
+use crate::changelog::ChangelogError;
+use crate::git::GitError;
+use crate::locking::LockFileError;
 use std::path::PathBuf;
 
 #[derive(Debug, thiserror::Error)]
@@ -13,10 +18,12 @@ pub(crate) enum Error {
     ReadBuildpackDescriptor(ReadBuildpackDescriptorError),
     #[error("No buildpacks were found in the given directory\nPath: {0}")]
     NoBuildpacks(PathBuf),
-    #[error("Could not read builder\nPath: {0}\nError: {1}")]
-    ReadingBuilder(PathBuf, #[source] std::io::Error),
-    #[error("Could not parse builder\nPath: {0}\nError: {1}")]
-    ParsingBuilder(PathBuf, #[source] toml_edit::TomlError),
+    #[error(transparent)]
+    LockFile(LockFileError),
+    #[error(transparent)]
+    ReadBuilderFile(ReadBuilderFileError),
+    #[error(transparent)]
+    ReadBuildersFile(ReadBuildersFileError),
     #[error("Error writing builder\nPath: {0}\nError: {1}")]
     WritingBuilder(PathBuf, #[source] std::io::Error),
     #[error("No builder.toml files found in the given builder directories\n{}", list_builders(.0))]
@@ -25,10 +32,69 @@ pub(crate) enum Error {
         "The following buildpack is missing the metadata.release.image.repository entry\nPath: {0}"
     )]
     MissingImageRepositoryMetadata(PathBuf),
+    #[error(transparent)]
+    InvalidImageRepository(InvalidImageRepositoryError),
     #[error("Failed to calculate digest for buildpack\nPath: {0}\nError: {1}")]
     CalculatingDigest(PathBuf, #[source] CalculateDigestError),
-    #[error("Missing required key `{0}` in builder")]
-    BuilderMissingRequiredKey(String),
+    #[error("Failed to verify image for buildpack\nPath: {0}\nError: {1}")]
+    VerifyingDigest(PathBuf, #[source] VerifyManifestError),
+    #[error("Builder has sections that can't be updated (use --lenient to skip them)\nPath: {}\n{}", .0.display(), list_malformed_sections(.1))]
+    MalformedBuilder(PathBuf, Vec<MalformedSection>),
+    #[error("Builder has no version to bump, expected `[metadata] version` in builder.toml or a VERSION file\nPath: {0}")]
+    MissingBuilderVersion(PathBuf),
+    #[error("Invalid builder version `{1}`, expected X.Y.Z\nPath: {0}")]
+    InvalidBuilderVersion(PathBuf, String),
+    #[error("Could not read builder version\nPath: {0}\nError: {1}")]
+    ReadingBuilderVersion(PathBuf, #[source] std::io::Error),
+    #[error("Could not write builder version\nPath: {0}\nError: {1}")]
+    WritingBuilderVersion(PathBuf, #[source] std::io::Error),
+    #[error("Could not read revert mapping\nPath: {0}\nError: {1}")]
+    ReadingRevertMapping(PathBuf, #[source] std::io::Error),
+    #[error("Could not parse revert mapping, expected tables of `version` and `uri` keyed by buildpack id\nPath: {0}\nError: {1}")]
+    ParsingRevertMapping(PathBuf, #[source] toml::de::Error),
+    #[error("Could not resolve revision `{0}` of the builder repository\nError: {1}")]
+    ResolvingRevertRevision(String, #[source] GitError),
+    #[error("Could not read builder\nPath: {0}\nError: {1}")]
+    ReadingBuilderAtRevision(String, #[source] GitError),
+    #[error("Builder doesn't exist at the revision to revert to\nPath: {0}")]
+    MissingBuilderAtRevision(String),
+    #[error("Could not parse builder\nPath: {0}\nError: {1}")]
+    ParsingBuilderAtRevision(String, #[source] toml_edit::TomlError),
+    #[error("Invalid buildpack version `{1}` in builder\nPath: {0}")]
+    InvalidBuildpackVersionAtRevision(String, String),
+    #[error("Could not read changelog\nPath: {0}\nError: {1}")]
+    ReadingChangelog(PathBuf, #[source] std::io::Error),
+    #[error("Could not parse changelog\nPath: {0}\nError: {1}")]
+    ParsingChangelog(PathBuf, #[source] ChangelogError),
+    #[error("Could not write changelog\nPath: {0}\nError: {1}")]
+    WritingChangelog(PathBuf, #[source] std::io::Error),
+}
+
+#[derive(Debug)]
+pub(crate) struct MalformedSection {
+    pub(crate) key: String,
+    pub(crate) found: String,
+    pub(crate) suggestion: String,
+}
+
+fn list_malformed_sections(malformed_sections: &[MalformedSection]) -> String {
+    malformed_sections
+        .iter()
+        .map(|malformed_section| {
+            format!(
+                "• `{}`: {}\n  Expected structure:\n{}",
+                malformed_section.key,
+                malformed_section.found,
+                malformed_section
+                    .suggestion
+                    .lines()
+                    .map(|line| format!("    {line}"))
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
 }
 
 fn list_builders(builders: &[String]) -> String {
@@ -0,0 +1,186 @@
+use crate::buildpacks::{
+    buildpack_changelog_path, descriptor_path, find_releasable_buildpacks,
+    read_buildpack_descriptor,
+};
+use crate::commands::watch::errors::Error;
+use crate::config::read_config;
+use crate::Cli;
+use clap::{CommandFactory, Parser};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::{Duration, SystemTime};
+
+type Result<T> = std::result::Result<T, Error>;
+
+#[derive(Parser, Debug)]
+#[command(author, version, about = "Re-runs a command whenever a buildpack.toml or CHANGELOG.md changes", long_about = None, disable_version_flag = true)]
+pub(crate) struct WatchArgs {
+    /// Milliseconds between checks for changed files.
+    #[arg(long, default_value_t = 500)]
+    pub(crate) interval: u64,
+    /// Command to re-run, with its arguments (e.g.; `generate-changelog --unreleased`).
+    #[arg(required = true, trailing_var_arg = true, allow_hyphen_values = true)]
+    pub(crate) command: Vec<String>,
+}
+
+// When each watched file was last modified, or `None` if it has been removed.
+type Snapshot = BTreeMap<PathBuf, Option<SystemTime>>;
+
+pub(crate) fn execute(args: &WatchArgs) -> Result<()> {
+    let current_dir = std::env::current_dir().map_err(Error::GetCurrentDir)?;
+    let executable = std::env::current_exe().map_err(Error::CurrentExe)?;
+    validate_command(&current_dir, &args.command)?;
+
+    let mut snapshot = take_snapshot(&current_dir)?;
+    eprintln!("👀 Watching {} files, press Ctrl+C to stop", snapshot.len());
+    run_command(&executable, &current_dir, &args.command)?;
+
+    loop {
+        std::thread::sleep(Duration::from_millis(args.interval));
+        let new_snapshot = take_snapshot(&current_dir)?;
+        let changed_files = find_changed_files(&snapshot, &new_snapshot);
+        if changed_files.is_empty() {
+            continue;
+        }
+        for changed_file in changed_files {
+            let path = changed_file
+                .strip_prefix(&current_dir)
+                .unwrap_or(changed_file);
+            eprintln!("✏️ Changed: {}", path.display());
+        }
+        snapshot = new_snapshot;
+        run_command(&executable, &current_dir, &args.command)?;
+    }
+}
+
+// Mistakes in the watched command are reported once up front instead of on every change. Options
+// set in the config file count, the same as when the command runs.
+fn validate_command(dir: &Path, command: &[String]) -> Result<()> {
+    if command.first().is_some_and(|name| name == "watch") {
+        Err(Error::WatchingWatch)?;
+    }
+    read_config(dir)
+        .and_then(|config| config.apply(Cli::command()))
+        .map_err(Error::Config)?
+        .try_get_matches_from(std::iter::once("actions").chain(command.iter().map(String::as_str)))
+        .map(|_| ())
+        .map_err(|e| Error::InvalidCommand(command.join(" "), e.render().to_string()))
+}
+
+// Buildpacks are looked up again every time so added buildpacks (or changelogs moved with
+// `[metadata.release] changelog`) are picked up. A buildpack.toml that can't be read (e.g.; while
+// it's being edited) is still watched along with the default changelog next to it.
+fn take_snapshot(dir: &Path) -> Result<Snapshot> {
+    let buildpack_dirs =
+        find_releasable_buildpacks(dir, &[]).map_err(Error::FindReleasableBuildpacks)?;
+    Ok(buildpack_dirs
+        .iter()
+        .flat_map(|buildpack_dir| {
+            let changelog_path = read_buildpack_descriptor(buildpack_dir).map_or_else(
+                |_| buildpack_dir.join("CHANGELOG.md"),
+                |descriptor| buildpack_changelog_path(buildpack_dir, &descriptor),
+            );
+            [descriptor_path(buildpack_dir), changelog_path]
+        })
+        .map(|path| {
+            let modified = std::fs::metadata(&path)
+                .and_then(|metadata| metadata.modified())
+                .ok();
+            (path, modified)
+        })
+        .collect())
+}
+
+fn find_changed_files<'a>(snapshot: &'a Snapshot, new_snapshot: &'a Snapshot) -> Vec<&'a Path> {
+    let mut changed_files = new_snapshot
+        .iter()
+        .filter(|(path, modified)| snapshot.get(*path) != Some(modified))
+        .chain(
+            snapshot
+                .iter()
+                .filter(|(path, _)| !new_snapshot.contains_key(*path)),
+        )
+        .map(|(path, _)| path.as_path())
+        .collect::<Vec<_>>();
+    changed_files.sort();
+    changed_files
+}
+
+// The command's outputs are printed instead of being written to the files of a GitHub Actions
+// step, and a failing command doesn't stop the watch.
+fn run_command(executable: &Path, current_dir: &Path, command: &[String]) -> Result<()> {
+    let name = command.join(" ");
+    eprintln!("▶️ Running `actions {name}`");
+    let status = Command::new(executable)
+        .args(command)
+        .current_dir(current_dir)
+        .env_remove("GITHUB_OUTPUT")
+        .env_remove("GITHUB_STEP_SUMMARY")
+        .status()
+        .map_err(|e| Error::CommandFailure(name.clone(), e))?;
+    if status.success() {
+        eprintln!("✅️ `actions {name}` succeeded");
+    } else {
+        eprintln!("❌ `actions {name}` failed ({status})");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use crate::commands::watch::command::{find_changed_files, validate_command, Snapshot};
+    use crate::commands::watch::errors::Error;
+    use std::path::{Path, PathBuf};
+    use std::time::{Duration, SystemTime};
+
+    #[test]
+    fn test_find_changed_files() {
+        let modified = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let snapshot = Snapshot::from([
+            (PathBuf::from("a/buildpack.toml"), Some(modified)),
+            (PathBuf::from("a/CHANGELOG.md"), Some(modified)),
+            (PathBuf::from("b/buildpack.toml"), Some(modified)),
+            (PathBuf::from("b/CHANGELOG.md"), Some(modified)),
+        ]);
+        let new_snapshot = Snapshot::from([
+            (PathBuf::from("a/buildpack.toml"), Some(modified)),
+            (
+                PathBuf::from("a/CHANGELOG.md"),
+                Some(modified + Duration::from_secs(1)),
+            ),
+            (PathBuf::from("b/buildpack.toml"), Some(modified)),
+            (PathBuf::from("b/CHANGELOG.md"), None),
+            (PathBuf::from("c/buildpack.toml"), Some(modified)),
+        ]);
+
+        assert_eq!(
+            find_changed_files(&snapshot, &new_snapshot),
+            vec![
+                Path::new("a/CHANGELOG.md"),
+                Path::new("b/CHANGELOG.md"),
+                Path::new("c/buildpack.toml")
+            ]
+        );
+        assert!(find_changed_files(&snapshot, &snapshot).is_empty());
+    }
+
+    #[test]
+    fn test_validate_command() {
+        let dir = tempfile::tempdir().unwrap();
+        let command = |args: &[&str]| args.iter().map(ToString::to_string).collect::<Vec<_>>();
+        assert!(validate_command(
+            dir.path(),
+            &command(&["generate-changelog", "--unreleased"])
+        )
+        .is_ok());
+        assert!(matches!(
+            validate_command(dir.path(), &command(&["generate-changelog", "--released"])),
+            Err(Error::InvalidCommand(_, _))
+        ));
+        assert!(matches!(
+            validate_command(dir.path(), &command(&["watch", "generate-changelog"])),
+            Err(Error::WatchingWatch)
+        ));
+    }
+}
@@ -0,0 +1,20 @@
+use crate::buildpacks::FindReleasableBuildpacksError;
+use crate::config::ConfigError;
+
+#[derive(Debug, thiserror::Error)]
+pub(crate) enum Error {
+    #[error("Failed to get current directory\nError: {0}")]
+    GetCurrentDir(#[source] std::io::Error),
+    #[error("Failed to locate the current executable\nError: {0}")]
+    CurrentExe(#[source] std::io::Error),
+    #[error(transparent)]
+    FindReleasableBuildpacks(FindReleasableBuildpacksError),
+    #[error(transparent)]
+    Config(ConfigError),
+    #[error("Invalid command to watch `actions {0}`\n{1}")]
+    InvalidCommand(String, String),
+    #[error("Command `actions watch` can't watch itself")]
+    WatchingWatch,
+    #[error("Failed to execute `actions {0}`\nError: {1}")]
+    CommandFailure(String, #[source] std::io::Error),
+}
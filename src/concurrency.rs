@@ -0,0 +1,116 @@
+use std::num::NonZeroUsize;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::thread;
+
+// Network calls (e.g.; `crane` and `gh`) are run as subprocesses so a handful of threads is
+// enough to overlap them without tripping registry or API rate limits.
+pub(crate) const MAX_CONCURRENT_REQUESTS: usize = 4;
+
+// Applies `f` to every item on a pool of at most `limit` threads, returning the results in the
+// same order as the items. Each thread takes the next item as soon as it's done with its last
+// one, so a slow item only holds up its own thread. Once an item fails no further items are
+// started, and the first error (by item order) is returned after the items already started have
+// finished.
+pub(crate) fn try_map_concurrently<T, R, E, F>(items: &[T], limit: usize, f: F) -> Result<Vec<R>, E>
+where
+    T: Sync,
+    R: Send,
+    E: Send,
+    F: Fn(&T) -> Result<R, E> + Sync,
+{
+    let limit = NonZeroUsize::new(limit.min(items.len())).map_or(1, NonZeroUsize::get);
+    let next_index = AtomicUsize::new(0);
+    let failed = AtomicBool::new(false);
+
+    let mut results = thread::scope(|scope| {
+        (0..limit)
+            .map(|_| {
+                scope.spawn(|| {
+                    let mut worker_results = vec![];
+                    while !failed.load(Ordering::SeqCst) {
+                        let index = next_index.fetch_add(1, Ordering::SeqCst);
+                        let Some(item) = items.get(index) else {
+                            break;
+                        };
+                        let result = f(item);
+                        if result.is_err() {
+                            failed.store(true, Ordering::SeqCst);
+                        }
+                        worker_results.push((index, result));
+                    }
+                    worker_results
+                })
+            })
+            .collect::<Vec<_>>()
+            .into_iter()
+            .flat_map(|handle| {
+                handle
+                    .join()
+                    .unwrap_or_else(|payload| std::panic::resume_unwind(payload))
+            })
+            .collect::<Vec<_>>()
+    });
+
+    // items are handed out in order, so every item before a failed one has finished
+    results.sort_by_key(|(index, _)| *index);
+    results.into_iter().map(|(_, result)| result).collect()
+}
+
+#[cfg(test)]
+mod test {
+    use crate::concurrency::try_map_concurrently;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Mutex;
+    use std::time::Duration;
+
+    #[test]
+    fn test_try_map_concurrently_preserves_order() {
+        let items = (0..10).collect::<Vec<_>>();
+        let results = try_map_concurrently(&items, 3, |item| Ok::<_, ()>(item * 2)).unwrap();
+        assert_eq!(results, (0..10).map(|item| item * 2).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_try_map_concurrently_stops_after_first_failure() {
+        let items = (0..10).collect::<Vec<_>>();
+        let calls = AtomicUsize::new(0);
+        let result = try_map_concurrently(&items, 1, |item| {
+            calls.fetch_add(1, Ordering::SeqCst);
+            if *item >= 3 {
+                Err(*item)
+            } else {
+                Ok(*item)
+            }
+        });
+        assert_eq!(result, Err(3));
+        assert_eq!(calls.load(Ordering::SeqCst), 4);
+
+        let calls = AtomicUsize::new(0);
+        let result = try_map_concurrently(&items, 2, |item| {
+            calls.fetch_add(1, Ordering::SeqCst);
+            if *item == 0 {
+                Err(*item)
+            } else {
+                std::thread::sleep(Duration::from_millis(50));
+                Ok(*item)
+            }
+        });
+        assert_eq!(result, Err(0));
+        assert!(calls.load(Ordering::SeqCst) <= 2);
+    }
+
+    #[test]
+    fn test_try_map_concurrently_keeps_going_around_a_slow_item() {
+        let items = (0..10).collect::<Vec<_>>();
+        let finished = Mutex::new(vec![]);
+        try_map_concurrently(&items, 2, |item| {
+            if *item == 0 {
+                std::thread::sleep(Duration::from_millis(200));
+            }
+            finished.lock().unwrap().push(*item);
+            Ok::<_, ()>(())
+        })
+        .unwrap();
+        assert_eq!(finished.into_inner().unwrap().last(), Some(&0));
+    }
+}
@@ -0,0 +1,293 @@
+use clap::builder::Resettable;
+use clap::Command;
+use std::collections::{BTreeMap, HashSet};
+use std::path::{Path, PathBuf};
+use toml::{Table, Value};
+
+// Repository-level defaults for command options so workflows don't have to pass the same flags
+// to every command. Options given on the command line always take precedence.
+pub(crate) const CONFIG_FILE: &str = ".github/buildpack-actions.toml";
+const CARGO_FILE: &str = "Cargo.toml";
+
+// Top-level keys are defaults for every command that has an option with that name (e.g.;
+// `repository_url` or `exclude`). A table named after a command (e.g.; `[prepare-release]`)
+// holds defaults for that command only and overrides the top-level ones.
+#[derive(Debug, Default)]
+pub(crate) struct Config {
+    source: PathBuf,
+    values: Table,
+}
+
+// Reads `.github/buildpack-actions.toml` or, if that doesn't exist, the
+// `[workspace.metadata.buildpack-actions]` table of the root `Cargo.toml`. It isn't named
+// `release` since cargo-release already reads `[workspace.metadata.release]`.
+pub(crate) fn read_config(dir: &Path) -> Result<Config, ConfigError> {
+    let config_path = dir.join(CONFIG_FILE);
+    if config_path.exists() {
+        let values = read_toml_table(&config_path)?;
+        return Ok(Config {
+            source: config_path,
+            values,
+        });
+    }
+
+    let cargo_path = dir.join(CARGO_FILE);
+    if cargo_path.exists() {
+        let values = read_toml_table(&cargo_path)?
+            .get("workspace")
+            .and_then(|workspace| workspace.get("metadata"))
+            .and_then(|metadata| metadata.get("buildpack-actions"))
+            .and_then(Value::as_table)
+            .cloned()
+            .unwrap_or_default();
+        return Ok(Config {
+            source: cargo_path,
+            values,
+        });
+    }
+
+    Ok(Config::default())
+}
+
+fn read_toml_table(path: &Path) -> Result<Table, ConfigError> {
+    let contents =
+        std::fs::read_to_string(path).map_err(|e| ConfigError::Reading(path.to_path_buf(), e))?;
+    contents
+        .parse::<Table>()
+        .map_err(|e| ConfigError::Parsing(path.to_path_buf(), e))
+}
+
+impl Config {
+    // Sets the configured values as the defaults of the matching command options.
+    pub(crate) fn apply(&self, mut command: Command) -> Result<Command, ConfigError> {
+        let defaults = self.option_defaults(&command)?;
+        for (command_name, option_id, values) in defaults {
+            command = command.mut_subcommand(command_name, |subcommand| {
+                // a configured default counts as providing the option
+                subcommand.mut_arg(option_id, |arg| {
+                    arg.default_values(values)
+                        .required(false)
+                        .required_unless_present(Resettable::Reset)
+                })
+            });
+        }
+        Ok(command)
+    }
+
+    // Resolves each configured value to the command and option it is a default for, as
+    // `(command name, option id, values)`.
+    fn option_defaults(
+        &self,
+        command: &Command,
+    ) -> Result<Vec<(String, String, Vec<String>)>, ConfigError> {
+        for (key, value) in &self.values {
+            if value.is_table() && command.find_subcommand(key).is_none() {
+                Err(ConfigError::UnknownCommand(
+                    self.source.clone(),
+                    key.clone(),
+                ))?;
+            }
+        }
+
+        let mut defaults = vec![];
+        let mut applied_keys = HashSet::new();
+        for subcommand in command.get_subcommands() {
+            let command_name = subcommand.get_name();
+            let command_values = self.values.get(command_name).and_then(Value::as_table);
+
+            let mut values = self
+                .values
+                .iter()
+                .filter(|(_, value)| !value.is_table())
+                .collect::<BTreeMap<_, _>>();
+            values.extend(command_values.into_iter().flatten());
+
+            for (key, value) in values {
+                let option_name = key.replace('_', "-");
+                let Some(arg) = subcommand
+                    .get_arguments()
+                    .find(|arg| arg.get_long() == Some(option_name.as_str()))
+                else {
+                    if command_values.is_some_and(|table| table.contains_key(key)) {
+                        Err(ConfigError::UnknownOption(
+                            self.source.clone(),
+                            format!("{command_name}.{key}"),
+                        ))?;
+                    }
+                    continue;
+                };
+                let option_values = option_values(value)
+                    .ok_or_else(|| ConfigError::InvalidValue(self.source.clone(), key.clone()))?;
+                defaults.push((
+                    command_name.to_string(),
+                    arg.get_id().to_string(),
+                    option_values,
+                ));
+                applied_keys.insert(key.as_str());
+            }
+        }
+
+        if let Some((key, _)) = self
+            .values
+            .iter()
+            .find(|(key, value)| !value.is_table() && !applied_keys.contains(key.as_str()))
+        {
+            Err(ConfigError::UnknownOption(self.source.clone(), key.clone()))?;
+        }
+
+        Ok(defaults)
+    }
+}
+
+fn option_values(value: &Value) -> Option<Vec<String>> {
+    match value {
+        Value::Array(values) => values.iter().map(option_value).collect(),
+        _ => option_value(value).map(|value| vec![value]),
+    }
+}
+
+fn option_value(value: &Value) -> Option<String> {
+    match value {
+        Value::String(value) => Some(value.clone()),
+        Value::Integer(value) => Some(value.to_string()),
+        Value::Float(value) => Some(value.to_string()),
+        Value::Boolean(value) => Some(value.to_string()),
+        Value::Datetime(value) => Some(value.to_string()),
+        Value::Array(_) | Value::Table(_) => None,
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub(crate) enum ConfigError {
+    #[error("Could not read config file\nPath: {0}\nError: {1}")]
+    Reading(PathBuf, #[source] std::io::Error),
+    #[error("Could not parse config file\nPath: {0}\nError: {1}")]
+    Parsing(PathBuf, #[source] toml::de::Error),
+    #[error("Unknown command `{1}` in config file\nPath: {0}")]
+    UnknownCommand(PathBuf, String),
+    #[error("Unknown option `{1}` in config file\nPath: {0}")]
+    UnknownOption(PathBuf, String),
+    #[error("Invalid value for `{1}` in config file, expected a string, number, boolean, or an array of those\nPath: {0}")]
+    InvalidValue(PathBuf, String),
+}
+
+#[cfg(test)]
+mod test {
+    use crate::commands::prepare_release::command::BumpCoordinate;
+    use crate::config::{read_config, ConfigError, CONFIG_FILE};
+    use crate::{Cli, Command};
+    use clap::{CommandFactory, FromArgMatches};
+    use std::path::Path;
+
+    fn parse_with_config(dir: &Path, arguments: &[&str]) -> Cli {
+        let command = read_config(dir).unwrap().apply(Cli::command()).unwrap();
+        let matches = command.try_get_matches_from(arguments).unwrap();
+        Cli::from_arg_matches(&matches).unwrap()
+    }
+
+    #[test]
+    fn test_config_file_supplies_option_defaults() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dir.path().join(".github")).unwrap();
+        std::fs::write(
+            dir.path().join(CONFIG_FILE),
+            r#"
+repository_url = "https://github.com/heroku/buildpacks-nodejs"
+exclude = ["test/**", "heroku/nodejs-engine"]
+
+[prepare-release]
+bump = "minor"
+sync_cargo_version = true
+"#,
+        )
+        .unwrap();
+
+        let cli = parse_with_config(dir.path(), &["actions", "prepare-release"]);
+        let Command::PrepareRelease(args) = cli.command else {
+            panic!("Expected the prepare-release command");
+        };
+        assert_eq!(
+            args.repository_url,
+            "https://github.com/heroku/buildpacks-nodejs"
+        );
+        assert_eq!(args.bump, Some(BumpCoordinate::Minor));
+        assert_eq!(args.exclude.len(), 2);
+        assert!(args.sync_cargo_version);
+
+        let cli = parse_with_config(
+            dir.path(),
+            &["actions", "prepare-release", "--bump", "patch"],
+        );
+        let Command::PrepareRelease(args) = cli.command else {
+            panic!("Expected the prepare-release command");
+        };
+        assert_eq!(args.bump, Some(BumpCoordinate::Patch));
+
+        let cli = parse_with_config(
+            dir.path(),
+            &["actions", "generate-changelog", "--unreleased"],
+        );
+        let Command::GenerateChangelog(args) = cli.command else {
+            panic!("Expected the generate-changelog command");
+        };
+        assert_eq!(args.exclude.len(), 2);
+    }
+
+    #[test]
+    fn test_config_from_cargo_workspace_metadata() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("Cargo.toml"),
+            r#"
+[workspace]
+members = ["buildpacks/*"]
+
+[workspace.metadata.release]
+shared-version = true
+
+[workspace.metadata.buildpack-actions]
+repository_url = "https://github.com/heroku/buildpacks-jvm"
+"#,
+        )
+        .unwrap();
+
+        let cli = parse_with_config(
+            dir.path(),
+            &["actions", "prepare-release", "--bump", "major"],
+        );
+        let Command::PrepareRelease(args) = cli.command else {
+            panic!("Expected the prepare-release command");
+        };
+        assert_eq!(
+            args.repository_url,
+            "https://github.com/heroku/buildpacks-jvm"
+        );
+    }
+
+    #[test]
+    fn test_config_with_unknown_options() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dir.path().join(".github")).unwrap();
+
+        for (contents, expected) in [
+            ("repository-link = \"x\"", "repository-link"),
+            (
+                "[prepare-release]\nbuilders = [\"builder-22\"]",
+                "prepare-release.builders",
+            ),
+            ("[release]\nbump = \"minor\"", "release"),
+        ] {
+            std::fs::write(dir.path().join(CONFIG_FILE), contents).unwrap();
+            match read_config(dir.path())
+                .unwrap()
+                .apply(Cli::command())
+                .unwrap_err()
+            {
+                ConfigError::UnknownOption(_, key) | ConfigError::UnknownCommand(_, key) => {
+                    assert_eq!(key, expected);
+                }
+                e => panic!("Unexpected error: {e}"),
+            }
+        }
+    }
+}
@@ -0,0 +1,234 @@
+use std::path::{Component, Path, PathBuf};
+use std::process::{Command, ExitStatus};
+
+#[derive(Debug, thiserror::Error)]
+pub(crate) enum GitError {
+    #[error("Failed to execute git {0}\nError: {1}")]
+    CommandFailure(String, #[source] std::io::Error),
+    #[error("Command git {0} exited with a non-zero status\nStatus: {1}\nOutput: {2}")]
+    ExitStatus(String, ExitStatus, String),
+}
+
+// The name of the environment variable the credential helper of `push_branch` reads the token
+// from, so it never shows up in the arguments of a process.
+const PUSH_TOKEN_ENV_VAR: &str = "ACTIONS_GIT_PUSH_TOKEN";
+
+fn run_git(dir: &Path, args: &[&str]) -> Result<String, GitError> {
+    run_git_with_env(dir, args, &[])
+}
+
+fn run_git_with_env(dir: &Path, args: &[&str], env: &[(&str, &str)]) -> Result<String, GitError> {
+    let command = args.join(" ");
+    let output = Command::new("git")
+        .args(args)
+        .envs(env.iter().copied())
+        .current_dir(dir)
+        .output()
+        .map_err(|e| GitError::CommandFailure(command.clone(), e))?;
+
+    if output.status.success() {
+        Ok(String::from_utf8_lossy(&output.stdout).to_string())
+    } else {
+        Err(GitError::ExitStatus(
+            command,
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        ))
+    }
+}
+
+// Resolves a tag, branch, or commit to the commit it points at.
+pub(crate) fn resolve_revision(dir: &Path, revision: &str) -> Result<String, GitError> {
+    run_git(
+        dir,
+        &["rev-parse", "--verify", &format!("{revision}^{{commit}}")],
+    )
+    .map(|output| output.trim().to_string())
+}
+
+// Reads a file as of the given revision, where `path` is relative to `dir`. Returns `None` if
+// the file didn't exist at that revision.
+pub(crate) fn read_file_at_revision(
+    dir: &Path,
+    revision: &str,
+    path: &Path,
+) -> Result<Option<String>, GitError> {
+    let object = format!("{revision}:./{}", normalize_path(path).display());
+    if run_git(dir, &["cat-file", "-e", &object]).is_err() {
+        return Ok(None);
+    }
+    run_git(dir, &["show", &object]).map(Some)
+}
+
+// Lists the files that changed since `HEAD` branched off from the given revision, including
+// uncommitted and untracked files, as absolute paths. Changes that landed on the revision after
+// that point don't count.
+pub(crate) fn changed_files_since(dir: &Path, revision: &str) -> Result<Vec<PathBuf>, GitError> {
+    let top_level = top_level_dir(dir)?;
+    let changed = run_git(
+        dir,
+        &[
+            "diff",
+            "--name-only",
+            "--no-renames",
+            "--merge-base",
+            revision,
+        ],
+    )?;
+    let untracked = run_git(&top_level, &["ls-files", "--others", "--exclude-standard"])?;
+    Ok(changed
+        .lines()
+        .chain(untracked.lines())
+        .map(|line| top_level.join(line))
+        .collect())
+}
+
+pub(crate) fn top_level_dir(dir: &Path) -> Result<PathBuf, GitError> {
+    run_git(dir, &["rev-parse", "--show-toplevel"]).map(|output| PathBuf::from(output.trim()))
+}
+
+pub(crate) fn current_branch(dir: &Path) -> Result<String, GitError> {
+    run_git(dir, &["rev-parse", "--abbrev-ref", "HEAD"]).map(|output| output.trim().to_string())
+}
+
+// Lists annotated and lightweight tags alike, by name.
+pub(crate) fn list_tags(dir: &Path) -> Result<Vec<String>, GitError> {
+    run_git(
+        dir,
+        &["for-each-ref", "--format=%(refname:short)", "refs/tags"],
+    )
+    .map(|output| output.lines().map(ToString::to_string).collect())
+}
+
+// Creates the branch at the current commit, or resets it there if it already exists, and
+// switches to it while keeping any changes in the working tree.
+pub(crate) fn checkout_branch(dir: &Path, branch: &str) -> Result<(), GitError> {
+    run_git(dir, &["checkout", "-B", branch]).map(|_| ())
+}
+
+// Stages the given files and commits only them, anything else that was already staged stays
+// staged and out of the commit. Returns `false` without committing if none of the files had
+// changes.
+pub(crate) fn commit_files<P: AsRef<Path>>(
+    dir: &Path,
+    paths: &[P],
+    message: &str,
+) -> Result<bool, GitError> {
+    let paths = paths
+        .iter()
+        .map(|path| path.as_ref().to_string_lossy().to_string())
+        .collect::<Vec<_>>();
+
+    let mut add_args = vec!["add", "--"];
+    add_args.extend(paths.iter().map(String::as_str));
+    run_git(dir, &add_args)?;
+
+    let mut diff_args = vec!["diff", "--cached", "--quiet", "--"];
+    diff_args.extend(paths.iter().map(String::as_str));
+    if run_git(dir, &diff_args).is_ok() {
+        return Ok(false);
+    }
+
+    let mut commit_args = vec!["commit", "--only", "--message", message, "--"];
+    commit_args.extend(paths.iter().map(String::as_str));
+    run_git(dir, &commit_args).map(|_| true)
+}
+
+// Force pushes the branch, since it's recreated from the current commit on every run. When a
+// token is given the push goes straight to the repository URL with it, instead of relying on
+// credentials configured for the `origin` remote. The token is handed to git through a
+// credential helper that reads it from the environment, so it isn't in the arguments of any
+// process or in git's error output.
+pub(crate) fn push_branch(
+    dir: &Path,
+    repository_url: &str,
+    branch: &str,
+    token: Option<&str>,
+) -> Result<(), GitError> {
+    let refspec = format!("HEAD:refs/heads/{branch}");
+    match token.filter(|_| repository_url.starts_with("https://")) {
+        Some(token) => {
+            let credential_helper = format!(
+                "!f() {{ test \"$1\" = get && echo username=x-access-token && echo \"password=${PUSH_TOKEN_ENV_VAR}\"; }}; f"
+            );
+            run_git_with_env(
+                dir,
+                &["push", "--force", repository_url, &refspec],
+                &[
+                    // the empty helper clears any helpers configured for the repository
+                    ("GIT_CONFIG_COUNT", "2"),
+                    ("GIT_CONFIG_KEY_0", "credential.helper"),
+                    ("GIT_CONFIG_VALUE_0", ""),
+                    ("GIT_CONFIG_KEY_1", "credential.helper"),
+                    ("GIT_CONFIG_VALUE_1", &credential_helper),
+                    (PUSH_TOKEN_ENV_VAR, token),
+                ],
+            )
+        }
+        None => run_git(dir, &["push", "--force", "origin", &refspec]),
+    }
+    .map(|_| ())
+}
+
+// Git doesn't resolve `..` components in the middle of a `<revision>:<path>` object name (e.g.;
+// a changelog path of `buildpacks/java/../../CHANGELOG.md`) so they're resolved up front. This
+// is purely lexical, symlinks aren't followed.
+pub(crate) fn normalize_path(path: &Path) -> PathBuf {
+    path.components()
+        .fold(PathBuf::new(), |mut normalized, component| {
+            match component {
+                Component::CurDir => {}
+                Component::ParentDir if normalized.file_name().is_some() => {
+                    normalized.pop();
+                }
+                component => normalized.push(component),
+            }
+            normalized
+        })
+}
+
+#[cfg(test)]
+mod test {
+    use crate::git::{commit_files, normalize_path, run_git};
+    use std::path::{Path, PathBuf};
+
+    #[test]
+    fn test_normalize_path() {
+        assert_eq!(
+            normalize_path(Path::new("buildpacks/java/../../CHANGELOG.md")),
+            PathBuf::from("CHANGELOG.md")
+        );
+        assert_eq!(
+            normalize_path(Path::new("./buildpacks/java/CHANGELOG.md")),
+            PathBuf::from("buildpacks/java/CHANGELOG.md")
+        );
+        assert_eq!(
+            normalize_path(Path::new("../CHANGELOG.md")),
+            PathBuf::from("../CHANGELOG.md")
+        );
+    }
+
+    #[test]
+    fn test_commit_files_leaves_other_staged_changes_out() {
+        let dir = tempfile::tempdir().unwrap();
+        let dir = dir.path();
+        run_git(dir, &["init", "--quiet"]).unwrap();
+        run_git(dir, &["config", "user.name", "test"]).unwrap();
+        run_git(dir, &["config", "user.email", "test@example.com"]).unwrap();
+        std::fs::write(dir.join("CHANGELOG.md"), "## [Unreleased]\n").unwrap();
+        std::fs::write(dir.join("notes.txt"), "unrelated\n").unwrap();
+        run_git(dir, &["add", "notes.txt"]).unwrap();
+
+        assert!(commit_files(dir, &["CHANGELOG.md"], "Prepare release").unwrap());
+        assert_eq!(
+            run_git(dir, &["show", "--name-only", "--format=", "HEAD"]).unwrap(),
+            "CHANGELOG.md\n"
+        );
+        assert_eq!(
+            run_git(dir, &["diff", "--cached", "--name-only"]).unwrap(),
+            "notes.txt\n"
+        );
+
+        assert!(!commit_files(dir, &["CHANGELOG.md"], "Prepare release").unwrap());
+    }
+}
@@ -1,7 +1,20 @@
 use std::fs::OpenOptions;
 use std::io::{stdout, Write};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
 use std::{io, iter};
 
+// Set from `--outputs-dir` for local runs, where there are no `GITHUB_OUTPUT` or
+// `GITHUB_STEP_SUMMARY` files and writing everything to stdout would interleave it.
+static OUTPUTS_DIR: OnceLock<PathBuf> = OnceLock::new();
+
+pub(crate) fn set_outputs_dir(dir: &Path) -> Result<(), WriteActionDataError> {
+    std::fs::create_dir_all(dir)
+        .map_err(|e| WriteActionDataError::CreatingOutputsDir(dir.to_path_buf(), e))?;
+    let _ = OUTPUTS_DIR.set(dir.to_path_buf());
+    Ok(())
+}
+
 pub(crate) fn set_summary<M: Into<String>>(markdown: M) -> Result<(), WriteActionDataError> {
     let markdown = markdown.into();
     write_data("GITHUB_STEP_SUMMARY", format!("{markdown}\n").as_bytes())
@@ -23,25 +36,87 @@ pub(crate) fn set_output<N: Into<String>, V: Into<String>>(
     write_data("GITHUB_OUTPUT", line.as_bytes())
 }
 
+// Workflow commands written to stdout show up as annotations on the run and, for files changed by
+// a pull request, on its diff.
+pub(crate) fn annotate_error(file: &Path, title: &str, message: &str) {
+    println!("{}", error_annotation(file, title, message));
+}
+
+fn error_annotation(file: &Path, title: &str, message: &str) -> String {
+    format!(
+        "::error file={},title={}::{}",
+        escape_property(&file.display().to_string()),
+        escape_property(title),
+        escape_data(message)
+    )
+}
+
+fn escape_data(value: &str) -> String {
+    value
+        .replace('%', "%25")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A")
+}
+
+fn escape_property(value: &str) -> String {
+    escape_data(value).replace(':', "%3A").replace(',', "%2C")
+}
+
 fn write_data(env_name: &str, data: &[u8]) -> Result<(), WriteActionDataError> {
-    let mut file: Box<dyn Write> = match std::env::var(env_name) {
-        Ok(github_output) => {
+    let mut file: Box<dyn Write> = match (OUTPUTS_DIR.get(), std::env::var(env_name)) {
+        (Some(outputs_dir), _) => {
+            let append_file = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(outputs_dir.join(outputs_dir_file_name(env_name)))
+                .map_err(WriteActionDataError::Opening)?;
+            Box::new(append_file)
+        }
+        (None, Ok(github_output)) => {
             let append_file = OpenOptions::new()
                 .append(true)
                 .open(github_output)
                 .map_err(WriteActionDataError::Opening)?;
             Box::new(append_file)
         }
-        Err(_) => Box::new(stdout()),
+        (None, Err(_)) => Box::new(stdout()),
     };
 
     file.write_all(data).map_err(WriteActionDataError::Writing)
 }
 
+fn outputs_dir_file_name(env_name: &str) -> &str {
+    match env_name {
+        "GITHUB_OUTPUT" => "outputs",
+        "GITHUB_STEP_SUMMARY" => "summary.md",
+        _ => env_name,
+    }
+}
+
 #[derive(Debug, thiserror::Error)]
 pub(crate) enum WriteActionDataError {
+    #[error("Could not create outputs directory\nPath: {0}\nError: {1}")]
+    CreatingOutputsDir(PathBuf, #[source] io::Error),
     #[error("Could not open action data file\nError: {0}")]
     Opening(#[source] io::Error),
     #[error("Could not write action data file\nError: {0}")]
     Writing(#[source] io::Error),
 }
+
+#[cfg(test)]
+mod test {
+    use crate::github::actions::error_annotation;
+    use std::path::Path;
+
+    #[test]
+    fn test_error_annotation() {
+        assert_eq!(
+            error_annotation(
+                Path::new("buildpacks/nodejs/buildpack.toml"),
+                "Skipped buildpack: heroku/nodejs, v1",
+                "Could not read buildpack\nError: 100% broken"
+            ),
+            "::error file=buildpacks/nodejs/buildpack.toml,title=Skipped buildpack%3A heroku/nodejs%2C v1::Could not read buildpack%0AError: 100%25 broken"
+        );
+    }
+}
@@ -1 +1,2 @@
 pub(crate) mod actions;
+pub(crate) mod pull_requests;
@@ -0,0 +1,111 @@
+use std::process::{Command, ExitStatus};
+
+#[derive(Debug, thiserror::Error)]
+pub(crate) enum PullRequestAuthorError {
+    #[error("Failed to execute gh api for pull request {0}#{1}\nError: {2}")]
+    CommandFailure(String, u64, #[source] std::io::Error),
+    #[error("Command gh api for pull request {0}#{1} exited with a non-zero status\nStatus: {2}")]
+    ExitStatus(String, u64, ExitStatus),
+}
+
+// Looks up the login of a pull request's author with the GitHub CLI, which picks up
+// credentials from `GH_TOKEN` / `GITHUB_TOKEN`. The number is looked up with the issues API,
+// which covers pull requests too, so an issue's number (e.g.; from `Fixes #123`) returns `None`
+// instead of failing.
+pub(crate) fn pull_request_author(
+    repository: &str,
+    number: u64,
+) -> Result<Option<String>, PullRequestAuthorError> {
+    let output = Command::new("gh")
+        .args([
+            "api",
+            &format!("repos/{repository}/issues/{number}"),
+            "--jq",
+            "if .pull_request then .user.login else empty end",
+        ])
+        .output()
+        .map_err(|e| PullRequestAuthorError::CommandFailure(repository.to_owned(), number, e))?;
+
+    if output.status.success() {
+        let login = String::from_utf8_lossy(&output.stdout).trim().to_string();
+        Ok(Some(login).filter(|login| !login.is_empty()))
+    } else {
+        Err(PullRequestAuthorError::ExitStatus(
+            repository.to_owned(),
+            number,
+            output.status,
+        ))
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub(crate) enum CreatePullRequestError {
+    #[error("Failed to execute gh pr {0} for branch {1}\nError: {2}")]
+    CommandFailure(&'static str, String, #[source] std::io::Error),
+    #[error(
+        "Command gh pr {0} for branch {1} exited with a non-zero status\nStatus: {2}\nOutput: {3}"
+    )]
+    ExitStatus(&'static str, String, ExitStatus, String),
+}
+
+// Opens a pull request from `head` into `base` and returns its URL. If the branch already has
+// an open pull request, that one is returned instead since pushing to the branch updates it.
+pub(crate) fn create_pull_request(
+    repository: &str,
+    base: &str,
+    head: &str,
+    title: &str,
+    body: &str,
+) -> Result<String, CreatePullRequestError> {
+    let existing_url = run_gh_pr(
+        "list",
+        head,
+        &[
+            "--repo",
+            repository,
+            "--head",
+            head,
+            "--state",
+            "open",
+            "--json",
+            "url",
+            "--jq",
+            ".[0].url // empty",
+        ],
+    )?;
+
+    if !existing_url.is_empty() {
+        return Ok(existing_url);
+    }
+
+    run_gh_pr(
+        "create",
+        head,
+        &[
+            "--repo", repository, "--base", base, "--head", head, "--title", title, "--body", body,
+        ],
+    )
+}
+
+fn run_gh_pr(
+    subcommand: &'static str,
+    head: &str,
+    args: &[&str],
+) -> Result<String, CreatePullRequestError> {
+    let output = Command::new("gh")
+        .args(["pr", subcommand])
+        .args(args)
+        .output()
+        .map_err(|e| CreatePullRequestError::CommandFailure(subcommand, head.to_owned(), e))?;
+
+    if output.status.success() {
+        Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+    } else {
+        Err(CreatePullRequestError::ExitStatus(
+            subcommand,
+            head.to_owned(),
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        ))
+    }
+}
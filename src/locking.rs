@@ -0,0 +1,128 @@
+use crate::git::normalize_path;
+use std::collections::BTreeSet;
+use std::fs::OpenOptions;
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+// How long to wait (in seconds) for another process to release a lock before giving up.
+const LOCK_TIMEOUT_ENV_VAR: &str = "ACTIONS_LOCK_TIMEOUT";
+const DEFAULT_LOCK_TIMEOUT: Duration = Duration::from_secs(60);
+const LOCK_RETRY_INTERVAL: Duration = Duration::from_millis(100);
+
+// An advisory lock on a file, held by creating a `<file>.lock` file next to it. The lock file
+// is removed when this is dropped. Only other commands from this crate honor the lock.
+#[derive(Debug)]
+pub(crate) struct FileLock {
+    lock_path: PathBuf,
+}
+
+impl Drop for FileLock {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.lock_path);
+    }
+}
+
+// Locks are always acquired in path order so two commands locking an overlapping set of files
+// can't deadlock, and a file listed more than once (e.g.; a changelog shared by several
+// buildpacks through `../CHANGELOG.md`) is only locked once.
+pub(crate) fn lock_files<I, P>(paths: I) -> Result<Vec<FileLock>, LockFileError>
+where
+    I: IntoIterator<Item = P>,
+    P: AsRef<Path>,
+{
+    let timeout = lock_timeout()?;
+    paths
+        .into_iter()
+        .map(|path| normalize_path(path.as_ref()))
+        .collect::<BTreeSet<_>>()
+        .iter()
+        .map(|path| lock_file(path, timeout))
+        .collect()
+}
+
+fn lock_file(path: &Path, timeout: Duration) -> Result<FileLock, LockFileError> {
+    let mut lock_path = path.as_os_str().to_owned();
+    lock_path.push(".lock");
+    let lock_path = PathBuf::from(lock_path);
+
+    let started = Instant::now();
+    loop {
+        match OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&lock_path)
+        {
+            Ok(mut file) => {
+                // the pid is only recorded to help track down a stale lock
+                let _ = writeln!(file, "{}", std::process::id());
+                return Ok(FileLock { lock_path });
+            }
+            Err(e) if e.kind() == ErrorKind::AlreadyExists => {
+                if started.elapsed() >= timeout {
+                    return Err(LockFileError::Timeout(lock_path, timeout.as_secs()));
+                }
+                std::thread::sleep(LOCK_RETRY_INTERVAL);
+            }
+            Err(e) => return Err(LockFileError::Creating(lock_path, e)),
+        }
+    }
+}
+
+fn lock_timeout() -> Result<Duration, LockFileError> {
+    match std::env::var(LOCK_TIMEOUT_ENV_VAR) {
+        Ok(value) => value
+            .parse()
+            .map(Duration::from_secs)
+            .map_err(|_| LockFileError::InvalidTimeout(value)),
+        Err(_) => Ok(DEFAULT_LOCK_TIMEOUT),
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub(crate) enum LockFileError {
+    #[error("Invalid value `{0}` for {LOCK_TIMEOUT_ENV_VAR}, expected a number of seconds")]
+    InvalidTimeout(String),
+    #[error("Could not create lock file\nPath: {0}\nError: {1}")]
+    Creating(PathBuf, #[source] std::io::Error),
+    #[error("Timed out after {1}s waiting for another process to release a lock (remove the lock file if no other process is running)\nPath: {0}")]
+    Timeout(PathBuf, u64),
+}
+
+#[cfg(test)]
+mod test {
+    use crate::locking::{lock_file, lock_files, LockFileError};
+    use std::time::Duration;
+
+    #[test]
+    fn test_lock_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("CHANGELOG.md");
+        let lock_path = dir.path().join("CHANGELOG.md.lock");
+
+        let lock = lock_file(&path, Duration::ZERO).unwrap();
+        assert!(lock_path.exists());
+
+        match lock_file(&path, Duration::ZERO).unwrap_err() {
+            LockFileError::Timeout(path, _) => assert_eq!(path, lock_path),
+            e => panic!("Unexpected error: {e}"),
+        }
+
+        drop(lock);
+        assert!(!lock_path.exists());
+        assert!(lock_file(&path, Duration::ZERO).is_ok());
+    }
+
+    #[test]
+    fn test_lock_files_with_a_shared_changelog() {
+        let dir = tempfile::tempdir().unwrap();
+        let changelog_paths = [
+            dir.path().join("buildpacks/a/../../CHANGELOG.md"),
+            dir.path().join("buildpacks/b/../../CHANGELOG.md"),
+        ];
+
+        let locks = lock_files(&changelog_paths).unwrap();
+        assert_eq!(locks.len(), 1);
+        assert!(dir.path().join("CHANGELOG.md.lock").exists());
+    }
+}
@@ -1,56 +1,129 @@
+use crate::commands::check_image_labels_policy::command::CheckImageLabelsPolicyArgs;
+use crate::commands::check_orphaned_artifacts::command::CheckOrphanedArtifactsArgs;
+use crate::commands::diff_release::command::DiffReleaseArgs;
+use crate::commands::export_catalog::command::ExportCatalogArgs;
 use crate::commands::generate_buildpack_matrix::command::GenerateBuildpackMatrixArgs;
 use crate::commands::generate_changelog::command::GenerateChangelogArgs;
+use crate::commands::generate_provenance::command::GenerateProvenanceArgs;
+use crate::commands::init_buildpack::command::InitBuildpackArgs;
+use crate::commands::mirror_images::command::MirrorImagesArgs;
+use crate::commands::preflight::command::PreflightArgs;
 use crate::commands::prepare_release::command::PrepareReleaseArgs;
+use crate::commands::selftest::command::SelftestArgs;
 use crate::commands::update_builder::command::UpdateBuilderArgs;
+use crate::commands::watch::command::WatchArgs;
 use crate::commands::{
-    generate_buildpack_matrix, generate_changelog, prepare_release, update_builder,
+    check_image_labels_policy, check_orphaned_artifacts, diff_release, export_catalog,
+    generate_buildpack_matrix, generate_changelog, generate_provenance, init_buildpack,
+    mirror_images, preflight, prepare_release, selftest, update_builder, watch,
 };
-use clap::Parser;
+use clap::{CommandFactory, FromArgMatches, Parser, Subcommand};
+use std::path::PathBuf;
 
+mod builders;
 mod buildpacks;
 mod changelog;
 mod commands;
+mod concurrency;
+mod config;
+mod git;
 mod github;
+mod locking;
+mod middleware;
 
 const UNSPECIFIED_ERROR: i32 = 1;
 
 #[derive(Parser)]
 #[command(bin_name = "actions")]
-enum Cli {
+struct Cli {
+    /// Writes outputs and step summaries to files in this directory instead of the files named
+    /// by `GITHUB_OUTPUT` and `GITHUB_STEP_SUMMARY` (useful when running commands locally).
+    #[arg(long, global = true)]
+    outputs_dir: Option<PathBuf>,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    CheckImageLabelsPolicy(CheckImageLabelsPolicyArgs),
+    CheckOrphanedArtifacts(CheckOrphanedArtifactsArgs),
+    DiffRelease(DiffReleaseArgs),
+    ExportCatalog(ExportCatalogArgs),
     GenerateBuildpackMatrix(GenerateBuildpackMatrixArgs),
     GenerateChangelog(GenerateChangelogArgs),
+    GenerateProvenance(GenerateProvenanceArgs),
+    InitBuildpack(InitBuildpackArgs),
+    MirrorImages(MirrorImagesArgs),
+    Preflight(PreflightArgs),
     PrepareRelease(PrepareReleaseArgs),
+    Selftest(SelftestArgs),
     UpdateBuilder(UpdateBuilderArgs),
+    Watch(WatchArgs),
 }
 
 fn main() {
-    match Cli::parse() {
-        Cli::GenerateBuildpackMatrix(args) => {
-            if let Err(error) = generate_buildpack_matrix::execute(&args) {
-                eprintln!("❌ {error}");
-                std::process::exit(UNSPECIFIED_ERROR);
-            }
-        }
+    let command = configured_command().unwrap_or_else(|error| {
+        eprintln!("❌ {error}");
+        std::process::exit(UNSPECIFIED_ERROR);
+    });
 
-        Cli::GenerateChangelog(args) => {
-            if let Err(error) = generate_changelog::execute(args) {
-                eprintln!("❌ {error}");
-                std::process::exit(UNSPECIFIED_ERROR);
-            }
-        }
+    let matches = command.get_matches();
+    let cli = Cli::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+    let command_name = matches.subcommand_name().unwrap_or_default().to_string();
+    let arguments = std::env::args().skip(1).collect::<Vec<_>>();
 
-        Cli::PrepareRelease(args) => {
-            if let Err(error) = prepare_release::execute(args) {
-                eprintln!("❌ {error}");
-                std::process::exit(UNSPECIFIED_ERROR);
-            }
+    if let Some(outputs_dir) = &cli.outputs_dir {
+        if let Err(error) = github::actions::set_outputs_dir(outputs_dir) {
+            eprintln!("❌ {error}");
+            std::process::exit(UNSPECIFIED_ERROR);
         }
+    }
 
-        Cli::UpdateBuilder(args) => {
-            if let Err(error) = update_builder::execute(args) {
-                eprintln!("❌ {error}");
-                std::process::exit(UNSPECIFIED_ERROR);
-            }
+    let succeeded = middleware::run_command(&command_name, &arguments, || match cli.command {
+        Command::CheckImageLabelsPolicy(args) => {
+            check_image_labels_policy::execute(&args).map_err(Into::into)
+        }
+        Command::CheckOrphanedArtifacts(args) => {
+            check_orphaned_artifacts::execute(&args).map_err(Into::into)
+        }
+        Command::DiffRelease(args) => diff_release::execute(&args).map_err(Into::into),
+        Command::ExportCatalog(args) => export_catalog::execute(&args).map_err(Into::into),
+        Command::GenerateBuildpackMatrix(args) => {
+            generate_buildpack_matrix::execute(&args).map_err(Into::into)
+        }
+        Command::GenerateChangelog(args) => generate_changelog::execute(args).map_err(Into::into),
+        Command::GenerateProvenance(args) => {
+            generate_provenance::execute(&args).map_err(Into::into)
         }
+        Command::InitBuildpack(args) => init_buildpack::execute(&args).map_err(Into::into),
+        Command::MirrorImages(args) => mirror_images::execute(&args).map_err(Into::into),
+        Command::Preflight(args) => preflight::execute(&args).map_err(Into::into),
+        Command::PrepareRelease(args) => prepare_release::execute(args).map_err(Into::into),
+        Command::Selftest(args) => selftest::execute(&args).map_err(Into::into),
+        Command::UpdateBuilder(args) => update_builder::execute(&args).map_err(Into::into),
+        Command::Watch(args) => watch::execute(&args).map_err(Into::into),
+    });
+
+    if !succeeded {
+        std::process::exit(UNSPECIFIED_ERROR);
+    }
+}
+
+// The CLI with defaults for its options taken from the repository's config file, if any.
+fn configured_command() -> Result<clap::Command, Box<dyn std::error::Error>> {
+    let current_dir = std::env::current_dir()?;
+    let config = config::read_config(&current_dir)?;
+    Ok(config.apply(Cli::command())?)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::Cli;
+    use clap::CommandFactory;
+
+    #[test]
+    fn test_cli() {
+        Cli::command().debug_assert();
     }
 }